@@ -0,0 +1,55 @@
+use argon2::password_hash::rand_core::{OsRng, RngCore};
+use chrono::{DateTime, Duration, Utc};
+use jsonwebtoken::{encode, EncodingKey, Header};
+use serde::{Deserialize, Serialize};
+use crate::models::{UserId, UserRole};
+
+/// Claims carried by the access tokens issued by `POST /v1/auth/login`.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Claims {
+    /// Id of the user, as a string per RFC 7519.
+    pub sub: String,
+    /// Role of the user when the token was issued.
+    pub role: UserRole,
+    /// Seconds since the epoch at which the token was issued.
+    pub iat: i64,
+    /// Seconds since the epoch after which the token is rejected.
+    pub exp: i64,
+}
+
+/// Signs HS256 access tokens with the secret from `JWT_SECRET`.
+#[derive(Clone)]
+pub struct Jwt {
+    encoding: EncodingKey,
+    ttl: Duration,
+}
+
+impl Jwt {
+    /// Tokens signed with `secret` that stay valid for `ttl_secs` seconds.
+    pub fn new(secret: &[u8], ttl_secs: u64) -> Self {
+        Jwt {
+            encoding: EncodingKey::from_secret(secret),
+            ttl: Duration::seconds(ttl_secs as i64),
+        }
+    }
+
+    /// Tokens signed with a random secret, for when none is configured.
+    pub fn with_random_secret(ttl_secs: u64) -> Self {
+        let mut secret = [0u8; 32];
+        OsRng.fill_bytes(&mut secret);
+        Jwt::new(&secret, ttl_secs)
+    }
+
+    /// Issue a token for the user, returning it with the time it expires.
+    pub fn issue(&self, id: UserId, role: UserRole, now: DateTime<Utc>) -> jsonwebtoken::errors::Result<(String, DateTime<Utc>)> {
+        let expires_at = now + self.ttl;
+        let claims = Claims {
+            sub: id.to_string(),
+            role,
+            iat: now.timestamp(),
+            exp: expires_at.timestamp(),
+        };
+        let token = encode(&Header::default(), &claims, &self.encoding)?;
+        Ok((token, expires_at))
+    }
+}
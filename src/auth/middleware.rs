@@ -1,141 +1,160 @@
-/*use crate::util::ErrorResponse;
-use crate::{API_KEY, API_KEY_NAME};
-use actix_web::dev::{Service, ServiceRequest, ServiceResponse, Transform};
-use actix_web::HttpResponse;
+use std::future::{ready, Ready};
+use std::rc::Rc;
+use actix_web::body::{EitherBody, MessageBody};
+use actix_web::dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform};
+use actix_web::http::header::HeaderName;
+use actix_web::{Error, HttpResponse};
 use futures::future::LocalBoxFuture;
-use log::info;
-use std::future;
-use std::future::Ready;
+use log::debug;
 use crate::utils::ErrorResponse;
+use super::constant_time_eq;
+
+/// Header and value clients authenticate with, and the paths reachable without them.
+#[derive(Clone)]
+pub struct ApiKey {
+    header: HeaderName,
+    key: Rc<str>,
+    public_paths: Rc<[String]>,
+}
+
+impl ApiKey {
+    pub fn new(header: HeaderName, key: &str) -> Self {
+        ApiKey { header, key: Rc::from(key), public_paths: Rc::from([]) }
+    }
+
+    /// Let requests to exactly these paths through without a key, e.g. so probes need no credentials.
+    pub fn with_public_paths(self, paths: impl IntoIterator<Item = String>) -> Self {
+        ApiKey { public_paths: paths.into_iter().collect(), ..self }
+    }
+}
 
-/// Require api key middleware will actually require valid api key
-pub struct RequireApiKey;
+/// Reject requests without the API key with `401 Unauthorized`.
+pub struct RequireApiKey(pub ApiKey);
 
-impl<S> Transform<S, ServiceRequest> for RequireApiKey
+impl<S, B> Transform<S, ServiceRequest> for RequireApiKey
 where
-    S: Service<
-        ServiceRequest,
-        Response = ServiceResponse<actix_web::body::BoxBody>,
-        Error = actix_web::Error,
-    >,
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
     S::Future: 'static,
+    B: MessageBody + 'static,
 {
-    type Response = ServiceResponse<actix_web::body::BoxBody>;
-    type Error = actix_web::Error;
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
     type Transform = ApiKeyMiddleware<S>;
     type InitError = ();
     type Future = Ready<Result<Self::Transform, Self::InitError>>;
 
     fn new_transform(&self, service: S) -> Self::Future {
-        future::ready(Ok(ApiKeyMiddleware {
+        ready(Ok(ApiKeyMiddleware {
             service,
+            api_key: self.0.clone(),
             log_only: false,
         }))
     }
 }
 
-/// Log api key middleware only logs about missing or invalid api keys
-pub struct LogApiKey;
+/// Only log requests with a missing or wrong API key and let them through, e.g. while clients are being given keys.
+pub struct LogApiKey(pub ApiKey);
 
-impl<S> Transform<S, ServiceRequest> for LogApiKey
+impl<S, B> Transform<S, ServiceRequest> for LogApiKey
 where
-    S: Service<
-        ServiceRequest,
-        Response = ServiceResponse<actix_web::body::BoxBody>,
-        Error = actix_web::Error,
-    >,
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
     S::Future: 'static,
+    B: MessageBody + 'static,
 {
-    type Response = ServiceResponse<actix_web::body::BoxBody>;
-    type Error = actix_web::Error;
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
     type Transform = ApiKeyMiddleware<S>;
     type InitError = ();
     type Future = Ready<Result<Self::Transform, Self::InitError>>;
 
     fn new_transform(&self, service: S) -> Self::Future {
-        future::ready(Ok(ApiKeyMiddleware {
+        ready(Ok(ApiKeyMiddleware {
             service,
+            api_key: self.0.clone(),
             log_only: true,
         }))
     }
 }
 
 pub struct ApiKeyMiddleware<S> {
-    pub(crate) service: S,
-    pub(crate) log_only: bool,
+    service: S,
+    api_key: ApiKey,
+    log_only: bool,
 }
 
-impl<S> Service<ServiceRequest> for ApiKeyMiddleware<S>
+impl<S, B> Service<ServiceRequest> for ApiKeyMiddleware<S>
 where
-    S: Service<
-        ServiceRequest,
-        Response = ServiceResponse<actix_web::body::BoxBody>,
-        Error = actix_web::Error,
-    >,
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
     S::Future: 'static,
+    B: MessageBody + 'static,
 {
-    type Response = ServiceResponse<actix_web::body::BoxBody>;
-    type Error = actix_web::Error;
-    type Future = LocalBoxFuture<'static, Result<Self::Response, actix_web::Error>>;
-
-    fn poll_ready(
-        &self,
-        ctx: &mut core::task::Context<'_>,
-    ) -> std::task::Poll<Result<(), Self::Error>> {
-        self.service.poll_ready(ctx)
-    }
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
 
-    fn call(&self, req: ServiceRequest) -> Self::Future {
-        let response = |req: ServiceRequest, response: HttpResponse| -> Self::Future {
-            Box::pin(async { Ok(req.into_response(response)) })
-        };
+    forward_ready!(service);
 
-        // Log the API key provided
-        if let Some(key) = req.headers().get(API_KEY_NAME) {
-            log::debug!("Received API key: {:?}", key.to_str());
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let path = req.path();
+        let rejection = if self.api_key.public_paths.iter().any(|public| public == path) {
+            None
         } else {
-            log::info!("API key missing in request");
-        }
+            match req.headers().get(&self.api_key.header) {
+                Some(key) if constant_time_eq(key.as_bytes(), self.api_key.key.as_bytes()) => None,
+                Some(_) => Some("Incorrect API key"),
+                None => Some("Missing API key"),
+            }
+        };
 
-        // MATCH HERE AGAINST DIFFERENT API KEYS
-        match req.headers().get(API_KEY_NAME) {
-            Some(key) if key.to_str().unwrap_or("") != API_KEY => {
-                if self.log_only {
-                    log::debug!("Incorrect API Key Provided!")
-                } else {
-                    return response(
-                        req,
-                        HttpResponse::Unauthorized().json(ErrorResponse::Unauthorized(
-                            String::from("Incorrect API Key!"),
-                        )),
-                    );
-                }
+        match rejection {
+            Some(message) if !self.log_only => {
+                let response = HttpResponse::Unauthorized().json(ErrorResponse::Unauthorized(message.to_string()));
+                Box::pin(async move { Ok(req.into_response(response).map_into_right_body()) })
             }
-            None => {
-                if self.log_only {
-                    log::debug!("Missing api key!!!")
-                } else {
-                    return response(
-                        req,
-                        HttpResponse::Unauthorized().json(ErrorResponse::Unauthorized(
-                            String::from("Missing API Key!"),
-                        )),
-                    );
+            _ => {
+                if let Some(message) = rejection {
+                    debug!("{} for {} {}", message, req.method(), req.path());
                 }
+                let response = self.service.call(req);
+                Box::pin(async move { response.await.map(ServiceResponse::map_into_left_body) })
             }
-            _ => (), // just passthrough
         }
+    }
+}
 
-        if self.log_only {
-            log::debug!("Performing operation")
+#[cfg(test)]
+mod tests {
+    use actix_web::{test, web, App, HttpResponse};
+    use actix_web::http::header::HeaderName;
+    use actix_web::http::StatusCode;
+    use super::{ApiKey, RequireApiKey};
+
+    #[actix_web::test]
+    async fn requires_the_key_except_on_public_paths() {
+        let api_key = ApiKey::new(HeaderName::from_static("x-api-key"), "secret")
+            .with_public_paths(["/v1/health".to_string()]);
+        let app = test::init_service(
+            App::new().service(
+                web::scope("/v1")
+                    .wrap(RequireApiKey(api_key))
+                    .route("/health", web::get().to(HttpResponse::Ok))
+                    .route("/jobs/health", web::get().to(HttpResponse::Ok)),
+            ),
+        )
+        .await;
+
+        for (path, key, status) in [
+            ("/v1/jobs/health", None, StatusCode::UNAUTHORIZED),
+            ("/v1/jobs/health", Some("wrong"), StatusCode::UNAUTHORIZED),
+            ("/v1/jobs/health", Some("secret"), StatusCode::OK),
+            ("/v1/health", None, StatusCode::OK),
+        ] {
+            let mut request = test::TestRequest::get().uri(path);
+            if let Some(key) = key {
+                request = request.insert_header(("x-api-key", key));
+            }
+            let response = test::call_service(&app, request.to_request()).await;
+            assert_eq!(response.status(), status, "{} with key {:?}", path, key);
         }
-
-        let future = self.service.call(req);
-
-        Box::pin(async move {
-            let response = future.await?;
-
-            Ok(response)
-        })
     }
-}*/
\ No newline at end of file
+}
@@ -1 +1,8 @@
-mod middleware;
\ No newline at end of file
+pub mod jwt;
+pub mod middleware;
+pub mod password;
+
+/// Compare without returning early, so response times do not reveal how much of a secret was right.
+pub(crate) fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
+}
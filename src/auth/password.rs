@@ -0,0 +1,27 @@
+use argon2::password_hash::rand_core::OsRng;
+use argon2::password_hash::{self, PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::Argon2;
+
+/// Stored in place of a password that no one can sign in with, e.g. for users restored from a backup.
+pub const UNUSABLE: &str = "!";
+
+/// Hash `password` with Argon2id and a random salt, returning the PHC string to store.
+pub fn hash(password: &str) -> Result<String, password_hash::Error> {
+    let salt = SaltString::generate(&mut OsRng);
+    Ok(Argon2::default().hash_password(password.as_bytes(), &salt)?.to_string())
+}
+
+/// Whether `password` matches the `stored` password hash.
+///
+/// Anything stored that is not a PHC hash string, such as `UNUSABLE`, matches no password.
+pub fn verify(password: &str, stored: &str) -> bool {
+    match PasswordHash::new(stored) {
+        Ok(hash) => Argon2::default().verify_password(password.as_bytes(), &hash).is_ok(),
+        Err(_) => false,
+    }
+}
+
+/// Whether `stored` is a PHC hash string rather than a password as it was sent.
+pub fn is_hash(stored: &str) -> bool {
+    PasswordHash::new(stored).is_ok()
+}
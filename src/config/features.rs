@@ -0,0 +1,105 @@
+use std::collections::BTreeMap;
+use std::env;
+use log::warn;
+use serde::Serialize;
+use utoipa::ToSchema;
+
+/// A behavior that can be rolled out gradually by switching it on or off per deployment.
+///
+/// Each feature is read from `FEATURE_<NAME>`, accepting `on`/`off`, `true`/`false` or `1`/`0`, and falls back to its
+/// default when unset or invalid.
+#[derive(Serialize, ToSchema, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum Feature {
+    /// `GET /v1/users/{id}/recommended-jobs`; returns 404 when off.
+    JobRecommendations,
+    /// Converting response timestamps with `?tz=`; the parameter is ignored when off.
+    TimezoneConversion,
+}
+
+impl Feature {
+    pub const ALL: &'static [Feature] = &[Feature::JobRecommendations, Feature::TimezoneConversion];
+
+    /// Environment variable the feature is read from.
+    pub fn env_var(self) -> &'static str {
+        match self {
+            Feature::JobRecommendations => "FEATURE_JOB_RECOMMENDATIONS",
+            Feature::TimezoneConversion => "FEATURE_TIMEZONE_CONVERSION",
+        }
+    }
+
+    /// Whether the feature is on when its environment variable is unset.
+    pub fn default_enabled(self) -> bool {
+        match self {
+            Feature::JobRecommendations => true,
+            Feature::TimezoneConversion => true,
+        }
+    }
+}
+
+/// State of a feature flag, as reported by `GET /v1/admin/features`.
+#[derive(Serialize, ToSchema, Clone, Debug)]
+pub struct FeatureFlag {
+    /// Name of the feature.
+    #[schema(example = "job_recommendations")]
+    pub feature: Feature,
+    /// Whether the feature is on.
+    #[schema(example = true)]
+    pub enabled: bool,
+    /// Whether the feature is on when its environment variable is unset.
+    #[schema(example = true)]
+    pub default: bool,
+    /// Environment variable the feature is read from.
+    #[schema(example = "FEATURE_JOB_RECOMMENDATIONS")]
+    pub env_var: String,
+}
+
+/// Feature flag states resolved from the environment at startup.
+#[derive(Clone, Debug)]
+pub struct FeatureFlags {
+    enabled: BTreeMap<Feature, bool>,
+}
+
+impl FeatureFlags {
+    pub fn from_env() -> Self {
+        let enabled = Feature::ALL
+            .iter()
+            .map(|&feature| {
+                let enabled = match env::var(feature.env_var()) {
+                    Ok(value) => parse_flag(&value).unwrap_or_else(|| {
+                        warn!("Ignoring {}={:?}, it must be on or off", feature.env_var(), value);
+                        feature.default_enabled()
+                    }),
+                    Err(_) => feature.default_enabled(),
+                };
+                (feature, enabled)
+            })
+            .collect();
+        FeatureFlags { enabled }
+    }
+
+    pub fn is_enabled(&self, feature: Feature) -> bool {
+        self.enabled.get(&feature).copied().unwrap_or_else(|| feature.default_enabled())
+    }
+
+    /// State of every feature, in declaration order.
+    pub fn flags(&self) -> Vec<FeatureFlag> {
+        Feature::ALL
+            .iter()
+            .map(|&feature| FeatureFlag {
+                feature,
+                enabled: self.is_enabled(feature),
+                default: feature.default_enabled(),
+                env_var: feature.env_var().to_string(),
+            })
+            .collect()
+    }
+}
+
+fn parse_flag(value: &str) -> Option<bool> {
+    match value.trim().to_ascii_lowercase().as_str() {
+        "on" | "true" | "1" => Some(true),
+        "off" | "false" | "0" => Some(false),
+        _ => None,
+    }
+}
@@ -0,0 +1,429 @@
+use std::env;
+use std::str::FromStr;
+use actix_web::http::header::HeaderName;
+use actix_web::http::Method;
+use log::{info, warn};
+use serde::{Serialize, Serializer};
+use utoipa::ToSchema;
+use crate::config::features::FeatureFlags;
+use crate::utils::list::{DEFAULT_LIMIT, MAX_LIMIT};
+
+pub mod features;
+
+/// Placeholder written in place of secret values when settings are serialized.
+pub const REDACTED: &str = "********";
+
+/// Application settings resolved from the environment.
+///
+/// Secret values are redacted when serialized, so `Settings` can be returned or logged as-is.
+#[derive(Serialize, ToSchema, Clone, Debug)]
+pub struct Settings {
+    /// Address the HTTP server binds to.
+    #[schema(example = "0.0.0.0")]
+    pub host: String,
+    /// Port the HTTP server listens on.
+    #[schema(example = 8080)]
+    pub port: u16,
+    /// Number of HTTP worker threads, or the number of CPUs when unset.
+    #[schema(example = 4)]
+    pub server_workers: Option<usize>,
+    /// Path prefix the API is mounted under, e.g. `/api` behind a reverse proxy. Empty by default.
+    #[schema(example = "/api")]
+    pub api_base_path: String,
+    /// Path of the SQLite database file.
+    #[schema(example = "/data/backend.db")]
+    pub database_url: String,
+    /// Path of a read-only replica of the database that read-only endpoints query instead, if any.
+    #[schema(example = "/replica/backend.db")]
+    pub read_database_url: Option<String>,
+    /// Most database connections kept open and shared by request handlers, per pool.
+    #[schema(example = 10)]
+    pub db_pool_size: u32,
+    /// Seconds a request waits for a free database connection before failing with `500`.
+    #[schema(example = 5)]
+    pub db_pool_timeout_secs: u64,
+    /// Whether an empty database is seeded with an administrator and sample records at startup.
+    #[schema(example = false)]
+    pub seed_on_empty: bool,
+    /// Seconds browsers may cache a CORS preflight response.
+    #[schema(example = 3600)]
+    pub cors_max_age: usize,
+    /// HTTP methods allowed in cross-origin requests.
+    #[schema(example = json!(["GET", "POST", "PUT", "PATCH", "DELETE", "OPTIONS"]))]
+    pub cors_allowed_methods: Vec<String>,
+    /// Request headers allowed in cross-origin requests.
+    #[schema(example = json!(["Accept", "Content-Type", "Authorization"]))]
+    pub cors_allowed_headers: Vec<String>,
+    /// Key clients must send to call the API. Requests are not authenticated when unset.
+    #[serde(serialize_with = "redact")]
+    #[schema(value_type = Option<String>, example = "********")]
+    pub api_key: Option<String>,
+    /// Request header carrying the API key.
+    #[schema(example = "Authorization")]
+    pub api_key_name: String,
+    /// Whether requests with a missing or wrong API key are only logged instead of rejected.
+    #[schema(example = false)]
+    pub api_key_log_only: bool,
+    /// Secret access tokens are signed with. A random one is used when unset, so tokens do not survive a restart.
+    #[serde(serialize_with = "redact")]
+    #[schema(value_type = Option<String>, example = "********")]
+    pub jwt_secret: Option<String>,
+    /// Seconds an access token stays valid after it is issued.
+    #[schema(example = 3600)]
+    pub jwt_ttl_secs: u64,
+    /// Seconds to wait for background tasks to finish during shutdown.
+    #[schema(example = 30)]
+    pub shutdown_timeout_secs: u64,
+    /// Most side effects, such as emails, that can wait in the work queue.
+    #[schema(example = 1024)]
+    pub work_queue_capacity: usize,
+    /// Whether enqueueing onto a full work queue waits for room instead of dropping the side effect.
+    #[schema(example = false)]
+    pub work_queue_block_when_full: bool,
+    /// Whether the API starts in maintenance mode, rejecting writes until it is turned off.
+    #[schema(example = false)]
+    pub maintenance_mode: bool,
+    /// Whether plain HTTP requests are redirected to HTTPS and HSTS headers are sent.
+    #[schema(example = false)]
+    pub force_https: bool,
+    /// Longest query string, in bytes, accepted before a request is rejected with `414`.
+    #[schema(example = 2048)]
+    pub max_query_length: usize,
+    /// Smallest response body, in bytes, that is compressed for clients accepting compression.
+    #[schema(example = 1024)]
+    pub compress_min_bytes: u64,
+    /// Hours after posting during which a job is flagged as new.
+    #[schema(example = 72)]
+    pub new_job_window_hours: i64,
+    /// Longest job title, in characters, accepted when a job is created.
+    #[schema(example = 200)]
+    pub max_job_title_length: usize,
+    /// Longest job description, in characters, accepted when a job is created.
+    #[schema(example = 10000)]
+    pub max_job_description_length: usize,
+    /// Whether list endpoints fail on rows that cannot be decoded instead of skipping them.
+    #[schema(example = true)]
+    pub strict_row_decoding: bool,
+    /// Seconds between two passes rejecting pending applications past their job's `auto_reject_after`.
+    #[schema(example = 300)]
+    pub auto_reject_interval_secs: u64,
+    /// Whether employers are sent a periodic digest of new applications.
+    #[schema(example = false)]
+    pub digest_enabled: bool,
+    /// Seconds between two application digests.
+    #[schema(example = 86400)]
+    pub digest_interval_secs: u64,
+}
+
+impl Settings {
+    /// Load the settings from environment variables, falling back to defaults where possible.
+    pub fn from_env() -> Self {
+        Settings {
+            host: env::var("HOST").unwrap_or_else(|_| "0.0.0.0".to_string()),
+            port: env::var("PORT")
+                .ok()
+                .and_then(|port| port.parse().ok())
+                .unwrap_or(8080),
+            server_workers: env::var("SERVER_WORKERS").ok().and_then(|value| {
+                let workers = parse_workers(&value);
+                if workers.is_none() {
+                    warn!("Ignoring SERVER_WORKERS={:?}, it must be a positive integer", value);
+                }
+                workers
+            }),
+            api_base_path: normalize_base_path(&env::var("API_BASE_PATH").unwrap_or_default()),
+            database_url: env::var("DATABASE_URL").expect("DATABASE_URL must be set"),
+            read_database_url: env::var("READ_DATABASE_URL").ok().filter(|url| !url.trim().is_empty()),
+            db_pool_size: env::var("DB_POOL_SIZE")
+                .ok()
+                .and_then(|size| size.parse().ok())
+                .filter(|size| *size > 0)
+                .unwrap_or(10),
+            db_pool_timeout_secs: env::var("DB_POOL_TIMEOUT_SECS")
+                .ok()
+                .and_then(|secs| secs.parse().ok())
+                .filter(|secs| *secs > 0)
+                .unwrap_or(5),
+            seed_on_empty: env::var("SEED_ON_EMPTY")
+                .is_ok_and(|value| value == "1" || value.eq_ignore_ascii_case("true")),
+            cors_max_age: env::var("CORS_MAX_AGE")
+                .ok()
+                .and_then(|value| {
+                    let secs = parse_max_age(&value);
+                    if secs.is_none() {
+                        warn!("Ignoring CORS_MAX_AGE={:?}, it must be a non-negative integer", value);
+                    }
+                    secs
+                })
+                .unwrap_or(3600),
+            cors_allowed_methods: env::var("CORS_ALLOWED_METHODS")
+                .map(|value| parse_list("CORS_ALLOWED_METHODS", &value, |method| Method::from_bytes(method.as_bytes()).is_ok()))
+                .unwrap_or_else(|_| {
+                    ["GET", "POST", "PUT", "PATCH", "DELETE", "OPTIONS"].map(String::from).to_vec()
+                }),
+            cors_allowed_headers: env::var("CORS_ALLOWED_HEADERS")
+                .map(|value| parse_list("CORS_ALLOWED_HEADERS", &value, |header| HeaderName::from_str(header).is_ok()))
+                .unwrap_or_else(|_| ["Accept", "Content-Type", "Authorization"].map(String::from).to_vec()),
+            api_key: env::var("API_KEY").ok().filter(|key| !key.is_empty()),
+            api_key_name: env::var("API_KEY_NAME")
+                .ok()
+                .filter(|name| {
+                    let valid = HeaderName::from_str(name).is_ok();
+                    if !valid {
+                        warn!("Ignoring invalid API_KEY_NAME {:?}", name);
+                    }
+                    valid
+                })
+                .unwrap_or_else(|| "Authorization".to_string()),
+            api_key_log_only: env::var("API_KEY_LOG_ONLY")
+                .is_ok_and(|value| value == "1" || value.eq_ignore_ascii_case("true")),
+            jwt_secret: env::var("JWT_SECRET").ok().filter(|secret| !secret.is_empty()),
+            jwt_ttl_secs: env::var("JWT_TTL_SECS")
+                .ok()
+                .and_then(|secs| secs.parse().ok())
+                .filter(|secs| *secs > 0)
+                .unwrap_or(3600),
+            shutdown_timeout_secs: env::var("SHUTDOWN_TIMEOUT_SECS")
+                .ok()
+                .and_then(|secs| secs.parse().ok())
+                .unwrap_or(30),
+            work_queue_capacity: env::var("WORK_QUEUE_CAPACITY")
+                .ok()
+                .and_then(|capacity| capacity.parse().ok())
+                .filter(|capacity| *capacity > 0)
+                .unwrap_or(1024),
+            work_queue_block_when_full: env::var("WORK_QUEUE_BLOCK_WHEN_FULL")
+                .is_ok_and(|value| value == "1" || value.eq_ignore_ascii_case("true")),
+            maintenance_mode: env::var("MAINTENANCE_MODE")
+                .is_ok_and(|value| value == "1" || value.eq_ignore_ascii_case("true")),
+            force_https: env::var("FORCE_HTTPS")
+                .is_ok_and(|value| value == "1" || value.eq_ignore_ascii_case("true")),
+            max_query_length: env::var("MAX_QUERY_LENGTH")
+                .ok()
+                .and_then(|length| length.parse().ok())
+                .unwrap_or(2048),
+            compress_min_bytes: env::var("COMPRESS_MIN_BYTES")
+                .ok()
+                .and_then(|bytes| bytes.parse().ok())
+                .unwrap_or(1024),
+            new_job_window_hours: new_job_window_hours(),
+            max_job_title_length: max_job_title_length(),
+            max_job_description_length: max_job_description_length(),
+            strict_row_decoding: strict_row_decoding(),
+            auto_reject_interval_secs: env::var("AUTO_REJECT_INTERVAL_SECS")
+                .ok()
+                .and_then(|secs| secs.parse().ok())
+                .filter(|secs| *secs > 0)
+                .unwrap_or(300),
+            digest_enabled: env::var("DIGEST_ENABLED")
+                .is_ok_and(|value| value == "1" || value.eq_ignore_ascii_case("true")),
+            digest_interval_secs: env::var("DIGEST_INTERVAL_SECS")
+                .ok()
+                .and_then(|secs| secs.parse().ok())
+                .filter(|secs| *secs > 0)
+                .unwrap_or(86400),
+        }
+    }
+
+    /// Full path of `path` under the versioned API, e.g. `/api/v1/jobs` for `/jobs` with `API_BASE_PATH=/api`.
+    pub fn api_path(&self, path: &str) -> String {
+        format!("{}/v1{}", self.api_base_path, path)
+    }
+}
+
+/// Split a comma separated list, dropping blank entries and logging entries rejected by `is_valid`.
+fn parse_list(name: &str, value: &str, is_valid: impl Fn(&str) -> bool) -> Vec<String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .filter(|item| {
+            let valid = is_valid(item);
+            if !valid {
+                warn!("Ignoring invalid {} entry {:?}", name, item);
+            }
+            valid
+        })
+        .map(String::from)
+        .collect()
+}
+
+/// Parse a CORS max-age, which must be a non-negative number of seconds.
+fn parse_max_age(value: &str) -> Option<usize> {
+    value.trim().parse().ok()
+}
+
+/// Parse a worker count, which must be a positive integer.
+fn parse_workers(value: &str) -> Option<usize> {
+    value.trim().parse().ok().filter(|workers| *workers > 0)
+}
+
+/// Turn a base path such as `api/` into `/api`, keeping an empty path empty.
+fn normalize_base_path(path: &str) -> String {
+    let path = path.trim().trim_matches('/');
+    if path.is_empty() {
+        String::new()
+    } else {
+        format!("/{}", path)
+    }
+}
+
+/// Hours after posting during which a job is flagged as new, read from `NEW_JOB_WINDOW_HOURS`.
+pub fn new_job_window_hours() -> i64 {
+    env::var("NEW_JOB_WINDOW_HOURS")
+        .ok()
+        .and_then(|hours| hours.parse().ok())
+        .unwrap_or(72)
+}
+
+/// Longest job title in characters, read from `MAX_JOB_TITLE_LENGTH`.
+pub fn max_job_title_length() -> usize {
+    env::var("MAX_JOB_TITLE_LENGTH")
+        .ok()
+        .and_then(|length| length.parse().ok())
+        .filter(|length| *length > 0)
+        .unwrap_or(200)
+}
+
+/// Longest job description in characters, read from `MAX_JOB_DESCRIPTION_LENGTH`.
+pub fn max_job_description_length() -> usize {
+    env::var("MAX_JOB_DESCRIPTION_LENGTH")
+        .ok()
+        .and_then(|length| length.parse().ok())
+        .filter(|length| *length > 0)
+        .unwrap_or(10000)
+}
+
+/// Whether list endpoints fail on malformed rows, read from `STRICT_ROW_DECODING`. On unless set to `0` or `false`.
+pub fn strict_row_decoding() -> bool {
+    env::var("STRICT_ROW_DECODING").map_or(true, |value| value != "0" && !value.eq_ignore_ascii_case("false"))
+}
+
+/// Log the resolved settings, feature flags and page sizes at info level as a single JSON line, secrets redacted.
+pub fn log_effective(settings: &Settings, features: &FeatureFlags) {
+    let mut config = serde_json::to_value(settings).unwrap_or_default();
+    if let Some(config) = config.as_object_mut() {
+        let features: serde_json::Map<_, _> = features
+            .flags()
+            .into_iter()
+            .map(|flag| (flag.env_var, flag.enabled.into()))
+            .collect();
+        config.insert("features".to_string(), features.into());
+        config.insert("default_page_size".to_string(), DEFAULT_LIMIT.into());
+        config.insert("max_page_size".to_string(), MAX_LIMIT.into());
+    }
+    info!("Effective configuration: {}", config);
+}
+
+fn redact<S: Serializer>(value: &Option<String>, serializer: S) -> Result<S::Ok, S::Error> {
+    match value {
+        Some(_) => serializer.serialize_some(REDACTED),
+        None => serializer.serialize_none(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+    use log::{Level, LevelFilter, Log, Metadata, Record};
+    use actix_cors::Cors;
+    use actix_web::{test, web, App, HttpResponse};
+    use actix_web::http::header::{ACCESS_CONTROL_MAX_AGE, ACCESS_CONTROL_REQUEST_METHOD, ORIGIN};
+    use actix_web::http::{Method, StatusCode};
+    use super::features::FeatureFlags;
+    use super::{log_effective, normalize_base_path, parse_list, parse_max_age, Settings};
+
+    fn settings() -> Settings {
+        if std::env::var("DATABASE_URL").is_err() {
+            std::env::set_var("DATABASE_URL", "unused.db");
+        }
+        Settings::from_env()
+    }
+
+    /// Keeps every info line so a test can look at what was logged.
+    struct CaptureLogger(Mutex<Vec<String>>);
+
+    impl Log for CaptureLogger {
+        fn enabled(&self, metadata: &Metadata) -> bool {
+            metadata.level() <= Level::Info
+        }
+
+        fn log(&self, record: &Record) {
+            if self.enabled(record.metadata()) {
+                self.0.lock().unwrap().push(record.args().to_string());
+            }
+        }
+
+        fn flush(&self) {}
+    }
+
+    static LOGGER: CaptureLogger = CaptureLogger(Mutex::new(Vec::new()));
+
+    #[actix_web::test]
+    async fn effective_configuration_is_logged_without_secrets() {
+        log::set_logger(&LOGGER).unwrap();
+        log::set_max_level(LevelFilter::Info);
+        let mut settings = settings();
+        settings.port = 4711;
+        settings.api_key = Some("not-the-api-key".to_string());
+        settings.jwt_secret = Some("not-the-jwt-secret".to_string());
+
+        log_effective(&settings, &FeatureFlags::from_env());
+
+        let lines = LOGGER.0.lock().unwrap();
+        let line = lines.iter().find(|line| line.starts_with("Effective configuration: ")).unwrap();
+        assert!(line.contains("\"port\":4711"), "{}", line);
+        assert!(!line.contains("not-the-jwt-secret"), "{}", line);
+        assert!(!line.contains("not-the-api-key"), "{}", line);
+    }
+
+    #[actix_web::test]
+    async fn routes_respond_under_the_base_path() {
+        for path in ["api", "/api/", " /api"] {
+            assert_eq!(normalize_base_path(path), "/api");
+        }
+        assert_eq!(normalize_base_path("/"), "");
+
+        let mut settings = settings();
+        settings.api_base_path = normalize_base_path("api/");
+        let app = test::init_service(
+            App::new().service(web::scope(&settings.api_path("")).route("/health", web::get().to(HttpResponse::Ok))),
+        )
+        .await;
+
+        let prefixed = test::TestRequest::get().uri(&settings.api_path("/health")).to_request();
+        assert_eq!(test::call_service(&app, prefixed).await.status(), StatusCode::OK);
+        let bare = test::TestRequest::get().uri("/v1/health").to_request();
+        assert_eq!(test::call_service(&app, bare).await.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[actix_web::test]
+    async fn cors_settings_are_parsed_and_applied_to_preflights() {
+        assert_eq!(parse_max_age(" 600 "), Some(600));
+        assert_eq!(parse_max_age("0"), Some(0));
+        assert_eq!(parse_max_age("-1"), None);
+        assert_eq!(parse_max_age("an hour"), None);
+        let methods = parse_list("CORS_ALLOWED_METHODS", "GET, ,POST,NOT A METHOD", |method| {
+            Method::from_bytes(method.as_bytes()).is_ok()
+        });
+        assert_eq!(methods, ["GET", "POST"]);
+
+        let mut settings = settings();
+        settings.cors_max_age = 600;
+        let app = test::init_service(
+            App::new()
+                .wrap(Cors::default().allow_any_origin().allowed_methods(["GET"]).max_age(settings.cors_max_age))
+                .route("/jobs", web::get().to(HttpResponse::Ok)),
+        )
+        .await;
+        let preflight = test::TestRequest::default()
+            .method(Method::OPTIONS)
+            .uri("/jobs")
+            .insert_header((ORIGIN, "https://example.com"))
+            .insert_header((ACCESS_CONTROL_REQUEST_METHOD, "GET"))
+            .to_request();
+        let response = test::call_service(&app, preflight).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers().get(ACCESS_CONTROL_MAX_AGE).unwrap(), "600");
+    }
+}
@@ -1,43 +1,66 @@
-use crate::models::{Application, ApplicationStatus};
+use crate::db::{attachment, collect_rows, like_pattern};
+use crate::utils::list::Sort;
+use crate::models::backup::ApplicationStatusChange;
+use crate::models::{Application, ApplicationId, ApplicationStatus, JobId, UserId};
 use log::{debug, error};
-use rusqlite::{params, Connection};
+use rusqlite::{params, params_from_iter, Connection, OptionalExtension, Row};
+use rusqlite::types::Value;
 use std::error::Error;
-use actix_web::web::Json;
 use chrono::{DateTime, Utc};
-use crate::models::application::ApplicationUpdateRequest;
 
 pub fn get_all(
     conn: &mut Connection,
     limit: i64,
     offset: i64,
 ) -> Result<Vec<Application>, Box<dyn Error>> {
+    Ok(get_page(conn, limit, offset, true)?.0)
+}
+
+/// A page of applications, along with the number of rows skipped because they could not be decoded.
+///
+/// Malformed rows fail the whole page when `strict` is set and are skipped otherwise.
+pub fn get_page(
+    conn: &mut Connection,
+    limit: i64,
+    offset: i64,
+    strict: bool,
+) -> Result<(Vec<Application>, usize), Box<dyn Error>> {
     let mut stmt = conn.prepare(
-        "SELECT id, job_seeker_id, job_id, cover_letter, resume, status, applied_at
+        "SELECT id, job_seeker_id, job_id, cover_letter, resume, status, applied_at, reference, rating
          FROM applications LIMIT ?1 OFFSET ?2"
     )?;
-    let application_iter = stmt.query_map(params![limit, offset], |row| {
-        let applied_at: String = row.get(6)?;
-
-        Ok(Application {
-            id: row.get(0)?,
-            job_seeker_id: row.get(1)?,
-            job_id: row.get(2)?,
-            cover_letter: row.get(3)?,
-            resume: row.get(4)?,
-            status: row.get(5)?,
-            applied_at: DateTime::parse_from_rfc3339(&applied_at).unwrap().with_timezone(&Utc),
-        })
-    })?;
+    let rows = stmt.query_map(params![limit, offset], application_from_row)?;
+    Ok(collect_rows(rows, strict)?)
+}
 
-    let mut applications = Vec::new();
-    for application in application_iter {
-        applications.push(application?);
-    }
-    Ok(applications)
+/// Applications whose reference contains `term`, ordered by id, along with the total number of matches.
+pub fn search(
+    conn: &mut Connection,
+    term: &str,
+    limit: i64,
+    offset: i64,
+) -> Result<(Vec<Application>, i64), Box<dyn Error>> {
+    let pattern = like_pattern(term);
+    let count: i64 = conn.query_row(
+        "SELECT COUNT(*) FROM applications WHERE fold(reference) LIKE ?1 ESCAPE '\\'",
+        params![pattern],
+        |row| row.get(0),
+    )?;
+    let mut stmt = conn.prepare(
+        "SELECT id, job_seeker_id, job_id, cover_letter, resume, status, applied_at, reference, rating
+         FROM applications WHERE fold(reference) LIKE ?1 ESCAPE '\\'
+         ORDER BY id LIMIT ?2 OFFSET ?3"
+    )?;
+    let applications = stmt
+        .query_map(params![pattern, limit, offset], application_from_row)?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok((applications, count))
 }
 
-pub fn create(conn: &mut Connection, application: Application) -> Result<(), Box<dyn Error>> {
-    conn.execute(
+/// Insert a new application and assign its reference, returning the id the database assigned and the reference.
+pub fn create(conn: &mut Connection, application: Application) -> Result<(ApplicationId, String), Box<dyn Error>> {
+    let tx = conn.transaction()?;
+    tx.execute(
         "INSERT INTO applications (job_seeker_id, job_id, cover_letter, resume, status, applied_at)
          VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
         params![
@@ -45,21 +68,31 @@ pub fn create(conn: &mut Connection, application: Application) -> Result<(), Box
             application.job_id,
             application.cover_letter,
             application.resume,
-            application.status as i32,
+            application.status,
             application.applied_at.to_rfc3339(),
         ],
     )?;
-    Ok(())
+    let id = ApplicationId(tx.last_insert_rowid());
+    let reference = Application::generate_reference(id, application.applied_at);
+    tx.execute(
+        "UPDATE applications SET reference = ?1 WHERE id = ?2",
+        params![reference, id],
+    )?;
+    if let Some(resume) = &application.resume {
+        attachment::set_primary_resume(&tx, id, resume, application.applied_at)?;
+    }
+    tx.commit()?;
+    Ok((id, reference))
 }
 
-pub fn delete(conn: &mut Connection, id: i64) -> Result<(), Box<dyn Error>> {
+pub fn delete(conn: &mut Connection, id: ApplicationId) -> Result<(), Box<dyn Error>> {
     conn.execute("DELETE FROM applications WHERE id = ?1", params![id])?;
     Ok(())
 }
 
-pub fn get_by_id(conn: &mut Connection, id: i64) -> Result<Option<Application>, Box<dyn Error>> {
+pub fn get_by_id(conn: &mut Connection, id: ApplicationId) -> Result<Option<Application>, Box<dyn Error>> {
     let mut stmt = conn.prepare(
-        "SELECT id, job_seeker_id, job_id, cover_letter, resume, status, applied_at
+        "SELECT id, job_seeker_id, job_id, cover_letter, resume, status, applied_at, reference, rating
          FROM applications WHERE id = ?1"
     )?;
     let mut rows = stmt.query(params![id])?;
@@ -75,6 +108,8 @@ pub fn get_by_id(conn: &mut Connection, id: i64) -> Result<Option<Application>,
             resume: row.get(4)?,
             status: row.get(5)?,
             applied_at: DateTime::parse_from_rfc3339(&applied_at)?.with_timezone(&Utc),
+            reference: row.get::<_, Option<String>>(7)?.unwrap_or_default(),
+            rating: row.get(8)?,
         };
         debug!("APPLICATION: {:#?}", application);
         Ok(Some(application))
@@ -84,24 +119,287 @@ pub fn get_by_id(conn: &mut Connection, id: i64) -> Result<Option<Application>,
     }
 }
 
-pub fn update(conn: &mut Connection, id: i64, application: Application) -> Result<(), Box<dyn Error>> {
-    conn.execute(
+/// Id of the employer who posted the job an application is for, or `None` if the application does not exist.
+pub fn get_employer_id(conn: &mut Connection, id: ApplicationId) -> Result<Option<UserId>, Box<dyn Error>> {
+    let employer_id = conn
+        .query_row(
+            "SELECT jobs.employer_id FROM applications JOIN jobs ON jobs.id = applications.job_id WHERE applications.id = ?1",
+            params![id],
+            |row| row.get(0),
+        )
+        .optional()?;
+    Ok(employer_id)
+}
+
+pub fn get_by_reference(conn: &mut Connection, reference: &str) -> Result<Option<Application>, Box<dyn Error>> {
+    let id: Option<ApplicationId> = conn
+        .query_row(
+            "SELECT id FROM applications WHERE reference = ?1",
+            params![reference],
+            |row| row.get(0),
+        )
+        .optional()?;
+    match id {
+        Some(id) => get_by_id(conn, id),
+        None => Ok(None),
+    }
+}
+
+pub fn update(conn: &mut Connection, id: ApplicationId, application: Application) -> Result<(), Box<dyn Error>> {
+    let tx = conn.transaction()?;
+    tx.execute(
         "UPDATE applications
          SET cover_letter = COALESCE(?1, cover_letter), resume = COALESCE(?2, resume), status = COALESCE(?3, status)
          WHERE id = ?4",
         params![
             application.cover_letter,
             application.resume,
-            application.status as i32,
+            application.status,
             id,
         ],
     )?;
+    if let Some(resume) = &application.resume {
+        attachment::set_primary_resume(&tx, id, resume, Utc::now())?;
+    }
+    tx.commit()?;
     debug!("Application updated in database.");
     Ok(())
 }
 
+/// Move a rejected application back to reviewed.
+///
+/// Returns false, leaving the application unchanged, if it does not exist or is not rejected.
+pub fn reopen(conn: &mut Connection, id: ApplicationId) -> Result<bool, Box<dyn Error>> {
+    let reopened = conn.execute(
+        "UPDATE applications SET status = ?1 WHERE id = ?2 AND status = ?3",
+        params![ApplicationStatus::Reviewed, id, ApplicationStatus::Rejected],
+    )?;
+    debug!("Application {} reopened: {}.", id, reopened > 0);
+    Ok(reopened > 0)
+}
+
+/// Reject every pending application to a job whose `auto_reject_after` is at or before `now`.
+///
+/// Returns the ids of the rejected applications with the ids of their jobs.
+pub fn auto_reject(conn: &mut Connection, now: DateTime<Utc>) -> Result<Vec<(i64, i64)>, Box<dyn Error>> {
+    let mut stmt = conn.prepare(
+        "UPDATE applications SET status = 'rejected'
+         WHERE status = 'pending'
+           AND job_id IN (SELECT id FROM jobs WHERE auto_reject_after IS NOT NULL AND auto_reject_after <= ?1)
+         RETURNING id, job_id"
+    )?;
+    let rejected = stmt
+        .query_map(params![now.to_rfc3339()], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(rejected)
+}
+
+/// Set or clear the employer's rating of an application.
+pub fn set_rating(conn: &mut Connection, id: ApplicationId, rating: Option<u8>) -> Result<(), Box<dyn Error>> {
+    conn.execute(
+        "UPDATE applications SET rating = ?1 WHERE id = ?2",
+        params![rating, id],
+    )?;
+    debug!("Application {} rating set to {:?}.", id, rating);
+    Ok(())
+}
+
+/// Applications to a job with a rating of at least `min_rating` when given, along with their total number.
+///
+/// Applications are ordered by `sort`, whose field must be a column of `applications`, with unrated applications
+/// last, and then by id.
+pub fn get_for_job(
+    conn: &mut Connection,
+    job_id: JobId,
+    min_rating: Option<u8>,
+    sort: Option<&Sort>,
+    limit: i64,
+    offset: i64,
+) -> Result<(Vec<Application>, i64), Box<dyn Error>> {
+    let count: i64 = conn.query_row(
+        "SELECT COUNT(*) FROM applications WHERE job_id = ?1 AND (?2 IS NULL OR rating >= ?2)",
+        params![job_id, min_rating],
+        |row| row.get(0),
+    )?;
+    let order_by = match sort {
+        Some(sort) => format!("{} {} NULLS LAST, id", sort.field, if sort.descending { "DESC" } else { "ASC" }),
+        None => "id".to_string(),
+    };
+    let mut stmt = conn.prepare(&format!(
+        "SELECT id, job_seeker_id, job_id, cover_letter, resume, status, applied_at, reference, rating
+         FROM applications WHERE job_id = ?1 AND (?2 IS NULL OR rating >= ?2)
+         ORDER BY {} LIMIT ?3 OFFSET ?4",
+        order_by
+    ))?;
+    let applications = stmt
+        .query_map(params![job_id, min_rating, limit, offset], application_from_row)?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok((applications, count))
+}
+
+/// Reject every pending application to a job in one transaction.
+///
+/// Returns the ids of the rejected applications with the email addresses of their job seekers.
+pub fn reject_pending(conn: &mut Connection, job_id: JobId) -> Result<Vec<(ApplicationId, String)>, Box<dyn Error>> {
+    let tx = conn.transaction()?;
+    let rejected = {
+        let mut stmt = tx.prepare(
+            "SELECT applications.id, users.email FROM applications
+             JOIN users ON users.id = applications.job_seeker_id
+             WHERE applications.job_id = ?1 AND applications.status = 'pending'
+             ORDER BY applications.id"
+        )?;
+        let rejected = stmt
+            .query_map(params![job_id], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<Result<Vec<_>, _>>()?;
+        rejected
+    };
+    tx.execute(
+        "UPDATE applications SET status = 'rejected' WHERE job_id = ?1 AND status = 'pending'",
+        params![job_id],
+    )?;
+    tx.commit()?;
+    debug!("Rejected {} pending application(s) to job {}.", rejected.len(), job_id);
+    Ok(rejected)
+}
+
+/// Number of applications per status for a job; statuses without applications are omitted.
+pub fn count_by_status_for_job(conn: &mut Connection, job_id: JobId) -> Result<Vec<(ApplicationStatus, i64)>, Box<dyn Error>> {
+    let mut stmt = conn.prepare(
+        "SELECT status, COUNT(*) FROM applications WHERE job_id = ?1 GROUP BY status"
+    )?;
+    let counts = stmt
+        .query_map(params![job_id], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(counts)
+}
+
+pub fn count_by_status_for_seeker(conn: &mut Connection, job_seeker_id: UserId) -> Result<Vec<(ApplicationStatus, i64)>, Box<dyn Error>> {
+    let mut stmt = conn.prepare(
+        "SELECT status, COUNT(*) FROM applications WHERE job_seeker_id = ?1 GROUP BY status"
+    )?;
+    let counts = stmt
+        .query_map(params![job_seeker_id], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(counts)
+}
+
+/// Status of the job seeker's application to each of `job_ids` they applied to, oldest application first.
+///
+/// A job applied to more than once appears once per application, so the last entry for a job is the latest.
+pub fn get_statuses_for_jobs(
+    conn: &mut Connection,
+    job_seeker_id: UserId,
+    job_ids: &[JobId],
+) -> Result<Vec<(JobId, ApplicationStatus)>, Box<dyn Error>> {
+    if job_ids.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let placeholders = vec!["?"; job_ids.len()].join(", ");
+    let mut stmt = conn.prepare(&format!(
+        "SELECT job_id, status FROM applications
+         WHERE job_seeker_id = ? AND job_id IN ({})
+         ORDER BY applied_at, id",
+        placeholders
+    ))?;
+    let values = std::iter::once(Value::Integer(job_seeker_id.0)).chain(job_ids.iter().map(|id| Value::Integer(id.0)));
+    let statuses = stmt
+        .query_map(params_from_iter(values), |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(statuses)
+}
+
+/// Every recorded status change, ordered by id.
+pub fn get_all_status_changes(conn: &mut Connection) -> Result<Vec<ApplicationStatusChange>, Box<dyn Error>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, application_id, status, changed_at FROM application_status_changes ORDER BY id"
+    )?;
+    let changes = stmt
+        .query_map([], |row| {
+            let changed_at: String = row.get(3)?;
+            Ok(ApplicationStatusChange {
+                id: row.get(0)?,
+                application_id: row.get(1)?,
+                status: row.get(2)?,
+                changed_at: DateTime::parse_from_rfc3339(&changed_at).unwrap().with_timezone(&Utc),
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(changes)
+}
+
 pub fn get_total_count(conn: &mut Connection) -> Result<i64, Box<dyn Error>> {
     let mut stmt = conn.prepare("SELECT COUNT(*) FROM applications")?;
     let count: i64 = stmt.query_row([], |row| row.get(0))?;
     Ok(count)
-}
\ No newline at end of file
+}
+
+fn application_from_row(row: &Row) -> rusqlite::Result<Application> {
+    let applied_at: String = row.get(6)?;
+
+    Ok(Application {
+        id: row.get(0)?,
+        job_seeker_id: row.get(1)?,
+        job_id: row.get(2)?,
+        cover_letter: row.get(3)?,
+        resume: row.get(4)?,
+        status: row.get(5)?,
+        applied_at: DateTime::parse_from_rfc3339(&applied_at).unwrap().with_timezone(&Utc),
+        reference: row.get::<_, Option<String>>(7)?.unwrap_or_default(),
+        rating: row.get(8)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+    use rusqlite::params;
+    use crate::db;
+    use crate::models::{Application, ApplicationId, ApplicationStatus, JobId, UserId};
+    use crate::utils::init_db;
+    use super::{create, get_all};
+
+    #[test]
+    fn created_applications_are_read_back() {
+        let mut conn = db::open(":memory:").unwrap();
+        init_db::initialize(&mut conn).unwrap();
+        conn.execute_batch(
+            "INSERT INTO users (id, name, email, password, role, created_at, updated_at)
+                 VALUES (1, 'Jane', 'jane@example.com', '!', 'employer', '2024-09-16T12:00:00+00:00', '2024-09-16T12:00:00+00:00'),
+                        (2, 'John', 'john@example.com', '!', 'job_seeker', '2024-09-16T12:00:00+00:00', '2024-09-16T12:00:00+00:00');",
+        )
+        .unwrap();
+        conn.execute(
+            "INSERT INTO jobs (id, employer_id, title, description, location, employment_type, posted_at, updated_at)
+             VALUES (1, ?1, 'Software Engineer', 'Build things.', 'Stockholm', 'full_time', ?2, ?2)",
+            params![1, "2024-09-16T12:00:00+00:00"],
+        )
+        .unwrap();
+        let applied_at = Utc.with_ymd_and_hms(2024, 9, 16, 15, 30, 0).unwrap();
+
+        let (id, reference) = create(&mut conn, Application {
+            id: ApplicationId::default(),
+            job_seeker_id: UserId(2),
+            job_id: JobId(1),
+            cover_letter: Some("Hello".to_string()),
+            resume: None,
+            status: ApplicationStatus::Pending,
+            applied_at,
+            reference: String::new(),
+            rating: None,
+        })
+        .unwrap();
+
+        let applications = get_all(&mut conn, 10, 0).unwrap();
+        assert_eq!(applications.len(), 1);
+        let application = &applications[0];
+        assert_eq!(application.id, id);
+        assert_eq!(application.reference, reference);
+        assert_eq!(application.job_seeker_id, UserId(2));
+        assert_eq!(application.job_id, JobId(1));
+        assert_eq!(application.cover_letter.as_deref(), Some("Hello"));
+        assert_eq!(application.status, ApplicationStatus::Pending);
+        assert_eq!(application.applied_at, applied_at);
+    }
+}
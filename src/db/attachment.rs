@@ -0,0 +1,118 @@
+use crate::models::ApplicationId;
+use crate::models::application::{ApplicationAttachment, AttachmentKind, AttachmentRequest};
+use chrono::{DateTime, Utc};
+use log::debug;
+use rusqlite::{params, Connection, OptionalExtension, Row, Transaction};
+use std::error::Error;
+
+/// Every attachment, ordered by id.
+pub fn get_all(conn: &mut Connection) -> Result<Vec<ApplicationAttachment>, Box<dyn Error>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, application_id, url, kind, uploaded_at FROM application_attachments ORDER BY id"
+    )?;
+    let attachments = stmt
+        .query_map([], attachment_from_row)?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(attachments)
+}
+
+/// Attachments of an application, oldest first.
+pub fn get_for_application(conn: &mut Connection, application_id: ApplicationId) -> Result<Vec<ApplicationAttachment>, Box<dyn Error>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, application_id, url, kind, uploaded_at FROM application_attachments
+         WHERE application_id = ?1
+         ORDER BY uploaded_at, id"
+    )?;
+    let attachments = stmt
+        .query_map(params![application_id], attachment_from_row)?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(attachments)
+}
+
+/// Attach a file to an application and refresh the application's legacy `resume` field.
+pub fn create(
+    conn: &mut Connection,
+    application_id: ApplicationId,
+    request: AttachmentRequest,
+) -> Result<ApplicationAttachment, Box<dyn Error>> {
+    let uploaded_at = Utc::now();
+    let tx = conn.transaction()?;
+    tx.execute(
+        "INSERT INTO application_attachments (application_id, url, kind, uploaded_at) VALUES (?1, ?2, ?3, ?4)",
+        params![application_id, request.url, request.kind, uploaded_at.to_rfc3339()],
+    )?;
+    let id = tx.last_insert_rowid();
+    sync_resume(&tx, application_id)?;
+    tx.commit()?;
+
+    Ok(ApplicationAttachment {
+        id,
+        application_id,
+        url: request.url,
+        kind: request.kind,
+        uploaded_at,
+    })
+}
+
+/// Remove an attachment from an application, returning whether it existed.
+pub fn delete(conn: &mut Connection, application_id: ApplicationId, id: i64) -> Result<bool, Box<dyn Error>> {
+    let tx = conn.transaction()?;
+    let deleted = tx.execute(
+        "DELETE FROM application_attachments WHERE id = ?1 AND application_id = ?2",
+        params![id, application_id],
+    )? > 0;
+    sync_resume(&tx, application_id)?;
+    tx.commit()?;
+    Ok(deleted)
+}
+
+/// Point the primary resume attachment of an application at `url`, creating it if there is none yet.
+///
+/// Keeps attachments in step with writes to the legacy `resume` field.
+pub fn set_primary_resume(tx: &Transaction, application_id: ApplicationId, url: &str, at: DateTime<Utc>) -> Result<(), Box<dyn Error>> {
+    let primary: Option<i64> = tx
+        .query_row(
+            "SELECT id FROM application_attachments
+             WHERE application_id = ?1 AND kind = ?2
+             ORDER BY uploaded_at, id LIMIT 1",
+            params![application_id, AttachmentKind::Resume],
+            |row| row.get(0),
+        )
+        .optional()?;
+
+    match primary {
+        Some(id) => tx.execute("UPDATE application_attachments SET url = ?1 WHERE id = ?2", params![url, id])?,
+        None => tx.execute(
+            "INSERT INTO application_attachments (application_id, url, kind, uploaded_at) VALUES (?1, ?2, ?3, ?4)",
+            params![application_id, url, AttachmentKind::Resume, at.to_rfc3339()],
+        )?,
+    };
+    Ok(())
+}
+
+/// Copy the URL of the primary resume attachment, or `NULL` if there is none, into the legacy `resume` field.
+fn sync_resume(tx: &Transaction, application_id: ApplicationId) -> Result<(), Box<dyn Error>> {
+    tx.execute(
+        "UPDATE applications SET resume = (
+             SELECT url FROM application_attachments
+             WHERE application_id = ?1 AND kind = ?2
+             ORDER BY uploaded_at, id LIMIT 1
+         )
+         WHERE id = ?1",
+        params![application_id, AttachmentKind::Resume],
+    )?;
+    debug!("Synced resume of application {} with its attachments", application_id);
+    Ok(())
+}
+
+fn attachment_from_row(row: &Row) -> rusqlite::Result<ApplicationAttachment> {
+    let uploaded_at: String = row.get(4)?;
+
+    Ok(ApplicationAttachment {
+        id: row.get(0)?,
+        application_id: row.get(1)?,
+        url: row.get(2)?,
+        kind: row.get(3)?,
+        uploaded_at: DateTime::parse_from_rfc3339(&uploaded_at).unwrap().with_timezone(&Utc),
+    })
+}
@@ -0,0 +1,242 @@
+use std::error::Error;
+use std::fmt;
+use log::info;
+use rusqlite::{params, Connection, Transaction};
+use crate::auth::password;
+use crate::db::tag;
+use crate::models::backup::Backup;
+use crate::utils::FieldError;
+
+/// Failure while restoring a backup, naming the section and record that caused it.
+#[derive(Debug)]
+pub struct RestoreError {
+    /// Backup section being restored, e.g. `jobs`.
+    pub section: &'static str,
+    /// Position of the failing record within its section, if a single record failed.
+    pub index: Option<usize>,
+    pub message: String,
+}
+
+impl fmt::Display for RestoreError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.index {
+            Some(index) => write!(f, "{}[{}]: {}", self.section, index, self.message),
+            None => write!(f, "{}: {}", self.section, self.message),
+        }
+    }
+}
+
+impl Error for RestoreError {}
+
+/// Number of records restored per section.
+pub struct RestoreCounts {
+    pub users: usize,
+    pub jobs: usize,
+    pub applications: usize,
+    pub attachments: usize,
+    pub notes: usize,
+    pub job_tags: usize,
+    pub preferences: usize,
+    pub status_changes: usize,
+}
+
+/// Restore a backup in a single transaction, optionally deleting all existing data first.
+///
+/// Records keep their ids. Any invalid record or failing insert rolls back the entire restore. The wipe only deletes
+/// tables a backup contains, so a backup and a wiped restore round-trip the database. Tag names and digest runs are
+/// kept.
+pub fn restore(conn: &mut Connection, backup: &Backup, wipe: bool) -> Result<RestoreCounts, RestoreError> {
+    validate(backup)?;
+
+    let tx = conn.transaction().map_err(|e| section_error("database", None, e))?;
+    if wipe {
+        tx.execute_batch(
+            "DELETE FROM application_status_changes; DELETE FROM application_notes; DELETE FROM application_attachments;
+             DELETE FROM applications; DELETE FROM job_tags; DELETE FROM jobs; DELETE FROM user_preferences;
+             DELETE FROM users;",
+        )
+            .map_err(|e| section_error("database", None, e))?;
+    }
+
+    insert_all(&tx, backup)?;
+    tx.commit().map_err(|e| section_error("database", None, e))?;
+
+    info!(
+        "Restored {} users, {} jobs, {} applications, {} attachments, {} notes, {} job tags, {} preferences and {} status changes (wipe = {})",
+        backup.users.len(),
+        backup.jobs.len(),
+        backup.applications.len(),
+        backup.attachments.len(),
+        backup.notes.len(),
+        backup.job_tags.len(),
+        backup.preferences.len(),
+        backup.status_changes.len(),
+        wipe
+    );
+    Ok(RestoreCounts {
+        users: backup.users.len(),
+        jobs: backup.jobs.len(),
+        applications: backup.applications.len(),
+        attachments: backup.attachments.len(),
+        notes: backup.notes.len(),
+        job_tags: backup.job_tags.len(),
+        preferences: backup.preferences.len(),
+        status_changes: backup.status_changes.len(),
+    })
+}
+
+fn validate(backup: &Backup) -> Result<(), RestoreError> {
+    let invalid = |section, index, errors: Vec<FieldError>| RestoreError {
+        section,
+        index: Some(index),
+        message: errors
+            .iter()
+            .map(|e| e.message.clone())
+            .collect::<Vec<_>>()
+            .join("; "),
+    };
+
+    for (index, user) in backup.users.iter().enumerate() {
+        user.validate().map_err(|errors| invalid("users", index, errors))?;
+    }
+    for (index, job) in backup.jobs.iter().enumerate() {
+        job.validate().map_err(|errors| invalid("jobs", index, errors))?;
+    }
+    Ok(())
+}
+
+fn insert_all(tx: &Transaction, backup: &Backup) -> Result<(), RestoreError> {
+    for (index, user) in backup.users.iter().enumerate() {
+        // Backups leave out passwords, so restored users cannot sign in until their password is reset.
+        let password = if password::is_hash(&user.password) { user.password.as_str() } else { password::UNUSABLE };
+        tx.execute(
+            "INSERT INTO users (id, name, email, password, role, created_at, updated_at, bio, avatar_url, phone)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+            params![
+                user.id,
+                user.name,
+                user.email,
+                password,
+                user.role,
+                user.created_at.to_rfc3339(),
+                user.updated_at.to_rfc3339(),
+                user.bio,
+                user.avatar_url,
+                user.phone,
+            ],
+        )
+        .map_err(|e| section_error("users", Some(index), e))?;
+    }
+
+    for (index, job) in backup.jobs.iter().enumerate() {
+        tx.execute(
+            "INSERT INTO jobs (id, employer_id, title, description, location, salary, employment_type, posted_at, updated_at, deadline,
+                               status, closed_at, auto_reject_after)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)",
+            params![
+                job.id,
+                job.employer_id,
+                job.title,
+                job.description,
+                job.location,
+                job.salary,
+                job.employment_type,
+                job.posted_at.to_rfc3339(),
+                job.updated_at.to_rfc3339(),
+                job.deadline.map(|d| d.to_rfc3339()),
+                job.status,
+                job.closed_at.map(|d| d.to_rfc3339()),
+                job.auto_reject_after.map(|d| d.to_rfc3339()),
+            ],
+        )
+        .map_err(|e| section_error("jobs", Some(index), e))?;
+    }
+
+    for (index, application) in backup.applications.iter().enumerate() {
+        tx.execute(
+            "INSERT INTO applications (id, job_seeker_id, job_id, cover_letter, resume, status, applied_at, reference, rating)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            params![
+                application.id,
+                application.job_seeker_id,
+                application.job_id,
+                application.cover_letter,
+                application.resume,
+                application.status,
+                application.applied_at.to_rfc3339(),
+                application.reference,
+                application.rating,
+            ],
+        )
+        .map_err(|e| section_error("applications", Some(index), e))?;
+    }
+
+    for (index, attachment) in backup.attachments.iter().enumerate() {
+        tx.execute(
+            "INSERT INTO application_attachments (id, application_id, url, kind, uploaded_at)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                attachment.id,
+                attachment.application_id,
+                attachment.url,
+                attachment.kind,
+                attachment.uploaded_at.to_rfc3339(),
+            ],
+        )
+        .map_err(|e| section_error("attachments", Some(index), e))?;
+    }
+
+    for (index, note) in backup.notes.iter().enumerate() {
+        tx.execute(
+            "INSERT INTO application_notes (id, application_id, author_id, body, created_at) VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![note.id, note.application_id, note.author_id, note.body, note.created_at.to_rfc3339()],
+        )
+        .map_err(|e| section_error("notes", Some(index), e))?;
+    }
+
+    for (index, job_tag) in backup.job_tags.iter().enumerate() {
+        let tag = tag::normalize(&job_tag.tag);
+        tx.execute("INSERT OR IGNORE INTO tags (name) VALUES (?1)", params![tag])
+            .and_then(|_| {
+                tx.execute(
+                    "INSERT INTO job_tags (job_id, tag_id) SELECT ?1, id FROM tags WHERE name = ?2",
+                    params![job_tag.job_id, tag],
+                )
+            })
+            .map_err(|e| section_error("job_tags", Some(index), e))?;
+    }
+
+    for (index, preferences) in backup.preferences.iter().enumerate() {
+        let employment_types: Vec<String> = preferences.employment_types.iter().map(ToString::to_string).collect();
+        tx.execute(
+            "INSERT INTO user_preferences (user_id, employment_types, locations, min_salary, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                preferences.user_id,
+                serde_json::Value::from(employment_types).to_string(),
+                serde_json::Value::from(preferences.locations.clone()).to_string(),
+                preferences.min_salary,
+                preferences.updated_at.unwrap_or(backup.created_at).to_rfc3339(),
+            ],
+        )
+        .map_err(|e| section_error("preferences", Some(index), e))?;
+    }
+
+    for (index, change) in backup.status_changes.iter().enumerate() {
+        tx.execute(
+            "INSERT INTO application_status_changes (id, application_id, status, changed_at) VALUES (?1, ?2, ?3, ?4)",
+            params![change.id, change.application_id, change.status, change.changed_at.to_rfc3339()],
+        )
+        .map_err(|e| section_error("status_changes", Some(index), e))?;
+    }
+
+    Ok(())
+}
+
+fn section_error(section: &'static str, index: Option<usize>, err: rusqlite::Error) -> RestoreError {
+    RestoreError {
+        section,
+        index,
+        message: err.to_string(),
+    }
+}
@@ -0,0 +1,63 @@
+use std::error::Error;
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection, OptionalExtension};
+use crate::models::UserId;
+
+/// Number of new applications one job of an employer received within a digest window.
+pub struct DigestRow {
+    pub employer_id: UserId,
+    pub employer_name: String,
+    pub employer_email: String,
+    pub job_title: String,
+    pub new_applications: i64,
+}
+
+/// When the application digest last ran, if ever.
+pub fn get_last_run(conn: &Connection) -> Result<Option<DateTime<Utc>>, Box<dyn Error>> {
+    let last_run_at: Option<String> = conn
+        .query_row("SELECT last_run_at FROM digest_runs WHERE id = 1", [], |row| row.get(0))
+        .optional()?;
+    match last_run_at {
+        Some(last_run_at) => Ok(Some(DateTime::parse_from_rfc3339(&last_run_at)?.with_timezone(&Utc))),
+        None => Ok(None),
+    }
+}
+
+/// Record `at` as the end of the last digest window.
+pub fn set_last_run(conn: &Connection, at: DateTime<Utc>) -> Result<(), Box<dyn Error>> {
+    conn.execute(
+        "INSERT INTO digest_runs (id, last_run_at) VALUES (1, ?1)
+         ON CONFLICT(id) DO UPDATE SET last_run_at = excluded.last_run_at",
+        params![at.to_rfc3339()],
+    )?;
+    Ok(())
+}
+
+/// Applications received after `since` and up to `until`, counted per job and ordered by employer.
+pub fn get_new_applications(
+    conn: &Connection,
+    since: DateTime<Utc>,
+    until: DateTime<Utc>,
+) -> Result<Vec<DigestRow>, Box<dyn Error>> {
+    let mut stmt = conn.prepare(
+        "SELECT users.id, users.name, users.email, jobs.title, COUNT(applications.id)
+         FROM applications
+         JOIN jobs ON jobs.id = applications.job_id
+         JOIN users ON users.id = jobs.employer_id
+         WHERE applications.applied_at > ?1 AND applications.applied_at <= ?2
+         GROUP BY users.id, jobs.id
+         ORDER BY users.id, jobs.id"
+    )?;
+    let rows = stmt
+        .query_map(params![since.to_rfc3339(), until.to_rfc3339()], |row| {
+            Ok(DigestRow {
+                employer_id: row.get(0)?,
+                employer_name: row.get(1)?,
+                employer_email: row.get(2)?,
+                job_title: row.get(3)?,
+                new_applications: row.get(4)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(rows)
+}
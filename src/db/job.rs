@@ -1,87 +1,181 @@
-use crate::models::Job;
+use crate::db::{collect_rows, is_foreign_key_violation, like_pattern};
+use crate::models::{Job, JobId, UserId};
+use crate::models::job::{JobApplicationCount, JobChange, JobDeleteImpact, JobDeleteStatus, JobLocation, RecommendedJob, ReviewQueueItem};
 use log::{debug, error};
-use rusqlite::{params, Connection};
+use rusqlite::{params, Connection, Row};
+use rusqlite::types::Type;
 use std::error::Error;
 use chrono::{DateTime, Utc};
 
+/// Columns selected for a `Job`, in the order `job_from_row` reads them.
+const JOB_COLUMNS: &str = "jobs.id, jobs.employer_id, jobs.title, jobs.description, jobs.location, jobs.salary,
+    jobs.employment_type, jobs.posted_at, jobs.updated_at, jobs.deadline, jobs.status, jobs.closed_at,
+    jobs.auto_reject_after";
+
+/// Number of columns in `JOB_COLUMNS`; extra selected columns start at this index.
+const JOB_COLUMN_COUNT: usize = 13;
+
+/// Id of a job created in bulk, or why it could not be created.
+pub type CreateOutcome = Result<JobId, String>;
+
 pub fn get_all(
     conn: &mut Connection,
     limit: i64,
     offset: i64,
 ) -> Result<Vec<Job>, Box<dyn Error>> {
-    let mut stmt = conn.prepare(
-        "SELECT id, employer_id, title, description, location, salary, employment_type, posted_at, updated_at
-         FROM jobs LIMIT ?1 OFFSET ?2"
+    Ok(get_page(conn, limit, offset, None, true)?.0)
+}
+
+/// A page of jobs, along with the number of rows skipped because they could not be decoded.
+///
+/// Jobs the job seeker `exclude_applied_by` applied to are left out. Malformed rows fail the whole page when `strict`
+/// is set and are skipped otherwise.
+pub fn get_page(
+    conn: &mut Connection,
+    limit: i64,
+    offset: i64,
+    exclude_applied_by: Option<UserId>,
+    strict: bool,
+) -> Result<(Vec<Job>, usize), Box<dyn Error>> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM jobs {} LIMIT ?1 OFFSET ?2",
+        JOB_COLUMNS,
+        exclude_applied_filter(exclude_applied_by, 3)
+    ))?;
+    let rows = match exclude_applied_by {
+        Some(job_seeker_id) => stmt.query_map(params![limit, offset, job_seeker_id], job_from_row)?,
+        None => stmt.query_map(params![limit, offset], job_from_row)?,
+    };
+    Ok(collect_rows(rows, strict)?)
+}
+
+/// Jobs whose title or description contains `term`, ordered by id, along with the total number of matches.
+pub fn search(conn: &mut Connection, term: &str, limit: i64, offset: i64) -> Result<(Vec<Job>, i64), Box<dyn Error>> {
+    let pattern = like_pattern(term);
+    let count: i64 = conn.query_row(
+        "SELECT COUNT(*) FROM jobs WHERE fold(title) LIKE ?1 ESCAPE '\\' OR fold(description) LIKE ?1 ESCAPE '\\'",
+        params![pattern],
+        |row| row.get(0),
     )?;
-    let job_iter = stmt.query_map(params![limit, offset], |row| {
-        let posted_at: String = row.get(7)?;
-        let updated_at: String = row.get(8)?;
-
-        Ok(Job {
-            id: row.get(0)?,
-            employer_id: row.get(1)?,
-            title: row.get(2)?,
-            description: row.get(3)?,
-            location: row.get(4)?,
-            salary: row.get(5)?,
-            employment_type: row.get(6)?,
-            posted_at: DateTime::parse_from_rfc3339(&posted_at).unwrap().with_timezone(&Utc),
-            updated_at: DateTime::parse_from_rfc3339(&updated_at).unwrap().with_timezone(&Utc),
-        })
-    })?;
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM jobs WHERE fold(title) LIKE ?1 ESCAPE '\\' OR fold(description) LIKE ?1 ESCAPE '\\'
+         ORDER BY id LIMIT ?2 OFFSET ?3",
+        JOB_COLUMNS
+    ))?;
+    let jobs = stmt
+        .query_map(params![pattern, limit, offset], job_from_row)?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok((jobs, count))
+}
 
-    let mut jobs = Vec::new();
-    for job in job_iter {
-        jobs.push(job?);
+/// Insert `job`, returning the id the database assigned to it. The `id` of `job` is ignored.
+pub fn create(conn: &mut Connection, job: Job) -> Result<JobId, Box<dyn Error>> {
+    Ok(insert(conn, &job)?)
+}
+
+/// Create the given jobs in a single transaction, returning the new id or the error of each one.
+///
+/// Every job is inserted under its own savepoint so a failing row does not affect the others. With `all_or_nothing`,
+/// the transaction is rolled back if any job fails; the returned flag tells whether it was committed.
+pub fn create_many(
+    conn: &mut Connection,
+    jobs: &[Job],
+    all_or_nothing: bool,
+) -> Result<(Vec<CreateOutcome>, bool), Box<dyn Error>> {
+    let mut tx = conn.transaction()?;
+    let mut results = Vec::with_capacity(jobs.len());
+    for job in jobs {
+        let sp = tx.savepoint()?;
+        match insert(&sp, job) {
+            Ok(id) => {
+                sp.commit()?;
+                results.push(Ok(id));
+            }
+            Err(e) => results.push(Err(e.to_string())),
+        }
     }
-    Ok(jobs)
+
+    let committed = !all_or_nothing || results.iter().all(Result::is_ok);
+    if committed {
+        tx.commit()?;
+    }
+    debug!("Imported jobs (committed: {}): {:?}", committed, results);
+    Ok((results, committed))
 }
 
-pub fn create(conn: &mut Connection, job: Job) -> Result<(), Box<dyn Error>> {
+fn insert(conn: &Connection, job: &Job) -> rusqlite::Result<JobId> {
     conn.execute(
-        "INSERT INTO jobs (employer_id, title, description, location, salary, employment_type, posted_at, updated_at)
-         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+        "INSERT INTO jobs (employer_id, title, description, location, salary, employment_type, posted_at, updated_at, deadline,
+                           status, closed_at, auto_reject_after)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
         params![
             job.employer_id,
             job.title,
             job.description,
             job.location,
             job.salary,
-            job.employment_type as i32,
+            job.employment_type,
             job.posted_at.to_rfc3339(),
             job.updated_at.to_rfc3339(),
+            job.deadline.map(|d| d.to_rfc3339()),
+            job.status,
+            job.closed_at.map(|d| d.to_rfc3339()),
+            job.auto_reject_after.map(|d| d.to_rfc3339()),
         ],
     )?;
-    Ok(())
+    Ok(JobId(conn.last_insert_rowid()))
 }
 
-pub fn delete(conn: &mut Connection, id: i64) -> Result<(), Box<dyn Error>> {
+pub fn delete(conn: &mut Connection, id: JobId) -> Result<(), Box<dyn Error>> {
     conn.execute("DELETE FROM jobs WHERE id = ?1", params![id])?;
     Ok(())
 }
 
-pub fn get_by_id(conn: &mut Connection, id: i64) -> Result<Option<Job>, Box<dyn Error>> {
-    let mut stmt = conn.prepare(
-        "SELECT id, employer_id, title, description, location, salary, employment_type, posted_at, updated_at
-         FROM jobs WHERE id = ?1"
+/// Delete all the given jobs in a single transaction.
+///
+/// Returns what happened to each id. Jobs that still have applications are kept and reported as conflicts without
+/// affecting the other ids; the whole batch is rolled back only if a statement fails for any other reason.
+pub fn delete_many(conn: &mut Connection, ids: &[JobId]) -> Result<Vec<(JobId, JobDeleteStatus)>, Box<dyn Error>> {
+    let tx = conn.transaction()?;
+    let mut results = Vec::with_capacity(ids.len());
+    {
+        let mut stmt = tx.prepare("DELETE FROM jobs WHERE id = ?1")?;
+        for &id in ids {
+            // A failed statement only undoes its own changes, so the transaction carries on after a conflict.
+            let status = match stmt.execute(params![id]) {
+                Ok(0) => JobDeleteStatus::NotFound,
+                Ok(_) => JobDeleteStatus::Deleted,
+                Err(e) if is_foreign_key_violation(&e) => JobDeleteStatus::Conflict,
+                Err(e) => return Err(e.into()),
+            };
+            results.push((id, status));
+        }
+    }
+    tx.commit()?;
+    debug!("Bulk deleted jobs: {:?}", results);
+    Ok(results)
+}
+
+/// Close every open job of the employer at `now`, in a single transaction.
+///
+/// Closed and expired jobs are left unchanged. Returns the number of jobs closed.
+pub fn close_all_for_employer(conn: &mut Connection, employer_id: UserId, now: DateTime<Utc>) -> Result<usize, Box<dyn Error>> {
+    let tx = conn.transaction()?;
+    let closed = tx.execute(
+        "UPDATE jobs SET status = 'closed', closed_at = ?1, updated_at = ?1 WHERE employer_id = ?2 AND status = 'open'",
+        params![now.to_rfc3339(), employer_id],
     )?;
+    tx.commit()?;
+    debug!("Closed {} open job(s) of employer {}.", closed, employer_id);
+    Ok(closed)
+}
+
+pub fn get_by_id(conn: &mut Connection, id: JobId) -> Result<Option<Job>, Box<dyn Error>> {
+    let mut stmt = conn.prepare(&format!("SELECT {} FROM jobs WHERE id = ?1", JOB_COLUMNS))?;
     let mut rows = stmt.query(params![id])?;
 
     if let Some(row) = rows.next()? {
-        let posted_at: String = row.get(7)?;
-        let updated_at: String = row.get(8)?;
-
-        let job = Job {
-            id: row.get(0)?,
-            employer_id: row.get(1)?,
-            title: row.get(2)?,
-            description: row.get(3)?,
-            location: row.get(4)?,
-            salary: row.get(5)?,
-            employment_type: row.get(6)?,
-            posted_at: DateTime::parse_from_rfc3339(&posted_at)?.with_timezone(&Utc),
-            updated_at: DateTime::parse_from_rfc3339(&updated_at)?.with_timezone(&Utc),
-        };
+        let job = job_from_row(row)?;
         debug!("JOB: {:#?}", job);
         Ok(Some(job))
     } else {
@@ -90,30 +184,317 @@ pub fn get_by_id(conn: &mut Connection, id: i64) -> Result<Option<Job>, Box<dyn
     }
 }
 
-pub fn update(conn: &mut Connection, id: i64, job: Job) -> Result<(), Box<dyn Error>> {
+pub fn update(conn: &mut Connection, id: JobId, job: Job) -> Result<(), Box<dyn Error>> {
     conn.execute(
         "UPDATE jobs
          SET employer_id = COALESCE(?1, employer_id), title = COALESCE(?2, title), description = COALESCE(?3, description),
              location = COALESCE(?4, location), salary = COALESCE(?5, salary), employment_type = COALESCE(?6, employment_type),
-             updated_at = ?7
-         WHERE id = ?8",
+             updated_at = ?7, deadline = COALESCE(?8, deadline), status = COALESCE(?9, status), closed_at = ?10,
+             auto_reject_after = COALESCE(?11, auto_reject_after)
+         WHERE id = ?12",
         params![
             job.employer_id,
             job.title,
             job.description,
             job.location,
             job.salary,
-            job.employment_type as i32,
+            job.employment_type,
             Utc::now().to_rfc3339(),
-            job.id,
+            job.deadline.map(|d| d.to_rfc3339()),
+            job.status,
+            job.closed_at.map(|d| d.to_rfc3339()),
+            job.auto_reject_after.map(|d| d.to_rfc3339()),
+            id,
         ],
     )?;
     debug!("Job updated in database.");
     Ok(())
 }
 
-pub fn get_total_count(conn: &mut Connection) -> Result<i64, Box<dyn Error>> {
-    let mut stmt = conn.prepare("SELECT COUNT(*) FROM jobs")?;
-    let count: i64 = stmt.query_row([], |row| row.get(0))?;
+/// Distinct non-empty job locations with the number of jobs for each, sorted alphabetically.
+pub fn get_locations(conn: &mut Connection) -> Result<Vec<JobLocation>, Box<dyn Error>> {
+    let mut stmt = conn.prepare(
+        "SELECT location, COUNT(*) FROM jobs
+         WHERE location IS NOT NULL AND TRIM(location) <> ''
+         GROUP BY location
+         ORDER BY location COLLATE NOCASE"
+    )?;
+    let locations = stmt
+        .query_map([], |row| {
+            Ok(JobLocation {
+                location: row.get(0)?,
+                count: row.get(1)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(locations)
+}
+
+/// Jobs of an employer with at least one pending application, oldest pending application first.
+pub fn get_review_queue(conn: &mut Connection, employer_id: UserId) -> Result<Vec<ReviewQueueItem>, Box<dyn Error>> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {}, COUNT(applications.id), MIN(applications.applied_at)
+         FROM jobs
+         JOIN applications ON applications.job_id = jobs.id AND applications.status = 'pending'
+         WHERE jobs.employer_id = ?1
+         GROUP BY jobs.id
+         ORDER BY MIN(applications.applied_at), jobs.id",
+        JOB_COLUMNS
+    ))?;
+    let items = stmt
+        .query_map(params![employer_id], |row| {
+            let oldest_pending_at: String = row.get(JOB_COLUMN_COUNT + 1)?;
+
+            Ok(ReviewQueueItem {
+                job: job_from_row(row)?,
+                pending_count: row.get(JOB_COLUMN_COUNT)?,
+                oldest_pending_at: parse_timestamp(JOB_COLUMN_COUNT + 1, &oldest_pending_at)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(items)
+}
+
+/// Number of applications for each of the employer's jobs, ordered by job id. Jobs without applications count zero.
+pub fn get_application_counts(conn: &mut Connection, employer_id: UserId) -> Result<Vec<JobApplicationCount>, Box<dyn Error>> {
+    let mut stmt = conn.prepare(
+        "SELECT jobs.id, COUNT(applications.id)
+         FROM jobs
+         LEFT JOIN applications ON applications.job_id = jobs.id
+         WHERE jobs.employer_id = ?1
+         GROUP BY jobs.id
+         ORDER BY jobs.id",
+    )?;
+    let counts = stmt
+        .query_map(params![employer_id], |row| {
+            Ok(JobApplicationCount {
+                job_id: row.get(0)?,
+                count: row.get(1)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(counts)
+}
+
+/// Count the records that depend on a job, as `delete` would leave them behind.
+pub fn get_delete_impact(conn: &mut Connection, id: JobId) -> Result<JobDeleteImpact, Box<dyn Error>> {
+    let impact = conn.query_row(
+        "SELECT (SELECT COUNT(*) FROM applications WHERE job_id = ?1),
+                (SELECT COUNT(*) FROM application_attachments
+                 WHERE application_id IN (SELECT id FROM applications WHERE job_id = ?1)),
+                (SELECT COUNT(*) FROM application_notes
+                 WHERE application_id IN (SELECT id FROM applications WHERE job_id = ?1)),
+                (SELECT COUNT(*) FROM job_tags WHERE job_id = ?1)",
+        params![id],
+        |row| {
+            let applications: i64 = row.get(0)?;
+            Ok(JobDeleteImpact {
+                job_id: id,
+                blocked: applications > 0,
+                applications,
+                attachments: row.get(1)?,
+                notes: row.get(2)?,
+                tags: row.get(3)?,
+            })
+        },
+    )?;
+    Ok(impact)
+}
+
+/// Open jobs sharing tags, employment type or location with the jobs a seeker applied to or matching the seeker's
+/// preferred employment types and locations, best matches first.
+///
+/// Jobs the seeker already applied to and jobs past their deadline at `now` are left out, as are jobs whose highest
+/// listed salary is below `min_salary`. Jobs without a readable salary are kept.
+pub fn get_recommended(
+    conn: &mut Connection,
+    job_seeker_id: UserId,
+    min_salary: Option<i64>,
+    now: DateTime<Utc>,
+    limit: i64,
+) -> Result<Vec<RecommendedJob>, Box<dyn Error>> {
+    let mut stmt = conn.prepare(&format!(
+        "WITH applied AS (SELECT jobs.* FROM jobs JOIN applications ON applications.job_id = jobs.id
+                          WHERE applications.job_seeker_id = ?1),
+              applied_tags AS (SELECT DISTINCT tag_id FROM job_tags WHERE job_id IN (SELECT id FROM applied)),
+              preferences AS (SELECT employment_types, locations FROM user_preferences WHERE user_id = ?1)
+         SELECT {},
+                (SELECT COUNT(*) FROM job_tags WHERE job_tags.job_id = jobs.id AND tag_id IN (SELECT tag_id FROM applied_tags))
+                + (jobs.employment_type IN (SELECT employment_type FROM applied))
+                + (lower(trim(jobs.location)) IN (SELECT lower(trim(location)) FROM applied))
+                + (jobs.employment_type IN (SELECT value FROM preferences, json_each(preferences.employment_types)))
+                + (lower(trim(jobs.location)) IN (SELECT lower(value) FROM preferences, json_each(preferences.locations))) AS score
+         FROM jobs
+         WHERE jobs.status = 'open'
+           AND (jobs.deadline IS NULL OR jobs.deadline >= ?2)
+           AND jobs.id NOT IN (SELECT id FROM applied)
+           AND score > 0
+         ORDER BY score DESC, jobs.posted_at DESC, jobs.id",
+        JOB_COLUMNS
+    ))?;
+    // Salaries are free text, so the floor is applied here rather than in SQL.
+    let mut jobs = Vec::new();
+    let rows = stmt.query_map(params![job_seeker_id, now.to_rfc3339()], |row| {
+        Ok(RecommendedJob {
+            job: job_from_row(row)?,
+            score: row.get(JOB_COLUMN_COUNT)?,
+        })
+    })?;
+    for recommended in rows {
+        let recommended = recommended?;
+        let below_floor = min_salary
+            .zip(recommended.job.max_salary())
+            .is_some_and(|(min_salary, max_salary)| max_salary < min_salary as f64);
+        if !below_floor {
+            jobs.push(recommended);
+        }
+        if jobs.len() as i64 >= limit {
+            break;
+        }
+    }
+    Ok(jobs)
+}
+
+/// Closed or expired jobs of an employer closed at or after `since`, most recently closed first.
+pub fn get_closed_for_employer(
+    conn: &mut Connection,
+    employer_id: UserId,
+    since: DateTime<Utc>,
+) -> Result<Vec<Job>, Box<dyn Error>> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM jobs
+         WHERE employer_id = ?1 AND status IN ('closed', 'expired') AND closed_at >= ?2
+         ORDER BY closed_at DESC, id",
+        JOB_COLUMNS
+    ))?;
+    let jobs = stmt
+        .query_map(params![employer_id, since.to_rfc3339()], job_from_row)?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(jobs)
+}
+
+/// Jobs updated and jobs deleted after `since`, ordered by when they changed, along with the total number of changes.
+pub fn get_changes_since(
+    conn: &mut Connection,
+    since: DateTime<Utc>,
+    limit: i64,
+    offset: i64,
+) -> Result<(Vec<JobChange>, i64), Box<dyn Error>> {
+    let since = since.to_rfc3339();
+    let count: i64 = conn.query_row(
+        "SELECT (SELECT COUNT(*) FROM jobs WHERE updated_at > ?1)
+              + (SELECT COUNT(*) FROM job_tombstones WHERE deleted_at > ?1)",
+        params![since],
+        |row| row.get(0),
+    )?;
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {}, changes.id, changes.changed_at, changes.deleted
+         FROM (
+             SELECT id, updated_at AS changed_at, 0 AS deleted FROM jobs WHERE updated_at > ?1
+             UNION ALL
+             SELECT job_id, deleted_at, 1 FROM job_tombstones WHERE deleted_at > ?1
+         ) AS changes
+         LEFT JOIN jobs ON jobs.id = changes.id AND changes.deleted = 0
+         ORDER BY changes.changed_at, changes.id
+         LIMIT ?2 OFFSET ?3",
+        JOB_COLUMNS
+    ))?;
+    let changes = stmt
+        .query_map(params![since, limit, offset], |row| {
+            let changed_at: String = row.get(JOB_COLUMN_COUNT + 1)?;
+            let deleted: bool = row.get(JOB_COLUMN_COUNT + 2)?;
+            Ok(JobChange {
+                id: row.get(JOB_COLUMN_COUNT)?,
+                changed_at: parse_timestamp(JOB_COLUMN_COUNT + 1, &changed_at)?,
+                deleted,
+                job: if deleted { None } else { Some(job_from_row(row)?) },
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok((changes, count))
+}
+
+/// Number of jobs, leaving out those the job seeker `exclude_applied_by` applied to.
+pub fn get_total_count(conn: &mut Connection, exclude_applied_by: Option<UserId>) -> Result<i64, Box<dyn Error>> {
+    let mut stmt = conn.prepare(&format!("SELECT COUNT(*) FROM jobs {}", exclude_applied_filter(exclude_applied_by, 1)))?;
+    let count: i64 = match exclude_applied_by {
+        Some(job_seeker_id) => stmt.query_row(params![job_seeker_id], |row| row.get(0))?,
+        None => stmt.query_row([], |row| row.get(0))?,
+    };
     Ok(count)
-}
\ No newline at end of file
+}
+
+/// `WHERE` clause leaving out the jobs applied to by the job seeker bound to parameter `index`, if there is one.
+///
+/// Applications are only queried when a job seeker is given, so listing jobs never depends on them.
+fn exclude_applied_filter(exclude_applied_by: Option<UserId>, index: usize) -> String {
+    match exclude_applied_by {
+        Some(_) => format!(
+            "WHERE jobs.id NOT IN (SELECT job_id FROM applications WHERE job_seeker_id = ?{})",
+            index
+        ),
+        None => String::new(),
+    }
+}
+
+/// Build a `Job` from a row whose first columns are `JOB_COLUMNS`.
+fn job_from_row(row: &Row) -> rusqlite::Result<Job> {
+    let posted_at: String = row.get(7)?;
+    let posted_at = parse_timestamp(7, &posted_at)?;
+    let updated_at: String = row.get(8)?;
+    let deadline: Option<String> = row.get(9)?;
+    let closed_at: Option<String> = row.get(11)?;
+    let auto_reject_after: Option<String> = row.get(12)?;
+
+    Ok(Job {
+        id: row.get(0)?,
+        employer_id: row.get(1)?,
+        title: row.get(2)?,
+        description: row.get(3)?,
+        location: row.get(4)?,
+        salary: row.get(5)?,
+        employment_type: row.get(6)?,
+        posted_at,
+        updated_at: parse_timestamp(8, &updated_at)?,
+        deadline: deadline.map(|d| parse_timestamp(9, &d)).transpose()?,
+        is_new: Job::is_recent(posted_at),
+        status: row.get(10)?,
+        closed_at: closed_at.map(|d| parse_timestamp(11, &d)).transpose()?,
+        auto_reject_after: auto_reject_after.map(|d| parse_timestamp(12, &d)).transpose()?,
+    })
+}
+
+fn parse_timestamp(column: usize, value: &str) -> rusqlite::Result<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(value)
+        .map(|timestamp| timestamp.with_timezone(&Utc))
+        .map_err(|e| rusqlite::Error::FromSqlConversionFailure(column, Type::Text, Box::new(e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::DateTime;
+    use crate::models::JobId;
+    use crate::utils::testing::TestDb;
+    use super::get_changes_since;
+
+    #[test]
+    fn reinserted_jobs_are_not_reported_as_deleted() {
+        let db = TestDb::new();
+        let employer = db.user("employer@example.com", "employer", "!");
+        let job = db.job(employer);
+        db.conn().execute("DELETE FROM jobs WHERE id = ?1", [job]).unwrap();
+        db.conn()
+            .execute(
+                "INSERT INTO jobs (id, employer_id, title, description, location, employment_type, posted_at, updated_at)
+                 VALUES (?1, ?2, 'Restored', 'Back again.', 'Stockholm', 'full_time',
+                         '2024-09-16T12:00:00+00:00', '2024-09-16T12:00:00+00:00')",
+                [job, employer],
+            )
+            .unwrap();
+
+        let (changes, count) = get_changes_since(&mut db.conn(), DateTime::UNIX_EPOCH, 10, 0).unwrap();
+        assert_eq!(count, 1);
+        assert_eq!(changes[0].id, JobId(job));
+        assert!(!changes[0].deleted);
+    }
+}
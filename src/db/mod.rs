@@ -1,3 +1,273 @@
+use std::env;
+use std::error::Error;
+use std::ops::Deref;
+use std::path::Path;
+use std::time::Duration;
+use log::{debug, warn};
+use r2d2_sqlite::SqliteConnectionManager;
+use rusqlite::functions::FunctionFlags;
+use rusqlite::{Connection, ErrorCode, OpenFlags};
+
 pub mod user;
 pub mod job;
 pub mod application;
+pub mod attachment;
+pub mod note;
+pub mod preferences;
+pub mod backup;
+pub mod tag;
+pub mod digest;
+
+/// Pool of connections to the database, shared by every request handler.
+pub type Pool = r2d2::Pool<SqliteConnectionManager>;
+
+/// Create a pool of at most `size` connections to the database at `path`.
+///
+/// Connections are opened lazily, log their queries and can call `fold` like connections from [`open`]. Waiting for a free connection
+/// gives up after `timeout`.
+pub fn pool<P: AsRef<Path>>(path: P, size: u32, timeout: Duration) -> Pool {
+    build_pool(SqliteConnectionManager::file(path), size, timeout)
+}
+
+/// Pool that handlers which only read take their connections from.
+///
+/// It is a separate, read-only pool when `READ_DATABASE_URL` points at a replica, and the primary pool otherwise.
+/// Handlers that write also read from the primary, so they see their own writes.
+#[derive(Clone)]
+pub struct ReadPool(Pool);
+
+impl ReadPool {
+    /// Pool of at most `size` read-only connections to the database at `path`.
+    pub fn open<P: AsRef<Path>>(path: P, size: u32, timeout: Duration) -> Self {
+        let manager = SqliteConnectionManager::file(path)
+            .with_flags(OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_URI | OpenFlags::SQLITE_OPEN_NO_MUTEX);
+        ReadPool(build_pool(manager, size, timeout))
+    }
+
+    /// Read from the primary pool.
+    pub fn primary(pool: Pool) -> Self {
+        ReadPool(pool)
+    }
+}
+
+impl Deref for ReadPool {
+    type Target = Pool;
+
+    fn deref(&self) -> &Pool {
+        &self.0
+    }
+}
+
+fn build_pool(manager: SqliteConnectionManager, size: u32, timeout: Duration) -> Pool {
+    let manager = manager.with_init(|conn| {
+        if query_log_enabled() {
+            conn.profile(Some(log_query));
+        }
+        register_fold(conn)
+    });
+    r2d2::Pool::builder()
+        .max_size(size)
+        .min_idle(Some(0))
+        .connection_timeout(timeout)
+        .build_unchecked(manager)
+}
+
+/// Open a connection to the database at `path`.
+///
+/// When `DB_QUERY_LOG` is set to `1` or `true`, every statement run on the connection is logged at debug level with its
+/// execution time. Only the SQL text with its `?` placeholders is logged, never the bound values, so passwords and
+/// other parameters stay out of the logs.
+///
+/// Queries on the connection can call `fold(text)`, which returns [`fold`] of its argument, or NULL for NULL.
+pub fn open<P: AsRef<Path>>(path: P) -> rusqlite::Result<Connection> {
+    let mut conn = Connection::open(path)?;
+    if query_log_enabled() {
+        conn.profile(Some(log_query));
+    }
+    register_fold(&conn)?;
+    Ok(conn)
+}
+
+fn register_fold(conn: &Connection) -> rusqlite::Result<()> {
+    conn.create_scalar_function(
+        "fold",
+        1,
+        FunctionFlags::SQLITE_UTF8 | FunctionFlags::SQLITE_DETERMINISTIC,
+        |ctx| Ok(ctx.get::<Option<String>>(0)?.map(|text| fold(&text))),
+    )
+}
+
+fn query_log_enabled() -> bool {
+    env::var("DB_QUERY_LOG").is_ok_and(|value| value == "1" || value.eq_ignore_ascii_case("true"))
+}
+
+fn log_query(sql: &str, elapsed: Duration) {
+    debug!("SQL ({} µs): {}", elapsed.as_micros(), sql);
+}
+
+/// Collect query results, skipping rows whose values cannot be decoded unless `strict` is set.
+///
+/// A value fails to decode when it does not fit the Rust type, e.g. an enum column holding an unknown value. Skipped
+/// rows are logged. Any other error is returned in both modes. Returns the decoded rows and the number skipped.
+pub fn collect_rows<T>(
+    rows: impl Iterator<Item = rusqlite::Result<T>>,
+    strict: bool,
+) -> rusqlite::Result<(Vec<T>, usize)> {
+    let mut items = Vec::new();
+    let mut skipped = 0;
+    for row in rows {
+        match row {
+            Ok(item) => items.push(item),
+            Err(e @ (rusqlite::Error::FromSqlConversionFailure(..) | rusqlite::Error::InvalidColumnType(..))) if !strict => {
+                warn!("Skipping malformed row: {}", e);
+                skipped += 1;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+    Ok((items, skipped))
+}
+
+/// `LIKE` pattern matching any value that contains `term`, to be used with `ESCAPE '\'`.
+///
+/// `%`, `_` and `\` in `term` are escaped so they match literally. `term` is [`fold`]ed, so the pattern is meant to
+/// be matched against `fold(column)`.
+pub fn like_pattern(term: &str) -> String {
+    let escaped = fold(term).replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_");
+    format!("%{}%", escaped)
+}
+
+/// `text` lowercased with the diacritics stripped from Latin letters, so that searches match regardless of case and
+/// accents, e.g. `Café` and `CAFE` both fold to `cafe`.
+///
+/// Letters that are distinct letters rather than accented ones, such as `ø`, `æ` and `ß`, are kept, like a Unicode
+/// NFKD decomposition followed by dropping the combining marks would.
+pub fn fold(text: &str) -> String {
+    text.chars()
+        .flat_map(char::to_lowercase)
+        .filter(|c| !('\u{0300}'..='\u{036F}').contains(c))
+        .map(|c| base_letter(c).unwrap_or(c))
+        .collect()
+}
+
+/// Base letter of a lowercase Latin-1 or Latin Extended-A letter with a diacritic.
+fn base_letter(c: char) -> Option<char> {
+    let base = match c {
+        'à'..='å' | 'ā' | 'ă' | 'ą' => 'a',
+        'ç' | 'ć' | 'ĉ' | 'ċ' | 'č' => 'c',
+        'ď' => 'd',
+        'è'..='ë' | 'ē' | 'ĕ' | 'ė' | 'ę' | 'ě' => 'e',
+        'ĝ' | 'ğ' | 'ġ' | 'ģ' => 'g',
+        'ĥ' => 'h',
+        'ì'..='ï' | 'ĩ' | 'ī' | 'ĭ' | 'į' => 'i',
+        'ĵ' => 'j',
+        'ķ' => 'k',
+        'ĺ' | 'ļ' | 'ľ' => 'l',
+        'ñ' | 'ń' | 'ņ' | 'ň' => 'n',
+        'ò'..='ö' | 'ō' | 'ŏ' | 'ő' => 'o',
+        'ŕ' | 'ŗ' | 'ř' => 'r',
+        'ś' | 'ŝ' | 'ş' | 'š' | 'ſ' => 's',
+        'ţ' | 'ť' => 't',
+        'ù'..='ü' | 'ũ' | 'ū' | 'ŭ' | 'ů' | 'ű' | 'ų' => 'u',
+        'ŵ' => 'w',
+        'ý' | 'ÿ' | 'ŷ' => 'y',
+        'ź' | 'ż' | 'ž' => 'z',
+        _ => return None,
+    };
+    Some(base)
+}
+
+/// Whether `err` is a SQLite UNIQUE constraint violation on the given `table.column`.
+pub fn is_unique_violation(err: &(dyn Error + 'static), column: &str) -> bool {
+    match err.downcast_ref::<rusqlite::Error>() {
+        Some(rusqlite::Error::SqliteFailure(failure, Some(message))) => {
+            failure.code == ErrorCode::ConstraintViolation
+                && message.starts_with("UNIQUE constraint failed")
+                && message.contains(column)
+        }
+        _ => false,
+    }
+}
+
+/// Whether `err` is a SQLite NOT NULL constraint violation, i.e. a required column was left out.
+pub fn is_not_null_violation(err: &(dyn Error + 'static)) -> bool {
+    match err.downcast_ref::<rusqlite::Error>() {
+        Some(rusqlite::Error::SqliteFailure(failure, Some(message))) => {
+            failure.code == ErrorCode::ConstraintViolation && message.starts_with("NOT NULL constraint failed")
+        }
+        _ => false,
+    }
+}
+
+/// Whether `err` is a SQLite FOREIGN KEY constraint violation, e.g. deleting a row other rows still refer to.
+pub fn is_foreign_key_violation(err: &(dyn Error + 'static)) -> bool {
+    match err.downcast_ref::<rusqlite::Error>() {
+        Some(rusqlite::Error::SqliteFailure(failure, Some(message))) => {
+            failure.code == ErrorCode::ConstraintViolation && message.starts_with("FOREIGN KEY constraint failed")
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::thread;
+    use crate::models::UserId;
+    use crate::utils::testing::TestDb;
+    use super::{job, like_pattern, open};
+
+    #[test]
+    fn pooled_connections_are_reused_across_concurrent_counts() {
+        let db = TestDb::new();
+        db.job(db.user("employer@example.com", "employer", "!"));
+        let pool = db.pool();
+        // A temp table lives as long as its connection, so an empty one marks a connection opened for this request.
+        let opened = AtomicUsize::new(0);
+
+        thread::scope(|scope| {
+            for _ in 0..16 {
+                scope.spawn(|| {
+                    for _ in 0..50 {
+                        let mut conn = pool.get().unwrap();
+                        assert_eq!(job::get_total_count(&mut conn, None).unwrap(), 1);
+                        assert_eq!(job::get_total_count(&mut conn, Some(UserId(1))).unwrap(), 1);
+                        conn.execute("CREATE TEMP TABLE IF NOT EXISTS seen (id INTEGER)", []).unwrap();
+                        let new = conn
+                            .query_row("SELECT COUNT(*) = 0 FROM temp.seen", [], |row| row.get::<_, bool>(0))
+                            .unwrap();
+                        if new {
+                            conn.execute("INSERT INTO temp.seen (id) VALUES (1)", []).unwrap();
+                            opened.fetch_add(1, Ordering::Relaxed);
+                        }
+                    }
+                });
+            }
+        });
+
+        let opened = opened.into_inner();
+        assert!((1..=4).contains(&opened), "opened {} connections for 800 requests", opened);
+    }
+
+    #[test]
+    fn like_patterns_match_wildcards_literally() {
+        let conn = open(":memory:").unwrap();
+        conn.execute_batch(
+            "CREATE TABLE terms (term TEXT NOT NULL);
+             INSERT INTO terms (term) VALUES ('100% remote'), ('1000 remote'), ('on_call'), ('oncall'), ('on-call'), ('C:\\dev');",
+        )
+        .unwrap();
+        let matches = |term: &str| -> Vec<String> {
+            let mut stmt = conn.prepare("SELECT term FROM terms WHERE fold(term) LIKE ?1 ESCAPE '\\' ORDER BY rowid").unwrap();
+            let rows = stmt.query_map([like_pattern(term)], |row| row.get(0)).unwrap();
+            rows.collect::<Result<_, _>>().unwrap()
+        };
+
+        assert_eq!(matches("%"), ["100% remote"]);
+        assert_eq!(matches("0%"), ["100% remote"]);
+        assert_eq!(matches("_"), ["on_call"]);
+        assert_eq!(matches("n_c"), ["on_call"]);
+        assert_eq!(matches("\\"), ["C:\\dev"]);
+        assert_eq!(matches("REMOTE").len(), 2);
+    }
+}
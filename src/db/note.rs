@@ -0,0 +1,63 @@
+use crate::models::{ApplicationId, UserId};
+use crate::models::application::ApplicationNote;
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection, Row};
+use std::error::Error;
+
+/// Every note, ordered by id.
+pub fn get_all(conn: &mut Connection) -> Result<Vec<ApplicationNote>, Box<dyn Error>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, application_id, author_id, body, created_at FROM application_notes ORDER BY id"
+    )?;
+    let notes = stmt
+        .query_map([], note_from_row)?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(notes)
+}
+
+/// Notes on an application, oldest first.
+pub fn get_for_application(conn: &mut Connection, application_id: ApplicationId) -> Result<Vec<ApplicationNote>, Box<dyn Error>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, application_id, author_id, body, created_at FROM application_notes
+         WHERE application_id = ?1
+         ORDER BY created_at, id"
+    )?;
+    let notes = stmt
+        .query_map(params![application_id], note_from_row)?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(notes)
+}
+
+/// Add a note to an application.
+pub fn create(
+    conn: &mut Connection,
+    application_id: ApplicationId,
+    author_id: UserId,
+    body: String,
+) -> Result<ApplicationNote, Box<dyn Error>> {
+    let created_at = Utc::now();
+    conn.execute(
+        "INSERT INTO application_notes (application_id, author_id, body, created_at) VALUES (?1, ?2, ?3, ?4)",
+        params![application_id, author_id, body, created_at.to_rfc3339()],
+    )?;
+
+    Ok(ApplicationNote {
+        id: conn.last_insert_rowid(),
+        application_id,
+        author_id,
+        body,
+        created_at,
+    })
+}
+
+fn note_from_row(row: &Row) -> rusqlite::Result<ApplicationNote> {
+    let created_at: String = row.get(4)?;
+
+    Ok(ApplicationNote {
+        id: row.get(0)?,
+        application_id: row.get(1)?,
+        author_id: row.get(2)?,
+        body: row.get(3)?,
+        created_at: DateTime::parse_from_rfc3339(&created_at).unwrap().with_timezone(&Utc),
+    })
+}
@@ -0,0 +1,82 @@
+use crate::models::user::{JobPreferences, JobPreferencesRequest};
+use crate::models::UserId;
+use chrono::{DateTime, Utc};
+use rusqlite::types::Type;
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use std::error::Error;
+
+/// Job preferences of a user, or `None` if they never saved any.
+///
+/// Employment types and locations are stored as JSON arrays, so recommendations can match them with `json_each`.
+pub fn get(conn: &mut Connection, user_id: UserId) -> Result<Option<JobPreferences>, Box<dyn Error>> {
+    let preferences = conn
+        .query_row(
+            "SELECT user_id, employment_types, locations, min_salary, updated_at FROM user_preferences WHERE user_id = ?1",
+            params![user_id],
+            preferences_from_row,
+        )
+        .optional()?;
+    Ok(preferences)
+}
+
+/// Saved job preferences of every user, ordered by user id.
+pub fn get_all(conn: &mut Connection) -> Result<Vec<JobPreferences>, Box<dyn Error>> {
+    let mut stmt = conn.prepare(
+        "SELECT user_id, employment_types, locations, min_salary, updated_at FROM user_preferences ORDER BY user_id"
+    )?;
+    let preferences = stmt
+        .query_map([], preferences_from_row)?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(preferences)
+}
+
+/// Replace the job preferences of a user, trimming locations.
+pub fn set(conn: &mut Connection, user_id: UserId, request: JobPreferencesRequest) -> Result<JobPreferences, Box<dyn Error>> {
+    let updated_at = Utc::now();
+    let locations: Vec<String> = request.locations.iter().map(|location| location.trim().to_string()).collect();
+    let employment_types: Vec<String> = request.employment_types.iter().map(ToString::to_string).collect();
+    conn.execute(
+        "INSERT INTO user_preferences (user_id, employment_types, locations, min_salary, updated_at)
+         VALUES (?1, ?2, ?3, ?4, ?5)
+         ON CONFLICT(user_id) DO UPDATE SET
+             employment_types = excluded.employment_types,
+             locations = excluded.locations,
+             min_salary = excluded.min_salary,
+             updated_at = excluded.updated_at",
+        params![
+            user_id,
+            serde_json::to_string(&employment_types)?,
+            serde_json::to_string(&locations)?,
+            request.min_salary,
+            updated_at.to_rfc3339()
+        ],
+    )?;
+
+    Ok(JobPreferences {
+        user_id,
+        employment_types: request.employment_types,
+        locations,
+        min_salary: request.min_salary,
+        updated_at: Some(updated_at),
+    })
+}
+
+fn preferences_from_row(row: &Row) -> rusqlite::Result<JobPreferences> {
+    let employment_types: String = row.get(1)?;
+    let locations: String = row.get(2)?;
+    let updated_at: String = row.get(4)?;
+
+    Ok(JobPreferences {
+        user_id: row.get(0)?,
+        employment_types: serde_json::from_str(&employment_types)
+            .map_err(|e| rusqlite::Error::FromSqlConversionFailure(1, Type::Text, Box::new(e)))?,
+        locations: serde_json::from_str(&locations)
+            .map_err(|e| rusqlite::Error::FromSqlConversionFailure(2, Type::Text, Box::new(e)))?,
+        min_salary: row.get(3)?,
+        updated_at: Some(
+            DateTime::parse_from_rfc3339(&updated_at)
+                .map_err(|e| rusqlite::Error::FromSqlConversionFailure(4, Type::Text, Box::new(e)))?
+                .with_timezone(&Utc),
+        ),
+    })
+}
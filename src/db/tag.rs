@@ -0,0 +1,138 @@
+use log::debug;
+use rusqlite::{params, Connection, OptionalExtension};
+use std::error::Error;
+use crate::models::backup::JobTag;
+use crate::models::JobId;
+
+/// Normalize a tag name so that variants such as `Rust`, `rust` and ` rust ` are stored as one tag.
+pub fn normalize(name: &str) -> String {
+    name.trim().to_lowercase()
+}
+
+/// Attach every tag in `tags` to every job in `job_ids` in a single transaction.
+///
+/// Tag names are normalized before they are stored. Tags are created if they do not exist yet and tags already attached
+/// to a job are left as-is. Returns whether each job existed and was tagged.
+pub fn tag_jobs(conn: &mut Connection, job_ids: &[JobId], tags: &[String]) -> Result<Vec<(JobId, bool)>, Box<dyn Error>> {
+    let tx = conn.transaction()?;
+    let mut results = Vec::with_capacity(job_ids.len());
+    {
+        let mut tag_ids = Vec::with_capacity(tags.len());
+        for tag in tags {
+            let tag = normalize(tag);
+            tx.execute("INSERT OR IGNORE INTO tags (name) VALUES (?1)", params![tag])?;
+            let tag_id: i64 = tx.query_row("SELECT id FROM tags WHERE name = ?1", params![tag], |row| row.get(0))?;
+            tag_ids.push(tag_id);
+        }
+
+        let mut exists = tx.prepare("SELECT 1 FROM jobs WHERE id = ?1")?;
+        let mut insert = tx.prepare("INSERT OR IGNORE INTO job_tags (job_id, tag_id) VALUES (?1, ?2)")?;
+        for &job_id in job_ids {
+            let found = exists.query_row(params![job_id], |_| Ok(())).optional()?.is_some();
+            if found {
+                for &tag_id in &tag_ids {
+                    insert.execute(params![job_id, tag_id])?;
+                }
+            }
+            results.push((job_id, found));
+        }
+    }
+    tx.commit()?;
+    debug!("Bulk tagged jobs with {:?}: {:?}", tags, results);
+    Ok(results)
+}
+
+/// Add and remove tags of a single job in one transaction and return its resulting tags.
+///
+/// Tag names are expected to be normalized already. Removes of tags the job does not have are ignored. Returns `None`
+/// if the job does not exist.
+pub fn patch_job_tags(
+    conn: &mut Connection,
+    job_id: JobId,
+    add: &[String],
+    remove: &[String],
+) -> Result<Option<Vec<String>>, Box<dyn Error>> {
+    let tx = conn.transaction()?;
+    let found = tx
+        .query_row("SELECT 1 FROM jobs WHERE id = ?1", params![job_id], |_| Ok(()))
+        .optional()?
+        .is_some();
+    if !found {
+        return Ok(None);
+    }
+
+    for tag in add {
+        tx.execute("INSERT OR IGNORE INTO tags (name) VALUES (?1)", params![tag])?;
+        tx.execute(
+            "INSERT OR IGNORE INTO job_tags (job_id, tag_id) SELECT ?1, id FROM tags WHERE name = ?2",
+            params![job_id, tag],
+        )?;
+    }
+    for tag in remove {
+        tx.execute(
+            "DELETE FROM job_tags WHERE job_id = ?1 AND tag_id IN (SELECT id FROM tags WHERE name = ?2)",
+            params![job_id, tag],
+        )?;
+    }
+
+    let tags = get_for_job(&tx, job_id)?;
+    tx.commit()?;
+    debug!("Patched tags of job {} (add {:?}, remove {:?}): {:?}", job_id, add, remove, tags);
+    Ok(Some(tags))
+}
+
+/// Names of the tags attached to a job, in alphabetical order.
+pub fn get_for_job(conn: &Connection, job_id: JobId) -> Result<Vec<String>, Box<dyn Error>> {
+    let mut stmt = conn.prepare(
+        "SELECT tags.name FROM job_tags
+         JOIN tags ON tags.id = job_tags.tag_id
+         WHERE job_tags.job_id = ?1
+         ORDER BY tags.name"
+    )?;
+    let tags = stmt
+        .query_map(params![job_id], |row| row.get(0))?
+        .collect::<Result<Vec<String>, _>>()?;
+    Ok(tags)
+}
+
+/// Every tag attached to a job, ordered by job and tag name.
+pub fn get_all_job_tags(conn: &mut Connection) -> Result<Vec<JobTag>, Box<dyn Error>> {
+    let mut stmt = conn.prepare(
+        "SELECT job_tags.job_id, tags.name FROM job_tags
+         JOIN tags ON tags.id = job_tags.tag_id
+         ORDER BY job_tags.job_id, tags.name"
+    )?;
+    let job_tags = stmt
+        .query_map([], |row| Ok(JobTag { job_id: row.get(0)?, tag: row.get(1)? }))?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(job_tags)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::models::JobId;
+    use crate::utils::testing::TestDb;
+    use super::{get_for_job, tag_jobs};
+
+    #[test]
+    fn variants_of_a_tag_are_stored_once() {
+        let db = TestDb::new();
+        let job = db.job(db.user("employer@example.com", "employer", "!"));
+        let mut conn = db.conn();
+
+        let tags = ["Rust", "rust", " rust "].map(String::from);
+        tag_jobs(&mut conn, &[JobId(job)], &tags).unwrap();
+        tag_jobs(&mut conn, &[JobId(job)], &["RUST ".to_string()]).unwrap();
+
+        let stored: Vec<String> = conn
+            .prepare("SELECT name FROM tags")
+            .unwrap()
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(stored, ["rust"]);
+        assert_eq!(get_for_job(&conn, JobId(job)).unwrap(), ["rust"]);
+        assert!(conn.execute("INSERT INTO tags (name) VALUES (' Rust')", []).is_err());
+    }
+}
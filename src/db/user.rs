@@ -1,65 +1,229 @@
-use crate::models::{User, UserRole};
+use crate::models::{JobId, User, UserId, UserRole};
 use log::{debug, error};
-use rusqlite::{params, Connection};
+use rusqlite::{params, params_from_iter, Connection, Row, TransactionBehavior};
+use crate::db::{collect_rows, like_pattern};
 use std::error::Error;
 use chrono::{DateTime, Utc};
-use crate::models::user::UserUpdateRequest;
+use crate::models::application::Applicant;
+use crate::models::user::{ActivityEvent, ActivityKind, Employer, UserUpdateRequest};
+use rusqlite::types::Type;
 
 pub fn get_all(
     conn: &mut Connection,
     limit: i64,
     offset: i64,
 ) -> Result<Vec<User>, Box<dyn Error>> {
+    Ok(get_page(conn, limit, offset, true)?.0)
+}
+
+/// A page of users, along with the number of rows skipped because they could not be decoded.
+///
+/// Malformed rows fail the whole page when `strict` is set and are skipped otherwise.
+pub fn get_page(
+    conn: &mut Connection,
+    limit: i64,
+    offset: i64,
+    strict: bool,
+) -> Result<(Vec<User>, usize), Box<dyn Error>> {
     let mut stmt = conn.prepare(
-        "SELECT id, name, email, password, role, created_at, updated_at
+        "SELECT id, name, email, password, role, created_at, updated_at, bio, avatar_url, phone
          FROM users LIMIT ?1 OFFSET ?2"
     )?;
-    let user_iter = stmt.query_map(params![limit, offset], |row| {
-        let created_at: String = row.get(5)?;
-        let updated_at: String = row.get(6)?;
+    let rows = stmt.query_map(params![limit, offset], user_from_row)?;
+    Ok(collect_rows(rows, strict)?)
+}
 
-        Ok(User {
-            id: row.get(0)?,
-            name: row.get(1)?,
-            email: row.get(2)?,
-            password: row.get(3)?,
-            role: row.get(4)?,
-            created_at: DateTime::parse_from_rfc3339(&created_at).unwrap().with_timezone(&Utc),
-            updated_at: DateTime::parse_from_rfc3339(&updated_at).unwrap().with_timezone(&Utc),
-        })
-    })?;
-
-    let mut users = Vec::new();
-    for user in user_iter {
-        users.push(user?);
+/// Users with any of the given ids, ordered by id. Unknown ids are ignored.
+pub fn get_by_ids(conn: &mut Connection, ids: &[UserId]) -> Result<Vec<User>, Box<dyn Error>> {
+    if ids.is_empty() {
+        return Ok(Vec::new());
     }
+
+    let placeholders = vec!["?"; ids.len()].join(", ");
+    let mut stmt = conn.prepare(&format!(
+        "SELECT id, name, email, password, role, created_at, updated_at, bio, avatar_url, phone
+         FROM users WHERE id IN ({}) ORDER BY id",
+        placeholders
+    ))?;
+    let users = stmt
+        .query_map(params_from_iter(ids), user_from_row)?
+        .collect::<Result<Vec<_>, _>>()?;
     Ok(users)
 }
 
-pub fn create(conn: &mut Connection, user: UserUpdateRequest) -> Result<(), Box<dyn Error>> {
+/// Users whose name or email contains `term`, ordered by id, along with the total number of matches.
+pub fn search(conn: &mut Connection, term: &str, limit: i64, offset: i64) -> Result<(Vec<User>, i64), Box<dyn Error>> {
+    let pattern = like_pattern(term);
+    let count: i64 = conn.query_row(
+        "SELECT COUNT(*) FROM users WHERE fold(name) LIKE ?1 ESCAPE '\\' OR fold(email) LIKE ?1 ESCAPE '\\'",
+        params![pattern],
+        |row| row.get(0),
+    )?;
+    let mut stmt = conn.prepare(
+        "SELECT id, name, email, password, role, created_at, updated_at, bio, avatar_url, phone
+         FROM users WHERE fold(name) LIKE ?1 ESCAPE '\\' OR fold(email) LIKE ?1 ESCAPE '\\'
+         ORDER BY id LIMIT ?2 OFFSET ?3"
+    )?;
+    let users = stmt
+        .query_map(params![pattern, limit, offset], user_from_row)?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok((users, count))
+}
+
+/// Distinct job seekers who applied to any job of an employer, ordered by id, along with the total number of them.
+///
+pub fn get_applicants_of_employer(
+    conn: &mut Connection,
+    employer_id: UserId,
+    limit: i64,
+    offset: i64,
+) -> Result<(Vec<Applicant>, i64), Box<dyn Error>> {
+    let count: i64 = conn.query_row(
+        "SELECT COUNT(DISTINCT applications.job_seeker_id) FROM applications
+         JOIN jobs ON jobs.id = applications.job_id
+         WHERE jobs.employer_id = ?1",
+        params![employer_id],
+        |row| row.get(0),
+    )?;
+    let mut stmt = conn.prepare(
+        "SELECT users.id, users.name, users.email, users.password, users.role, users.created_at, users.updated_at,
+                users.bio, users.avatar_url, users.phone, COUNT(applications.id), GROUP_CONCAT(DISTINCT applications.job_id)
+         FROM applications
+         JOIN jobs ON jobs.id = applications.job_id
+         JOIN users ON users.id = applications.job_seeker_id
+         WHERE jobs.employer_id = ?1
+         GROUP BY users.id
+         ORDER BY users.id
+         LIMIT ?2 OFFSET ?3"
+    )?;
+    let applicants = stmt
+        .query_map(params![employer_id, limit, offset], |row| {
+            let job_ids: String = row.get(11)?;
+            let mut job_ids: Vec<JobId> = job_ids.split(',').filter_map(|id| id.parse().ok()).collect();
+            job_ids.sort_unstable();
+            Ok(Applicant {
+                user: user_from_row(row)?,
+                application_count: row.get(10)?,
+                job_ids,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok((applicants, count))
+}
+
+/// Employers ordered by id with their number of open jobs, along with the total number of matching employers.
+///
+/// A job counts as open when its status is `open` and its deadline, if any, has not passed at `now`. When
+/// `has_open_jobs` is set, only employers with at least one open job are returned.
+pub fn get_employers(
+    conn: &mut Connection,
+    has_open_jobs: bool,
+    now: DateTime<Utc>,
+    limit: i64,
+    offset: i64,
+) -> Result<(Vec<Employer>, i64), Box<dyn Error>> {
+    let now = now.to_rfc3339();
+    let count: i64 = conn.query_row(
+        "SELECT COUNT(*) FROM users
+         WHERE users.role = 'employer'
+           AND (NOT ?1 OR EXISTS (SELECT 1 FROM jobs WHERE jobs.employer_id = users.id AND jobs.status = 'open'
+                                  AND (jobs.deadline IS NULL OR jobs.deadline >= ?2)))",
+        params![has_open_jobs, now],
+        |row| row.get(0),
+    )?;
+    let mut stmt = conn.prepare(
+        "SELECT users.id, users.name, users.email, users.password, users.role, users.created_at, users.updated_at,
+                users.bio, users.avatar_url, users.phone, COUNT(jobs.id)
+         FROM users
+         LEFT JOIN jobs ON jobs.employer_id = users.id AND jobs.status = 'open'
+                       AND (jobs.deadline IS NULL OR jobs.deadline >= ?2)
+         WHERE users.role = 'employer'
+         GROUP BY users.id
+         HAVING NOT ?1 OR COUNT(jobs.id) > 0
+         ORDER BY users.id
+         LIMIT ?3 OFFSET ?4"
+    )?;
+    let employers = stmt
+        .query_map(params![has_open_jobs, now, limit, offset], |row| {
+            Ok(Employer {
+                user: user_from_row(row)?,
+                open_job_count: row.get(10)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok((employers, count))
+}
+
+/// Insert `user`, returning the id the database assigned to it.
+pub fn create(conn: &mut Connection, user: UserUpdateRequest) -> Result<UserId, Box<dyn Error>> {
     conn.execute(
-        "INSERT INTO users (name, email, password, role, created_at, updated_at)
-         VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        "INSERT INTO users (name, email, password, role, created_at, updated_at, bio, avatar_url, phone)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
         params![
             user.name,
             user.email,
             user.password,
-            user.role.unwrap_or(UserRole::JobSeeker) as i32,
+            user.role.unwrap_or(UserRole::JobSeeker),
             Utc::now().to_rfc3339(),
             Utc::now().to_rfc3339(),
+            user.bio,
+            user.avatar_url,
+            user.phone,
         ],
     )?;
-    Ok(())
+    Ok(UserId(conn.last_insert_rowid()))
 }
 
-pub fn delete(conn: &mut Connection, id: i64) -> Result<(), Box<dyn Error>> {
+/// Create the user with the given email from `user`, or update the fields `user` sets if it already exists.
+///
+/// Runs in an immediate transaction so concurrent calls for the same email cannot both create the user. Returns the
+/// stored user and whether it was created. Creating requires `user` to set a name and a password.
+pub fn upsert_by_email(conn: &mut Connection, email: &str, user: UserUpdateRequest) -> Result<(User, bool), Box<dyn Error>> {
+    let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+    let now = Utc::now().to_rfc3339();
+    let updated = tx.execute(
+        "UPDATE users
+         SET name = COALESCE(?1, name), password = COALESCE(?2, password), role = COALESCE(?3, role),
+             updated_at = ?4, bio = COALESCE(?5, bio), avatar_url = COALESCE(?6, avatar_url), phone = COALESCE(?7, phone)
+         WHERE email = ?8",
+        params![user.name, user.password, user.role, now, user.bio, user.avatar_url, user.phone, email],
+    )?;
+    let created = updated == 0;
+    if created {
+        tx.execute(
+            "INSERT INTO users (name, email, password, role, created_at, updated_at, bio, avatar_url, phone)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?5, ?6, ?7, ?8)",
+            params![
+                user.name,
+                email,
+                user.password,
+                user.role.unwrap_or(UserRole::JobSeeker),
+                now,
+                user.bio,
+                user.avatar_url,
+                user.phone,
+            ],
+        )?;
+    }
+    let stored = tx.query_row(
+        "SELECT id, name, email, password, role, created_at, updated_at, bio, avatar_url, phone
+         FROM users WHERE email = ?1",
+        params![email],
+        user_from_row,
+    )?;
+    tx.commit()?;
+    debug!("User with email {} {}.", email, if created { "created" } else { "updated" });
+    Ok((stored, created))
+}
+
+pub fn delete(conn: &mut Connection, id: UserId) -> Result<(), Box<dyn Error>> {
     conn.execute("DELETE FROM users WHERE id = ?1", params![id])?;
     Ok(())
 }
 
-pub fn get_by_id(conn: &mut Connection, id: i64) -> Result<Option<User>, Box<dyn Error>> {
+pub fn get_by_id(conn: &mut Connection, id: UserId) -> Result<Option<User>, Box<dyn Error>> {
     let mut stmt = conn.prepare(
-        "SELECT id, name, email, password, role, created_at, updated_at
+        "SELECT id, name, email, password, role, created_at, updated_at, bio, avatar_url, phone
          FROM users WHERE id = ?1"
     )?;
     let mut rows = stmt.query(params![id])?;
@@ -76,6 +240,9 @@ pub fn get_by_id(conn: &mut Connection, id: i64) -> Result<Option<User>, Box<dyn
             role: row.get(4)?,
             created_at: DateTime::parse_from_rfc3339(&created_at)?.with_timezone(&Utc),
             updated_at: DateTime::parse_from_rfc3339(&updated_at)?.with_timezone(&Utc),
+            bio: row.get(7)?,
+            avatar_url: row.get(8)?,
+            phone: row.get(9)?,
         };
         debug!("USER: {:#?}", user);
         Ok(Some(user))
@@ -85,18 +252,31 @@ pub fn get_by_id(conn: &mut Connection, id: i64) -> Result<Option<User>, Box<dyn
     }
 }
 
-pub fn update(conn: &mut Connection, id: i64, user: User) -> Result<(), Box<dyn Error>> {
+pub fn get_by_email(conn: &mut Connection, email: &str) -> Result<Option<User>, Box<dyn Error>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, name, email, password, role, created_at, updated_at, bio, avatar_url, phone
+         FROM users WHERE email = ?1"
+    )?;
+    let mut rows = stmt.query_map(params![email], user_from_row)?;
+    Ok(rows.next().transpose()?)
+}
+
+pub fn update(conn: &mut Connection, id: UserId, user: User) -> Result<(), Box<dyn Error>> {
     conn.execute(
         "UPDATE users
          SET name = COALESCE(?1, name), email = COALESCE(?2, email), password = COALESCE(?3, password),
-             role = COALESCE(?4, role), updated_at = ?5
-         WHERE id = ?6",
+             role = COALESCE(?4, role), updated_at = ?5, bio = COALESCE(?6, bio),
+             avatar_url = COALESCE(?7, avatar_url), phone = COALESCE(?8, phone)
+         WHERE id = ?9",
         params![
             user.name,
             user.email,
             user.password,
             user.role,
             Utc::now().to_rfc3339(),
+            user.bio,
+            user.avatar_url,
+            user.phone,
             id,
         ],
     )?;
@@ -108,4 +288,76 @@ pub fn get_total_count(conn: &mut Connection) -> Result<i64, Box<dyn Error>> {
     let mut stmt = conn.prepare("SELECT COUNT(*) FROM users")?;
     let count: i64 = stmt.query_row([], |row| row.get(0))?;
     Ok(count)
-}
\ No newline at end of file
+}
+
+fn user_from_row(row: &Row) -> rusqlite::Result<User> {
+    let created_at: String = row.get(5)?;
+    let updated_at: String = row.get(6)?;
+
+    Ok(User {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        email: row.get(2)?,
+        password: row.get(3)?,
+        role: row.get(4)?,
+        created_at: DateTime::parse_from_rfc3339(&created_at).unwrap().with_timezone(&Utc),
+        updated_at: DateTime::parse_from_rfc3339(&updated_at).unwrap().with_timezone(&Utc),
+        bio: row.get(7)?,
+        avatar_url: row.get(8)?,
+        phone: row.get(9)?,
+    })
+}
+
+/// Activity of a job seeker, newest first: applications they submitted and status changes of those applications.
+///
+/// With `after`, the `type` and id of an event, only events older than it are returned; an unknown event yields none.
+pub fn get_activity(
+    conn: &mut Connection,
+    job_seeker_id: UserId,
+    after: Option<(ActivityKind, i64)>,
+    limit: i64,
+) -> Result<Vec<ActivityEvent>, Box<dyn Error>> {
+    let mut stmt = conn.prepare(
+        "WITH events AS (
+             SELECT 'application_submitted' AS kind, applications.id AS event_id, applications.id AS application_id,
+                    applications.job_id, NULL AS status, applications.applied_at AS at
+             FROM applications WHERE applications.job_seeker_id = ?1
+             UNION ALL
+             SELECT 'status_changed', changes.id, changes.application_id, applications.job_id, changes.status, changes.changed_at
+             FROM application_status_changes AS changes
+             JOIN applications ON applications.id = changes.application_id
+             WHERE applications.job_seeker_id = ?1
+         )
+         SELECT events.kind, events.event_id, events.application_id, events.job_id, jobs.title, events.status, events.at
+         FROM events
+         LEFT JOIN jobs ON jobs.id = events.job_id
+         WHERE ?2 IS NULL
+            OR (events.at, events.kind, events.event_id)
+               < (SELECT at, kind, event_id FROM events WHERE kind = ?2 AND event_id = ?3)
+         ORDER BY events.at DESC, events.kind DESC, events.event_id DESC
+         LIMIT ?4",
+    )?;
+    let (kind, event_id) = after.map_or((None, None), |(kind, event_id)| (Some(kind.as_str()), Some(event_id)));
+    let events = stmt
+        .query_map(params![job_seeker_id, kind, event_id, limit], activity_from_row)?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(events)
+}
+
+fn activity_from_row(row: &Row) -> rusqlite::Result<ActivityEvent> {
+    let kind: String = row.get(0)?;
+    let at: String = row.get(6)?;
+
+    Ok(ActivityEvent {
+        kind: ActivityKind::parse(&kind)
+            .ok_or_else(|| rusqlite::Error::FromSqlConversionFailure(0, Type::Text, format!("unknown activity {}", kind).into()))?,
+        event_id: row.get(1)?,
+        application_id: row.get(2)?,
+        job_id: row.get(3)?,
+        job_title: row.get(4)?,
+        status: row.get(5)?,
+        at: DateTime::parse_from_rfc3339(&at)
+            .map_err(|e| rusqlite::Error::FromSqlConversionFailure(6, Type::Text, Box::new(e)))?
+            .with_timezone(&Utc),
+    })
+}
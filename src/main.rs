@@ -3,22 +3,52 @@ mod db;
 mod routes;
 mod utils;
 mod auth;
+mod config;
 
+use std::rc::Rc;
+use std::str::FromStr;
+use std::time::Duration;
 use actix_cors::Cors;
-use actix_web::middleware::Logger;
-use actix_web::web::Data;
+use actix_web::middleware::{from_fn, Compress, Condition, Logger, NormalizePath};
+use actix_web::web::{Data, JsonConfig, PathConfig};
+use actix_web::http::header::HeaderName;
 use actix_web::{web, App, HttpServer};
 use dotenv::dotenv;
+use log::warn;
 use utoipa::{
     openapi::security::{ApiKey, ApiKeyValue, SecurityScheme},
-    Modify, OpenApi,
+    openapi::server::Server,
+    OpenApi,
 };
 use utoipa_swagger_ui::SwaggerUi;
-use crate::models::{ApplicationStore, JobStore, UserStore};
+use crate::models::{ApplicationStore, JobReads, JobStore, UserStore};
+use crate::utils::background::BackgroundTasks;
+use crate::utils::{auto_reject, digest, queue};
+use crate::utils::envelope::wrap_in_envelope;
+use crate::utils::timezone::{convert_timestamps, TIMEZONE_HEADER};
+use crate::utils::https::force_https;
+use crate::utils::limits::limit_query_length;
 use crate::utils::init_db::initialize_database;
-use crate::utils::{PaginationUser, PaginationJob, PaginationApplication, ErrorResponse};
-use crate::models::{User, Job, Application, UserRole, EmploymentType, ApplicationStatus};
-use crate::routes::{user, job, application};
+use crate::utils::compress::skip_small_compression;
+use crate::utils::content_type::require_json;
+use crate::utils::maintenance::{block_writes, MaintenanceMode};
+use crate::utils::readiness::{require_ready, Readiness};
+use crate::utils::seed::seed_if_empty;
+use crate::utils::{json_error_handler, path_error_handler, PAGINATION_DRIFT_HEADER, SKIPPED_ROWS_HEADER, PaginationUser, PaginationJob, PaginationJobChange, PaginationApplicant, PaginationEmployer, PaginationActivity, PaginationApplication, ErrorResponse, FieldError, ValidationResponse};
+use crate::models::{User, Job, Application, UserRole, EmploymentType, JobStatus, ApplicationStatus, UserId, JobId, ApplicationId};
+use crate::models::job::{JobApplicationCount, RecommendedJob, JobBulkDeleteRequest, JobChange, JobBulkDeleteResult, JobDeleteStatus, JobCloseAllResult, JobComparison, JobDeleteImpact, JobDraft, JobFieldComparison, JobImportReport, JobImportResult, JobLocation, JobPreview, JobTagPatch, JobTagRequest, JobTagResult, ReviewQueueItem};
+use crate::models::application::{Applicant, ApplicationAttachment, ApplicationNote, NoteRequest, RatingRequest, RejectPendingRequest, RejectPendingResult, ApplicationStatusCount, AttachmentKind, AttachmentRequest};
+use crate::models::backup::{ApplicationStatusChange, Backup, JobTag, RestoreSummary};
+use crate::models::user::{ActivityEvent, ActivityKind, Employer, JobPreferences, JobPreferencesRequest, ProfileCompleteness};
+use crate::routes::{user, job, application, admin, meta};
+use crate::routes::admin::{Maintenance, SearchResults};
+use crate::routes::meta::{EnumValues, Health, Version};
+use crate::routes::auth::{AccessToken, LoginRequest};
+use crate::config::features::{Feature, FeatureFlag, FeatureFlags};
+use crate::config::Settings;
+use crate::db::ReadPool;
+use crate::auth::jwt::Jwt;
+use crate::auth::middleware::{LogApiKey, RequireApiKey};
 
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
@@ -26,9 +56,32 @@ async fn main() -> std::io::Result<()> {
     std::env::set_var("RUST_LOG", "debug");
     env_logger::init();
 
-    match initialize_database() {
-        Ok(()) => println!("Database initialized successfully."),
-        Err(err) => eprintln!("Failed to initialize the database: {}", err),
+    let settings = Settings::from_env();
+    let features = FeatureFlags::from_env();
+    config::log_effective(&settings, &features);
+
+    // The database is set up while the server already accepts connections; `/v1` requests get 503 until it is ready.
+    let readiness = Readiness::default();
+    {
+        let readiness = readiness.clone();
+        let database_url = settings.database_url.clone();
+        let seed_on_empty = settings.seed_on_empty;
+        actix_web::rt::task::spawn_blocking(move || {
+            match initialize_database() {
+                Ok(()) => println!("Database initialized successfully."),
+                Err(err) => {
+                    eprintln!("Failed to initialize the database: {}", err);
+                    return;
+                }
+            }
+
+            if seed_on_empty {
+                if let Err(err) = seed_if_empty(&database_url) {
+                    eprintln!("Failed to seed the database: {}", err);
+                }
+            }
+            readiness.mark_ready();
+        });
     }
 
     #[derive(OpenApi)]
@@ -43,88 +96,296 @@ async fn main() -> std::io::Result<()> {
         paths(
             user::get_users,
             user::get_user_by_id,
+            user::get_employers,
+            user::ensure_user,
+            user::get_completeness,
+            user::get_preferences,
+            user::set_preferences,
+            user::get_activity,
+            user::get_application_statuses,
             user::create_user,
             user::update_user,
             user::delete_user,
             job::get_jobs,
+            job::get_job_locations,
+            job::compare_jobs,
             job::get_job_by_id,
+            job::get_job_preview,
+            job::validate_job,
             job::create_job,
             job::update_job,
             job::delete_job,
+            job::get_delete_impact,
+            job::delete_jobs,
+            job::tag_jobs,
+            job::import_jobs,
+            job::patch_job_tags,
+            job::get_review_queue,
+            job::get_application_counts,
+            job::get_closed_jobs,
+            job::close_all_jobs,
+            job::get_recommended_jobs,
             application::get_applications,
             application::get_application_by_id,
+            application::get_application_by_reference,
             application::create_application,
             application::update_application,
             application::delete_application,
+            application::reopen_application,
+            application::reject_pending_applications,
+            application::get_job_applications,
+            application::rate_application,
+            application::get_job_application_summary,
+            application::get_user_application_summary,
+            application::get_employer_applicants,
+            application::get_job_application,
+            application::get_application_attachments,
+            application::create_application_attachment,
+            application::delete_application_attachment,
+            application::get_application_notes,
+            application::create_application_note,
+            application::get_application_pdf,
+            admin::get_config,
+            admin::get_features,
+            admin::get_backup,
+            admin::restore_backup,
+            admin::search,
+            admin::get_maintenance,
+            admin::set_maintenance,
+            meta::get_health,
+            meta::get_version,
+            meta::get_enums,
+            routes::auth::login,
         ),
         components(
             schemas(
+                UserId,
+                JobId,
+                ApplicationId,
                 User,
                 UserRole,
                 Job,
                 EmploymentType,
+                JobStatus,
+                JobBulkDeleteRequest,
+                JobBulkDeleteResult,
+                JobDeleteStatus,
+                JobCloseAllResult,
+                JobPreview,
+                JobTagRequest,
+                JobTagPatch,
+                JobImportResult,
+                JobComparison,
+                JobApplicationCount,
+                JobDeleteImpact,
+                JobDraft,
+                RecommendedJob,
+                JobFieldComparison,
+                JobImportReport,
+                JobTagResult,
+                ReviewQueueItem,
+                JobLocation,
                 Application,
                 ApplicationStatus,
+                ApplicationStatusCount,
+                ApplicationAttachment,
+                ApplicationNote,
+                NoteRequest,
+                RatingRequest,
+                RejectPendingRequest,
+                RejectPendingResult,
+                AttachmentRequest,
+                AttachmentKind,
                 PaginationUser,
                 PaginationJob,
+                PaginationJobChange,
+                PaginationApplicant,
+                PaginationEmployer,
+                Employer,
+                JobPreferences,
+                JobPreferencesRequest,
+                ProfileCompleteness,
+                ActivityEvent,
+                ActivityKind,
+                PaginationActivity,
+                Applicant,
+                JobChange,
                 PaginationApplication,
-                ErrorResponse
+                ErrorResponse,
+                FieldError,
+                ValidationResponse,
+                Settings,
+                Feature,
+                FeatureFlag,
+                Backup,
+                JobTag,
+                ApplicationStatusChange,
+                RestoreSummary,
+                SearchResults,
+                Maintenance,
+                EnumValues,
+                Health,
+                Version,
+                LoginRequest,
+                AccessToken
             )
         ),
         tags(
             (name = "users", description = "User endpoints."),
             (name = "jobs", description = "Job endpoints."),
-            (name = "applications", description = "Application endpoints.")
+            (name = "applications", description = "Application endpoints."),
+            (name = "admin", description = "Administration endpoints."),
+            (name = "auth", description = "Authentication endpoints."),
+            (name = "meta", description = "API metadata endpoints.")
         ),
-        modifiers(&SecurityAddon)
     )]
     struct ApiDoc;
 
-    struct SecurityAddon;
-
-    impl Modify for SecurityAddon {
-        fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
-            let components = openapi.components.as_mut().unwrap();
-            components.add_security_scheme(
-                "api_key",
-                SecurityScheme::ApiKey(ApiKey::Header(ApiKeyValue::new("Authorization"))),
-            )
-        }
-    }
-
+    let pool_timeout = Duration::from_secs(settings.db_pool_timeout_secs);
+    let pool = db::pool(&settings.database_url, settings.db_pool_size, pool_timeout);
+    let read_pool = Data::new(match &settings.read_database_url {
+        Some(read_database_url) => ReadPool::open(read_database_url, settings.db_pool_size, pool_timeout),
+        None => ReadPool::primary(pool.clone()),
+    });
+    let pool = Data::new(pool);
     let user_store = Data::new(UserStore::default());
     let job_store = Data::new(JobStore::default());
+    let job_reads = Data::new(JobReads::default());
     let application_store = Data::new(ApplicationStore::default());
+    let bind_address = (settings.host.clone(), settings.port);
+    let shutdown_timeout = Duration::from_secs(settings.shutdown_timeout_secs);
+    let max_query_length = settings.max_query_length;
+    let https_only = settings.force_https;
+    let workers = settings.server_workers;
+    let compress_min_bytes = settings.compress_min_bytes;
+    let cors_max_age = settings.cors_max_age;
+    let maintenance = MaintenanceMode::new(settings.maintenance_mode);
+    let maintenance_data = Data::new(maintenance.clone());
+    let convert_timezones = features.is_enabled(Feature::TimezoneConversion);
+    let features = Data::new(features);
+    let cors_allowed_methods = settings.cors_allowed_methods.clone();
+    let cors_allowed_headers = settings.cors_allowed_headers.clone();
+    let background_tasks = BackgroundTasks::default();
+    let tasks = Data::new(background_tasks.clone());
+    let (work_queue, work_queue_worker) = queue::start(settings.work_queue_capacity, settings.work_queue_block_when_full);
+    background_tasks.spawn("work_queue", work_queue_worker);
+    auto_reject::start(&background_tasks, pool.get_ref().clone(), readiness.clone(), settings.auto_reject_interval_secs);
+    if settings.digest_enabled {
+        digest::start(
+            &background_tasks,
+            pool.get_ref().clone(),
+            readiness.clone(),
+            settings.digest_interval_secs,
+            work_queue.clone(),
+        );
+    }
+    let api_key_name = HeaderName::from_str(&settings.api_key_name).expect("API_KEY_NAME is validated by Settings");
+    let api_key = settings.api_key.clone().unwrap_or_default();
+    let require_api_key = settings.api_key.is_some() && !settings.api_key_log_only;
+    let log_api_key = settings.api_key.is_some() && settings.api_key_log_only;
+    let mount_admin = settings.api_key.is_some();
+    if !mount_admin {
+        warn!("API_KEY is not set, requests to the API are not authenticated and the /v1/admin endpoints are not mounted");
+    }
+    let jwt = Data::new(match &settings.jwt_secret {
+        Some(secret) => Jwt::new(secret.as_bytes(), settings.jwt_ttl_secs),
+        None => {
+            warn!("JWT_SECRET is not set, access tokens are signed with a random secret and stop working on restart");
+            Jwt::with_random_secret(settings.jwt_ttl_secs)
+        }
+    });
+    let settings = Data::new(settings);
+    let queue = Data::new(work_queue.clone());
 
-    let openapi = ApiDoc::openapi();
+    let mut openapi = ApiDoc::openapi();
+    if let Some(components) = openapi.components.as_mut() {
+        components.add_security_scheme(
+            "api_key",
+            SecurityScheme::ApiKey(ApiKey::Header(ApiKeyValue::new(&settings.api_key_name))),
+        );
+    }
+    let base_path = settings.api_base_path.clone();
+    if !base_path.is_empty() {
+        openapi.servers = Some(vec![Server::new(base_path.clone())]);
+    }
 
-    HttpServer::new(move || {
+    let mut server = HttpServer::new(move || {
         let cors = Cors::default()
             .allow_any_origin() // Change this if you don't want to allow any origin to access the API
-            .allowed_methods(vec!["GET", "POST", "PUT", "DELETE", "OPTIONS"])
-            .allowed_headers(vec!["Accept", "Content-Type", "Authorization"])
+            .allowed_methods(cors_allowed_methods.iter().map(String::as_str))
+            .allowed_headers(cors_allowed_headers.iter().map(String::as_str))
+            .expose_headers(vec![PAGINATION_DRIFT_HEADER, SKIPPED_ROWS_HEADER, TIMEZONE_HEADER])
             .supports_credentials()
-            .max_age(3600);
+            .max_age(cors_max_age);
+        let health_path: Rc<str> = Rc::from(settings.api_path("/health"));
+        let https_health_path = health_path.clone();
 
         let app = App::new()
             .wrap(Logger::default())
+            .app_data(pool.clone())
+            .app_data(read_pool.clone())
             .app_data(user_store.clone())
             .app_data(job_store.clone())
             .app_data(application_store.clone())
+            .app_data(tasks.clone())
+            .app_data(queue.clone())
+            .app_data(maintenance_data.clone())
+            .app_data(features.clone())
+            .app_data(PathConfig::default().error_handler(path_error_handler))
+            .app_data(JsonConfig::default().error_handler(json_error_handler))
+            .wrap(from_fn(move |req, next| convert_timestamps(convert_timezones, req, next)))
+            .wrap(from_fn(wrap_in_envelope))
+            .wrap(from_fn(move |req, next| skip_small_compression(compress_min_bytes, req, next)))
+            .wrap(Compress::default())
+            .wrap(from_fn(move |req, next| limit_query_length(max_query_length, req, next)))
+            .wrap(from_fn(move |req, next| force_https(https_only, https_health_path.clone(), req, next)))
             .wrap(cors)
+            // Trim trailing slashes so `/v1/jobs/` and `/v1/jobs` reach the same handler.
+            .wrap(NormalizePath::trim())
             .configure(|cfg| {
-                cfg.service(web::scope("/v1")
+                let readiness = readiness.clone();
+                let health_path = health_path.clone();
+                let maintenance = maintenance.clone();
+                let maintenance_path: Rc<str> = Rc::from(settings.api_path("/admin/maintenance"));
+                let import_path: Rc<str> = Rc::from(settings.api_path("/jobs/import"));
+                let api_key = auth::middleware::ApiKey::new(api_key_name.clone(), &api_key)
+                    .with_public_paths([settings.api_path("/health"), settings.api_path("/version")]);
+                cfg.service(web::scope(&settings.api_path(""))
+                    .wrap(from_fn(move |req, next| require_json(import_path.clone(), req, next)))
+                    .wrap(from_fn(move |req, next| block_writes(maintenance.clone(), maintenance_path.clone(), req, next)))
+                    .wrap(from_fn(move |req, next| require_ready(readiness.clone(), health_path.clone(), req, next)))
+                    .wrap(Condition::new(require_api_key, RequireApiKey(api_key.clone())))
+                    .wrap(Condition::new(log_api_key, LogApiKey(api_key.clone())))
                     .configure(|scope| {
                         user::configure(user_store.clone())(scope);
+                        job::configure(job_store.clone(), job_reads.clone())(scope);
+                        application::configure(application_store.clone())(scope);
+                        admin::configure(settings.clone(), mount_admin.then(|| api_key.clone()))(scope);
+                        meta::configure()(scope);
+                        routes::auth::configure(jwt.clone())(scope);
                     }));
             })
+            // `NormalizePath` turns `/swagger-ui/` into `/swagger-ui`, which the UI's wildcard route does not match.
+            .service(web::redirect(
+                format!("{}/swagger-ui", base_path),
+                format!("{}/swagger-ui/index.html", base_path),
+            ))
             .service(
-                SwaggerUi::new("/swagger-ui/{_:.*}").url("/api-docs/openapi.json", openapi.clone()),
+                SwaggerUi::new(format!("{}/swagger-ui/{{_:.*}}", base_path))
+                    .url(format!("{}/api-docs/openapi.json", base_path), openapi.clone()),
             );
 
         app
-    })
-        .bind(("0.0.0.0", 8080))?
+    });
+    if let Some(workers) = workers {
+        server = server.workers(workers);
+    }
+    server
+        .bind(bind_address)?
         .run()
-        .await
+        .await?;
+
+    work_queue.close().await;
+    background_tasks.shutdown(shutdown_timeout).await;
+    Ok(())
 }
\ No newline at end of file
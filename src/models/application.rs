@@ -1,26 +1,27 @@
 use std::fmt;
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Datelike, Utc};
 use rusqlite::ToSql;
 use rusqlite::types::{FromSql, FromSqlResult, ToSqlOutput, ValueRef};
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
 use utoipa::ToSchema;
+use crate::models::{deserialize_variant, ApplicationId, InvalidEnumValue, JobId, User, UserId};
 
 /// Application object
 #[derive(Serialize, Deserialize, ToSchema, Clone, Debug)]
 pub struct Application {
     /// Unique table id for the Application.
     #[schema(example = 1)]
-    pub id: i64,
+    pub id: ApplicationId,
     /// Foreign key referencing the job seeker who applied.
     #[schema(example = 1)]
-    pub job_seeker_id: i64,
+    pub job_seeker_id: UserId,
     /// Foreign key referencing the job that was applied for.
     #[schema(example = 1)]
-    pub job_id: i64,
+    pub job_id: JobId,
     /// Optional cover letter provided by the job seeker.
     #[schema(example = "I am very excited about this opportunity.")]
     pub cover_letter: Option<String>,
-    /// Link to the resume or file.
+    /// Link to the resume or file. Mirrors the primary resume attachment, i.e. the oldest attachment of kind `resume`.
     #[schema(example = "https://example.com/resume.pdf")]
     pub resume: Option<String>,
     /// Status of the application.
@@ -31,6 +32,39 @@ pub struct Application {
     #[serde(rename = "applied_at")]
     #[schema(example = "2024-09-16T15:30:00Z")]
     pub applied_at: DateTime<Utc>,
+    /// Human readable reference generated by the server when the application is created.
+    #[serde(default)]
+    #[schema(example = "APP-2024-000123", read_only)]
+    pub reference: String,
+    /// Rating from 1 to 5 given by the employer, set through the rating endpoint.
+    #[serde(default)]
+    #[schema(example = 4, minimum = 1, maximum = 5, read_only)]
+    pub rating: Option<u8>,
+}
+
+impl Application {
+    /// Fields that can be selected through the `fields` query parameter.
+    pub const FIELDS: &'static [&'static str] = &[
+        "id",
+        "job_seeker_id",
+        "job_id",
+        "cover_letter",
+        "resume",
+        "status",
+        "applied_at",
+        "reference",
+        "rating",
+    ];
+
+    /// Lowest and highest rating an employer can give.
+    pub const RATINGS: std::ops::RangeInclusive<u8> = 1..=5;
+
+    /// Build the human readable reference for an application, e.g. `APP-2024-000123`.
+    ///
+    /// The reference is derived from the unique application id, so it is unique as well.
+    pub fn generate_reference(id: ApplicationId, applied_at: DateTime<Utc>) -> String {
+        format!("APP-{}-{:06}", applied_at.year(), id.0)
+    }
 }
 
 /// Request to update existing `Application` item.
@@ -47,8 +81,134 @@ pub struct ApplicationUpdateRequest {
     pub status: Option<ApplicationStatus>,
 }
 
-/// Enum for application statuses.
+/// File attached to an `Application`, such as a resume or portfolio.
+#[derive(Serialize, Deserialize, ToSchema, Clone, Debug)]
+pub struct ApplicationAttachment {
+    /// Unique table id for the attachment.
+    #[schema(example = 1)]
+    pub id: i64,
+    /// Foreign key referencing the application the file is attached to.
+    #[schema(example = 1)]
+    pub application_id: ApplicationId,
+    /// Link to the attached file.
+    #[schema(example = "https://example.com/resume.pdf")]
+    pub url: String,
+    /// What the attached file is.
+    #[schema(example = "resume")]
+    pub kind: AttachmentKind,
+    /// Timestamp of when the file was attached.
+    #[serde(with = "chrono::serde::ts_seconds")]
+    #[schema(value_type = i64, example = 1726500600)]
+    pub uploaded_at: DateTime<Utc>,
+}
+
+/// Request to attach a file to an `Application`.
+#[derive(Serialize, Deserialize, ToSchema, Clone, Debug)]
+pub struct AttachmentRequest {
+    /// Link to the file to attach.
+    #[schema(example = "https://example.com/portfolio.pdf")]
+    pub url: String,
+    /// What the attached file is.
+    #[schema(example = "portfolio")]
+    pub kind: AttachmentKind,
+}
+
+/// Private note an employer wrote about an `Application`. Never shown to the job seeker.
+#[derive(Serialize, Deserialize, ToSchema, Clone, Debug)]
+pub struct ApplicationNote {
+    /// Unique table id for the note.
+    #[schema(example = 1)]
+    pub id: i64,
+    /// Foreign key referencing the application the note is about.
+    pub application_id: ApplicationId,
+    /// Foreign key referencing the employer who wrote the note.
+    pub author_id: UserId,
+    /// Text of the note.
+    #[schema(example = "Strong Rust background, schedule a call.")]
+    pub body: String,
+    /// Timestamp of when the note was written.
+    #[serde(with = "chrono::serde::ts_seconds")]
+    #[schema(value_type = i64, example = 1726500600)]
+    pub created_at: DateTime<Utc>,
+}
+
+/// Request to add a note to an `Application`.
+#[derive(Serialize, Deserialize, ToSchema, Clone, Debug)]
+pub struct NoteRequest {
+    /// The employer writing the note. Must own the job the `Application` is for.
+    pub author_id: UserId,
+    /// Text of the note.
+    #[schema(example = "Strong Rust background, schedule a call.")]
+    pub body: String,
+}
+
+/// Request to rate an `Application`.
+#[derive(Serialize, Deserialize, ToSchema, Clone, Debug)]
+pub struct RatingRequest {
+    /// The employer giving the rating. Must own the job the `Application` is for.
+    pub employer_id: UserId,
+    /// Rating from 1 to 5, or `null` to clear it.
+    #[schema(example = 4, minimum = 1, maximum = 5)]
+    pub rating: Option<u8>,
+}
+
+/// Request to reject the pending applications to a `Job`.
+#[derive(Serialize, Deserialize, ToSchema, Clone, Debug)]
+pub struct RejectPendingRequest {
+    /// The employer rejecting the applications. Must own the `Job`.
+    pub employer_id: UserId,
+}
+
+/// Outcome of rejecting the pending applications to a `Job`.
+#[derive(Serialize, Deserialize, ToSchema, Clone, Debug)]
+pub struct RejectPendingResult {
+    /// Number of applications moved from `pending` to `rejected`.
+    #[schema(example = 4)]
+    pub rejected: usize,
+}
+
+/// A job seeker who applied to one or more jobs of an employer.
+#[derive(Serialize, Deserialize, ToSchema, Clone, Debug)]
+pub struct Applicant {
+    /// The job seeker. The password is never included.
+    pub user: User,
+    /// Number of applications the job seeker submitted to the employer's jobs.
+    #[schema(example = 2)]
+    pub application_count: i64,
+    /// Ids of the employer's jobs the job seeker applied to, in ascending order.
+    #[schema(example = json!([1, 3]))]
+    pub job_ids: Vec<JobId>,
+}
+
+/// Number of applications in a given status.
 #[derive(Serialize, Deserialize, ToSchema, Clone, Debug)]
+pub struct ApplicationStatusCount {
+    /// Status being counted.
+    #[schema(example = "pending")]
+    pub status: ApplicationStatus,
+    /// Number of applications in this status.
+    #[schema(example = 4)]
+    pub count: i64,
+}
+
+impl ApplicationStatusCount {
+    /// Expand grouped counts to one entry per `ApplicationStatus`, using zero for missing statuses.
+    pub fn zero_filled(counts: &[(ApplicationStatus, i64)]) -> Vec<ApplicationStatusCount> {
+        ApplicationStatus::ALL
+            .iter()
+            .map(|status| ApplicationStatusCount {
+                status: status.clone(),
+                count: counts
+                    .iter()
+                    .find(|(counted, _)| counted == status)
+                    .map_or(0, |(_, count)| *count),
+            })
+            .collect()
+    }
+}
+
+/// Enum for application statuses.
+#[derive(Serialize, ToSchema, Clone, Debug, PartialEq, Eq)]
 pub enum ApplicationStatus {
     #[schema(rename = "pending")]
     Pending,
@@ -60,8 +220,38 @@ pub enum ApplicationStatus {
     Rejected,
 }
 
+impl ApplicationStatus {
+    /// Every `ApplicationStatus` variant, in declaration order.
+    pub const ALL: &'static [ApplicationStatus] = &[
+        ApplicationStatus::Pending,
+        ApplicationStatus::Reviewed,
+        ApplicationStatus::Accepted,
+        ApplicationStatus::Rejected,
+    ];
+
+    /// Whether an application may move from this status to `next` through an update.
+    ///
+    /// Applications only move forward: `pending` to any other status, and `reviewed` to `accepted` or `rejected`.
+    /// `accepted` and `rejected` are final, except that a rejected application can be reopened through its own
+    /// endpoint. Keeping the current status is always allowed.
+    pub fn can_transition_to(&self, next: &ApplicationStatus) -> bool {
+        use ApplicationStatus::*;
+        self == next
+            || matches!(
+                (self, next),
+                (Pending, Reviewed | Accepted | Rejected) | (Reviewed, Accepted | Rejected)
+            )
+    }
+}
+
+impl<'de> Deserialize<'de> for ApplicationStatus {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserialize_variant(deserializer, "application_status", ApplicationStatus::ALL)
+    }
+}
+
 impl ToSql for ApplicationStatus {
-    fn to_sql(&self) -> Result<ToSqlOutput, rusqlite::Error> {
+    fn to_sql(&self) -> Result<ToSqlOutput<'_>, rusqlite::Error> {
         Ok(ToSqlOutput::from(self.to_string()))
     }
 }
@@ -74,7 +264,7 @@ impl FromSql for ApplicationStatus {
             "reviewed" => Ok(ApplicationStatus::Reviewed),
             "accepted" => Ok(ApplicationStatus::Accepted),
             "rejected" => Ok(ApplicationStatus::Rejected),
-            _ => Err(rusqlite::types::FromSqlError::InvalidType),
+            other => Err(InvalidEnumValue::from_sql("application_status", other)),
         }
     }
 }
@@ -88,4 +278,59 @@ impl fmt::Display for ApplicationStatus {
         };
         write!(f, "{}", status_str)
     }
-}
\ No newline at end of file
+}
+
+/// Enum for application attachment kinds.
+#[derive(Serialize, ToSchema, Clone, Debug, PartialEq, Eq)]
+pub enum AttachmentKind {
+    #[schema(rename = "resume")]
+    Resume,
+    #[schema(rename = "portfolio")]
+    Portfolio,
+    #[schema(rename = "other")]
+    Other,
+}
+
+impl AttachmentKind {
+    /// Every `AttachmentKind` variant, in declaration order.
+    pub const ALL: &'static [AttachmentKind] = &[
+        AttachmentKind::Resume,
+        AttachmentKind::Portfolio,
+        AttachmentKind::Other,
+    ];
+}
+
+impl<'de> Deserialize<'de> for AttachmentKind {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserialize_variant(deserializer, "attachment_kind", AttachmentKind::ALL)
+    }
+}
+
+impl ToSql for AttachmentKind {
+    fn to_sql(&self) -> Result<ToSqlOutput<'_>, rusqlite::Error> {
+        Ok(ToSqlOutput::from(self.to_string()))
+    }
+}
+
+impl FromSql for AttachmentKind {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        let s: String = value.as_str()?.to_string();
+        match s.as_str() {
+            "resume" => Ok(AttachmentKind::Resume),
+            "portfolio" => Ok(AttachmentKind::Portfolio),
+            "other" => Ok(AttachmentKind::Other),
+            other => Err(InvalidEnumValue::from_sql("attachment_kind", other)),
+        }
+    }
+}
+
+impl fmt::Display for AttachmentKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind_str = match self {
+            AttachmentKind::Resume => "resume",
+            AttachmentKind::Portfolio => "portfolio",
+            AttachmentKind::Other => "other",
+        };
+        write!(f, "{}", kind_str)
+    }
+}
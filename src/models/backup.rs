@@ -0,0 +1,92 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use crate::models::application::{ApplicationAttachment, ApplicationNote};
+use crate::models::user::JobPreferences;
+use crate::models::{Application, ApplicationId, ApplicationStatus, Job, JobId, User};
+
+/// Portable JSON backup of the whole database.
+#[derive(Serialize, Deserialize, ToSchema, Clone, Debug)]
+pub struct Backup {
+    /// Timestamp of when the backup was taken.
+    #[serde(with = "chrono::serde::ts_seconds")]
+    #[schema(value_type = i64, example = 1726500600)]
+    pub created_at: DateTime<Utc>,
+    /// Every user, without their passwords.
+    pub users: Vec<User>,
+    /// Every job.
+    pub jobs: Vec<Job>,
+    /// Every application.
+    pub applications: Vec<Application>,
+    /// Every application attachment. Missing in backups taken before attachments existed.
+    #[serde(default)]
+    pub attachments: Vec<ApplicationAttachment>,
+    /// Every note on an application. Missing in backups taken before notes were included.
+    #[serde(default)]
+    pub notes: Vec<ApplicationNote>,
+    /// Every tag attached to a job. Missing in backups taken before tags were included.
+    #[serde(default)]
+    pub job_tags: Vec<JobTag>,
+    /// Every job seeker's saved preferences. Missing in backups taken before preferences were included.
+    #[serde(default)]
+    pub preferences: Vec<JobPreferences>,
+    /// Every recorded application status change. Missing in backups taken before the history was included.
+    #[serde(default)]
+    pub status_changes: Vec<ApplicationStatusChange>,
+}
+
+/// A tag attached to a job.
+#[derive(Serialize, Deserialize, ToSchema, Clone, Debug)]
+pub struct JobTag {
+    /// Foreign key referencing the tagged job.
+    pub job_id: JobId,
+    /// Normalized name of the tag.
+    #[schema(example = "rust")]
+    pub tag: String,
+}
+
+/// A recorded change of an application's status.
+#[derive(Serialize, Deserialize, ToSchema, Clone, Debug)]
+pub struct ApplicationStatusChange {
+    /// Unique table id for the change.
+    #[schema(example = 1)]
+    pub id: i64,
+    /// Foreign key referencing the application whose status changed.
+    pub application_id: ApplicationId,
+    /// Status the application changed to.
+    #[schema(example = "reviewed")]
+    pub status: ApplicationStatus,
+    /// Timestamp of the change.
+    #[serde(with = "chrono::serde::ts_seconds")]
+    #[schema(value_type = i64, example = 1726500600)]
+    pub changed_at: DateTime<Utc>,
+}
+
+/// Number of records restored from a `Backup`.
+#[derive(Serialize, Deserialize, ToSchema, Clone, Debug)]
+pub struct RestoreSummary {
+    /// Number of users restored.
+    #[schema(example = 10)]
+    pub users: usize,
+    /// Number of jobs restored.
+    #[schema(example = 25)]
+    pub jobs: usize,
+    /// Number of applications restored.
+    #[schema(example = 40)]
+    pub applications: usize,
+    /// Number of application attachments restored.
+    #[schema(example = 45)]
+    pub attachments: usize,
+    /// Number of application notes restored.
+    #[schema(example = 12)]
+    pub notes: usize,
+    /// Number of job tags restored.
+    #[schema(example = 30)]
+    pub job_tags: usize,
+    /// Number of job seekers' preferences restored.
+    #[schema(example = 6)]
+    pub preferences: usize,
+    /// Number of application status changes restored.
+    #[schema(example = 60)]
+    pub status_changes: usize,
+}
@@ -1,19 +1,22 @@
 use std::fmt;
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Duration, Utc};
+use crate::config;
 use rusqlite::ToSql;
 use rusqlite::types::{FromSql, FromSqlResult, ToSqlOutput, ValueRef};
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
 use utoipa::ToSchema;
+use crate::models::{deserialize_variant, InvalidEnumValue, JobId, UserId};
+use crate::utils::FieldError;
 
 /// Job object
 #[derive(Serialize, Deserialize, ToSchema, Clone, Debug)]
 pub struct Job {
     /// Unique table id for the Job.
     #[schema(example = 1)]
-    pub id: i64,
+    pub id: JobId,
     /// Foreign key referencing the employer who posted the job.
     #[schema(example = 1)]
-    pub employer_id: i64,
+    pub employer_id: UserId,
     /// Title of the job.
     #[schema(example = "Software Engineer")]
     pub title: String,
@@ -39,6 +42,143 @@ pub struct Job {
     #[serde(rename = "updated_at")]
     #[schema(example = "2024-09-16T15:30:00Z")]
     pub updated_at: DateTime<Utc>,
+    /// Optional deadline after which applications are no longer accepted, as a Unix timestamp.
+    #[serde(default, with = "chrono::serde::ts_seconds_option")]
+    #[schema(value_type = Option<i64>, example = 1729123199)]
+    pub deadline: Option<DateTime<Utc>>,
+    /// Whether the `Job` was posted within the last `NEW_JOB_WINDOW_HOURS` hours. Computed, never stored.
+    #[serde(default, skip_deserializing)]
+    #[schema(read_only, example = true)]
+    pub is_new: bool,
+    /// Lifecycle status of the job.
+    #[serde(default)]
+    #[schema(example = "open")]
+    pub status: JobStatus,
+    /// Timestamp of when the job was closed or expired, unset while it is open. Set by the server.
+    #[serde(default, with = "chrono::serde::ts_seconds_option")]
+    #[schema(read_only, value_type = Option<i64>, example = 1726500600)]
+    pub closed_at: Option<DateTime<Utc>>,
+    /// Optional time after which applications still pending are automatically rejected.
+    #[serde(default, with = "chrono::serde::ts_seconds_option")]
+    #[schema(value_type = Option<i64>, example = 1729123199)]
+    pub auto_reject_after: Option<DateTime<Utc>>,
+}
+
+impl Job {
+    /// Fields that can be selected through the `fields` query parameter.
+    pub const FIELDS: &'static [&'static str] = &[
+        "id",
+        "employer_id",
+        "title",
+        "description",
+        "location",
+        "salary",
+        "employment_type",
+        "posted_at",
+        "updated_at",
+        "deadline",
+        "is_new",
+        "status",
+        "closed_at",
+        "auto_reject_after",
+    ];
+
+    /// Validate the `Job` fields that cannot be expressed through the type system.
+    ///
+    /// Returns every failing field so callers can report them all at once.
+    pub fn validate(&self) -> Result<(), Vec<FieldError>> {
+        let mut errors = Vec::new();
+
+        if self.employer_id.0 <= 0 {
+            errors.push(FieldError {
+                field: "employer_id".to_string(),
+                message: "employer_id must be a positive id".to_string(),
+            });
+        }
+
+        for (field, value) in [
+            ("title", &self.title),
+            ("description", &self.description),
+            ("location", &self.location),
+        ] {
+            if value.trim().is_empty() {
+                errors.push(FieldError {
+                    field: field.to_string(),
+                    message: format!("{} is required", field),
+                });
+            }
+        }
+
+        for (field, value, max_length) in [
+            ("title", &self.title, config::max_job_title_length()),
+            ("description", &self.description, config::max_job_description_length()),
+        ] {
+            if value.chars().count() > max_length {
+                errors.push(FieldError {
+                    field: field.to_string(),
+                    message: format!("{} must be at most {} characters", field, max_length),
+                });
+            }
+        }
+
+        if let Some(salary) = &self.salary {
+            if !is_valid_salary(salary) {
+                errors.push(FieldError {
+                    field: "salary".to_string(),
+                    message: "salary must be an amount or a range like \"$120,000 - $150,000\"".to_string(),
+                });
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+impl Job {
+    /// Validate a `Job` about to be posted at `now`: the checks of [`Job::validate`], and that its deadline has not
+    /// already passed.
+    pub fn validate_new(&self, now: DateTime<Utc>) -> Result<(), Vec<FieldError>> {
+        let mut errors = self.validate().err().unwrap_or_default();
+        errors.extend(deadline_error(self.deadline, now));
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+/// The error for a deadline being set that has already passed at `now`.
+///
+/// Only deadlines being set are checked, as stored jobs keep their deadline after it passes.
+pub fn deadline_error(deadline: Option<DateTime<Utc>>, now: DateTime<Utc>) -> Option<FieldError> {
+    match deadline {
+        Some(deadline) if deadline < now => Some(FieldError {
+            field: "deadline".to_string(),
+            message: "deadline must not be in the past".to_string(),
+        }),
+        _ => None,
+    }
+}
+
+/// Check that a salary is a single amount or a `low - high` range.
+///
+/// Amounts may start with a currency symbol and use `,` as a thousands separator.
+fn is_valid_salary(salary: &str) -> bool {
+    let parts: Vec<&str> = salary.split('-').map(str::trim).collect();
+    if parts.len() > 2 {
+        return false;
+    }
+
+    parts.iter().all(|part| {
+        let amount = part.trim_start_matches(['$', '€', '£']);
+        amount.chars().any(|c| c.is_ascii_digit())
+            && amount.chars().all(|c| c.is_ascii_digit() || c == ',' || c == '.')
+    })
 }
 
 /// Request to update existing `Job` item.
@@ -59,10 +199,489 @@ pub struct JobUpdateRequest {
     /// Optional new value for the `Job` employment_type.
     #[schema(example = "contract")]
     pub employment_type: Option<EmploymentType>,
+    /// Optional new value for the `Job` deadline, as a Unix timestamp.
+    #[serde(default, with = "chrono::serde::ts_seconds_option")]
+    #[schema(value_type = Option<i64>, example = 1730505599)]
+    pub deadline: Option<DateTime<Utc>>,
+    /// Optional new value for the `Job` status.
+    #[serde(default)]
+    #[schema(example = "closed")]
+    pub status: Option<JobStatus>,
+    /// Optional new value for the `Job` auto_reject_after.
+    #[serde(default, with = "chrono::serde::ts_seconds_option")]
+    #[schema(value_type = Option<i64>, example = 1729123199)]
+    pub auto_reject_after: Option<DateTime<Utc>>,
 }
 
-/// Enum for employment types.
+/// Job posting to validate without saving it.
+///
+/// Every field is optional, so a missing field or an unknown enum value is reported as a `FieldError` instead of
+/// failing to parse.
+#[derive(Serialize, Deserialize, ToSchema, Clone, Debug, Default)]
+#[serde(default)]
+pub struct JobDraft {
+    /// Employer who would post the job.
+    #[schema(example = 1)]
+    pub employer_id: Option<UserId>,
+    /// Title of the job.
+    #[schema(example = "Software Engineer")]
+    pub title: Option<String>,
+    /// Detailed job description.
+    #[schema(example = "Responsible for developing and maintaining software applications.")]
+    pub description: Option<String>,
+    /// Location of the job.
+    #[schema(example = "San Francisco, CA")]
+    pub location: Option<String>,
+    /// Salary or pay range for the job.
+    #[schema(example = "$120,000 - $150,000")]
+    pub salary: Option<String>,
+    /// Type of employment, one of `full_time`, `part_time` or `contract`.
+    #[schema(example = "full_time")]
+    pub employment_type: Option<String>,
+    /// Lifecycle status of the job, `open` unless given.
+    #[schema(example = "open")]
+    pub status: Option<String>,
+    /// Deadline after which applications are no longer accepted, as a Unix timestamp.
+    #[schema(example = 1729123199)]
+    pub deadline: Option<i64>,
+    /// Time after which applications still pending are automatically rejected, as a Unix timestamp.
+    #[schema(example = 1729123199)]
+    pub auto_reject_after: Option<i64>,
+}
+
+impl JobDraft {
+    /// The `Job` this draft would create at the given time, or every failing field.
+    ///
+    /// Runs the same checks as job creation, after reporting missing required fields and unknown enum values.
+    pub fn into_job(self, now: DateTime<Utc>) -> Result<Job, Vec<FieldError>> {
+        let mut errors = Vec::new();
+        let mut required = |field: &str, present: bool| {
+            if !present {
+                errors.push(FieldError {
+                    field: field.to_string(),
+                    message: format!("{} is required", field),
+                });
+            }
+        };
+        required("employer_id", self.employer_id.is_some());
+        required("title", self.title.is_some());
+        required("description", self.description.is_some());
+        required("location", self.location.is_some());
+        required("employment_type", self.employment_type.is_some());
+
+        let employment_type = parse_field(&mut errors, "employment_type", self.employment_type);
+        let status = parse_field(&mut errors, "status", self.status);
+        let deadline = timestamp_field(&mut errors, "deadline", self.deadline);
+        let auto_reject_after = timestamp_field(&mut errors, "auto_reject_after", self.auto_reject_after);
+        let status: JobStatus = status.unwrap_or_default();
+
+        let job = Job {
+            id: JobId::default(),
+            employer_id: self.employer_id.unwrap_or_default(),
+            title: self.title.unwrap_or_default(),
+            description: self.description.unwrap_or_default(),
+            location: self.location.unwrap_or_default(),
+            salary: self.salary,
+            employment_type: employment_type.unwrap_or(EmploymentType::FullTime),
+            posted_at: now,
+            updated_at: now,
+            deadline,
+            is_new: true,
+            closed_at: match status {
+                JobStatus::Open => None,
+                _ => Some(now),
+            },
+            status,
+            auto_reject_after,
+        };
+        if let Err(job_errors) = job.validate_new(now) {
+            // A missing field is reported once, as missing, rather than also as blank or invalid.
+            let reported: Vec<String> = errors.iter().map(|error| error.field.clone()).collect();
+            errors.extend(job_errors.into_iter().filter(|error| !reported.contains(&error.field)));
+        }
+
+        if errors.is_empty() {
+            Ok(job)
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+/// Parse an enum field given as a string, recording a `FieldError` when it is not a known value.
+fn parse_field<T: for<'de> Deserialize<'de>>(errors: &mut Vec<FieldError>, field: &str, value: Option<String>) -> Option<T> {
+    match T::deserialize(serde_json::Value::String(value?)) {
+        Ok(parsed) => Some(parsed),
+        Err(e) => {
+            errors.push(FieldError {
+                field: field.to_string(),
+                message: e.to_string(),
+            });
+            None
+        }
+    }
+}
+
+/// Convert a Unix timestamp field, recording a `FieldError` when it is out of range.
+fn timestamp_field(errors: &mut Vec<FieldError>, field: &str, seconds: Option<i64>) -> Option<DateTime<Utc>> {
+    let timestamp = DateTime::from_timestamp(seconds?, 0);
+    if timestamp.is_none() {
+        errors.push(FieldError {
+            field: field.to_string(),
+            message: format!("{} is not a valid Unix timestamp", field),
+        });
+    }
+    timestamp
+}
+
+impl Job {
+    /// Whether the `Job` is open and still accepts applications at the given time.
+    pub fn accepts_applications_at(&self, at: DateTime<Utc>) -> bool {
+        matches!(self.status, JobStatus::Open) && self.deadline.map_or(true, |deadline| at <= deadline)
+    }
+
+    /// Whether a `Job` posted at `posted_at` is still within the window in which it counts as new.
+    pub fn is_recent(posted_at: DateTime<Utc>) -> bool {
+        Utc::now() - posted_at <= Duration::hours(config::new_job_window_hours())
+    }
+
+    /// The `closed_at` timestamp the `Job` should have after moving to `status` at the given time.
+    ///
+    /// Closing an open job records the time, staying closed keeps the original time and reopening clears it.
+    pub fn closed_at_after(&self, status: &JobStatus, at: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        match (status, &self.status) {
+            (JobStatus::Open, _) => None,
+            (_, JobStatus::Open) => Some(at),
+            _ => self.closed_at.or(Some(at)),
+        }
+    }
+
+    /// Salary formatted for display, with amounts separated by an en dash, e.g. `$120,000 – $150,000`.
+    pub fn salary_display(&self) -> Option<String> {
+        let salary = self.salary.as_deref()?.trim();
+        if salary.is_empty() {
+            return None;
+        }
+
+        let parts: Vec<&str> = salary.split('-').map(str::trim).collect();
+        Some(parts.join(" – "))
+    }
+
+    /// Highest amount of the salary, e.g. `150000` for `$120,000 - $150,000`, or `None` without a readable salary.
+    pub fn max_salary(&self) -> Option<f64> {
+        self.salary
+            .as_deref()?
+            .split('-')
+            .filter_map(|part| part.trim().trim_start_matches(['$', '€', '£']).replace(',', "").parse::<f64>().ok())
+            .reduce(f64::max)
+    }
+}
+
+/// Jobs placed side by side, with the fields seekers weigh compared across them.
+#[derive(Serialize, Deserialize, ToSchema, Clone, Debug)]
+pub struct JobComparison {
+    /// The compared jobs, in the order they were requested.
+    pub jobs: Vec<Job>,
+    /// Comparison of each compared field.
+    pub fields: Vec<JobFieldComparison>,
+}
+
+/// The normalized values of one field across compared jobs.
+#[derive(Serialize, Deserialize, ToSchema, Clone, Debug)]
+pub struct JobFieldComparison {
+    /// Name of the compared field.
+    #[schema(example = "salary")]
+    pub field: String,
+    /// Normalized value of the field for each job, in the same order as `jobs`.
+    #[schema(example = json!(["$120,000 – $150,000", null]))]
+    pub values: Vec<Option<String>>,
+    /// Whether the values are not all the same.
+    #[schema(example = true)]
+    pub differs: bool,
+}
+
+impl JobComparison {
+    /// Compare `salary`, `location` and `employment_type` across the given jobs.
+    ///
+    /// Values are normalized before comparing: salaries are formatted for display and locations are trimmed.
+    pub fn new(jobs: Vec<Job>) -> Self {
+        let compare = |field: &str, value: fn(&Job) -> Option<String>| {
+            let values: Vec<Option<String>> = jobs.iter().map(value).collect();
+            JobFieldComparison {
+                field: field.to_string(),
+                differs: values.windows(2).any(|pair| pair[0] != pair[1]),
+                values,
+            }
+        };
+        let fields = vec![
+            compare("salary", Job::salary_display),
+            compare("location", |job| Some(job.location.trim().to_string())),
+            compare("employment_type", |job| Some(job.employment_type.to_string())),
+        ];
+
+        JobComparison { jobs, fields }
+    }
+}
+
+/// Request to delete several `Job` items at once.
+#[derive(Serialize, Deserialize, ToSchema, Clone, Debug)]
+pub struct JobBulkDeleteRequest {
+    /// Ids of the `Job` items to delete.
+    #[schema(example = json!([1, 2, 3]))]
+    pub ids: Vec<JobId>,
+}
+
+/// Outcome of closing every open `Job` of an employer.
+#[derive(Serialize, Deserialize, ToSchema, Clone, Debug)]
+pub struct JobCloseAllResult {
+    /// Number of jobs moved from `open` to `closed`.
+    #[schema(example = 3)]
+    pub closed: usize,
+}
+
+/// Outcome of deleting a single `Job` in a bulk delete.
+#[derive(Serialize, Deserialize, ToSchema, Clone, Debug)]
+pub struct JobBulkDeleteResult {
+    /// Id of the `Job` that was requested for deletion.
+    #[schema(example = 1)]
+    pub id: JobId,
+    /// What happened to the `Job`.
+    pub status: JobDeleteStatus,
+}
+
+/// Outcome of deleting one `Job` of a bulk delete.
+#[derive(Serialize, Deserialize, ToSchema, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum JobDeleteStatus {
+    /// The `Job` was deleted.
+    Deleted,
+    /// No `Job` with this id exists.
+    NotFound,
+    /// The `Job` was kept because applications were submitted to it.
+    Conflict,
+}
+
+/// Request to attach tags to several `Job` items at once.
+#[derive(Serialize, Deserialize, ToSchema, Clone, Debug)]
+pub struct JobTagRequest {
+    /// Ids of the `Job` items to tag.
+    #[schema(example = json!([1, 2]))]
+    pub job_ids: Vec<JobId>,
+    /// Tags to attach to every listed `Job`.
+    #[schema(example = json!(["remote", "rust"]))]
+    pub tags: Vec<String>,
+}
+
+/// Outcome of tagging a single `Job` in a bulk tag request.
 #[derive(Serialize, Deserialize, ToSchema, Clone, Debug)]
+pub struct JobTagResult {
+    /// Id of the `Job` that was requested for tagging.
+    #[schema(example = 1)]
+    pub id: JobId,
+    /// Whether a `Job` with this id existed and was tagged.
+    #[schema(example = true)]
+    pub tagged: bool,
+}
+
+/// Incremental edit of the tags of a single `Job`.
+#[derive(Serialize, Deserialize, ToSchema, Clone, Debug)]
+pub struct JobTagPatch {
+    /// Tags to attach to the `Job`.
+    #[serde(default)]
+    #[schema(example = json!(["remote"]))]
+    pub add: Vec<String>,
+    /// Tags to detach from the `Job`. Tags the `Job` does not have are ignored.
+    #[serde(default)]
+    #[schema(example = json!(["onsite"]))]
+    pub remove: Vec<String>,
+}
+
+/// A row of a CSV job import. The header row names these fields; timestamps are Unix timestamps.
+#[derive(Deserialize, Clone, Debug)]
+pub struct JobImportRow {
+    pub employer_id: UserId,
+    pub title: String,
+    pub description: String,
+    pub location: String,
+    pub salary: Option<String>,
+    pub employment_type: EmploymentType,
+    pub deadline: Option<i64>,
+    pub status: Option<JobStatus>,
+    pub auto_reject_after: Option<i64>,
+}
+
+impl JobImportRow {
+    /// The `Job` to create for this row, posted at the given time.
+    pub fn into_job(self, now: DateTime<Utc>) -> Result<Job, String> {
+        let timestamp = |field: &str, value: Option<i64>| match value {
+            Some(seconds) => DateTime::from_timestamp(seconds, 0)
+                .map(Some)
+                .ok_or_else(|| format!("{} is not a valid Unix timestamp", field)),
+            None => Ok(None),
+        };
+        let status = self.status.unwrap_or_default();
+
+        Ok(Job {
+            id: JobId::default(),
+            employer_id: self.employer_id,
+            title: self.title,
+            description: self.description,
+            location: self.location,
+            salary: self.salary,
+            employment_type: self.employment_type,
+            posted_at: now,
+            updated_at: now,
+            deadline: timestamp("deadline", self.deadline)?,
+            is_new: true,
+            closed_at: match status {
+                JobStatus::Open => None,
+                _ => Some(now),
+            },
+            status,
+            auto_reject_after: timestamp("auto_reject_after", self.auto_reject_after)?,
+        })
+    }
+}
+
+/// Outcome of a single row of a CSV job import.
+#[derive(Serialize, Deserialize, ToSchema, Clone, Debug)]
+pub struct JobImportResult {
+    /// Line of the row in the uploaded file, counting the header as line 1.
+    #[schema(example = 2)]
+    pub line: u64,
+    /// Id of the created `Job`, absent if the row failed or the import was rolled back.
+    #[schema(example = 42)]
+    pub id: Option<JobId>,
+    /// Why the row failed, absent if it succeeded.
+    #[schema(example = "title is required")]
+    pub error: Option<String>,
+}
+
+/// Report of a CSV job import.
+#[derive(Serialize, Deserialize, ToSchema, Clone, Debug)]
+pub struct JobImportReport {
+    /// Number of jobs created.
+    #[schema(example = 2)]
+    pub created: usize,
+    /// Number of rows that failed.
+    #[schema(example = 1)]
+    pub failed: usize,
+    /// Whether nothing was saved because a row failed in all-or-nothing mode.
+    #[schema(example = false)]
+    pub rolled_back: bool,
+    /// Outcome of every row, in file order.
+    pub results: Vec<JobImportResult>,
+}
+
+/// Read-only preview of how a `Job` will be presented to job seekers.
+#[derive(Serialize, Deserialize, ToSchema, Clone, Debug)]
+pub struct JobPreview {
+    /// The `Job` as stored.
+    pub job: Job,
+    /// Description rendered from Markdown to HTML.
+    #[schema(example = "<p>Responsible for developing <strong>backend</strong> services.</p>\n")]
+    pub description_html: String,
+    /// Salary formatted for display, if the `Job` has one.
+    #[schema(example = "$120,000 – $150,000")]
+    pub salary_display: Option<String>,
+    /// Tags attached to the `Job`.
+    #[schema(example = json!(["remote", "rust"]))]
+    pub tags: Vec<String>,
+}
+
+/// A `Job` with applications waiting for the employer to review them.
+#[derive(Serialize, Deserialize, ToSchema, Clone, Debug)]
+pub struct ReviewQueueItem {
+    /// The `Job` with pending applications.
+    pub job: Job,
+    /// Number of pending applications for the `Job`.
+    #[schema(example = 3)]
+    pub pending_count: i64,
+    /// When the oldest pending application was submitted.
+    #[serde(with = "chrono::serde::ts_seconds")]
+    #[schema(value_type = i64, example = 1726500600)]
+    pub oldest_pending_at: DateTime<Utc>,
+}
+
+/// An open `Job` recommended to a job seeker.
+#[derive(Serialize, Deserialize, ToSchema, Clone, Debug)]
+pub struct RecommendedJob {
+    /// The recommended `Job`.
+    pub job: Job,
+    /// How many attributes the `Job` shares with jobs the seeker applied to: one per shared tag, plus one each for a
+    /// matching employment type and location. Matching the seeker's preferred employment types and locations adds one
+    /// each.
+    #[schema(example = 3)]
+    pub score: i64,
+}
+
+/// Number of applications received by a `Job`.
+#[derive(Serialize, Deserialize, ToSchema, Clone, Debug)]
+pub struct JobApplicationCount {
+    /// Id of the `Job`.
+    #[schema(example = 1)]
+    pub job_id: JobId,
+    /// Number of applications for the `Job`, in any status.
+    #[schema(example = 12)]
+    pub count: i64,
+}
+
+/// Records affected by deleting a `Job`, reported without deleting anything.
+///
+/// Deleting a `Job` is refused with `409` while it has applications, since they and their attachments and notes keep
+/// referring to it. Without applications only its tag links go with it.
+#[derive(Serialize, Deserialize, ToSchema, Clone, Debug)]
+pub struct JobDeleteImpact {
+    /// Id of the `Job`.
+    #[schema(example = 1)]
+    pub job_id: JobId,
+    /// Whether deleting the `Job` would be refused with `409` because it still has applications.
+    #[schema(example = true)]
+    pub blocked: bool,
+    /// Number of applications for the `Job`, in any status. Any application blocks the delete.
+    #[schema(example = 12)]
+    pub applications: i64,
+    /// Number of attachments of those applications.
+    #[schema(example = 9)]
+    pub attachments: i64,
+    /// Number of employer notes on those applications.
+    #[schema(example = 4)]
+    pub notes: i64,
+    /// Number of tags attached to the `Job`, which are detached when it is deleted.
+    #[schema(example = 3)]
+    pub tags: i64,
+}
+
+/// A change to a `Job` since a given time, for incremental sync.
+#[derive(Serialize, Deserialize, ToSchema, Clone, Debug)]
+pub struct JobChange {
+    /// Id of the changed `Job`.
+    #[schema(example = 1)]
+    pub id: JobId,
+    /// When the `Job` was last updated, or when it was deleted.
+    #[serde(with = "chrono::serde::ts_seconds")]
+    #[schema(value_type = i64, example = 1726500600)]
+    pub changed_at: DateTime<Utc>,
+    /// Whether the `Job` was deleted. Mirrors should remove it.
+    #[schema(example = false)]
+    pub deleted: bool,
+    /// The `Job` as it is now, absent when it was deleted.
+    pub job: Option<Job>,
+}
+
+/// A distinct `Job` location together with the number of jobs posted there.
+#[derive(Serialize, Deserialize, ToSchema, Clone, Debug)]
+pub struct JobLocation {
+    /// Location as stored on the `Job`.
+    #[schema(example = "San Francisco, CA")]
+    pub location: String,
+    /// Number of jobs with this location.
+    #[schema(example = 3)]
+    pub count: i64,
+}
+
+/// Enum for employment types.
+#[derive(Serialize, ToSchema, Clone, Debug)]
 pub enum EmploymentType {
     #[schema(rename = "full_time")]
     FullTime,
@@ -72,8 +691,23 @@ pub enum EmploymentType {
     Contract,
 }
 
+impl EmploymentType {
+    /// Every `EmploymentType` variant, in declaration order.
+    pub const ALL: &'static [EmploymentType] = &[
+        EmploymentType::FullTime,
+        EmploymentType::PartTime,
+        EmploymentType::Contract,
+    ];
+}
+
+impl<'de> Deserialize<'de> for EmploymentType {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserialize_variant(deserializer, "employment_type", EmploymentType::ALL)
+    }
+}
+
 impl ToSql for EmploymentType {
-    fn to_sql(&self) -> Result<ToSqlOutput, rusqlite::Error> {
+    fn to_sql(&self) -> Result<ToSqlOutput<'_>, rusqlite::Error> {
         Ok(ToSqlOutput::from(self.to_string()))
     }
 }
@@ -85,7 +719,7 @@ impl FromSql for EmploymentType {
             "full_time" => Ok(EmploymentType::FullTime),
             "part_time" => Ok(EmploymentType::PartTime),
             "contract" => Ok(EmploymentType::Contract),
-            _ => Err(rusqlite::types::FromSqlError::InvalidType),
+            other => Err(InvalidEnumValue::from_sql("employment_type", other)),
         }
     }
 }
@@ -101,3 +735,95 @@ impl fmt::Display for EmploymentType {
     }
 }
 
+/// Enum for job lifecycle statuses.
+#[derive(Serialize, ToSchema, Clone, Debug, Default)]
+pub enum JobStatus {
+    #[default]
+    #[schema(rename = "open")]
+    Open,
+    #[schema(rename = "closed")]
+    Closed,
+    #[schema(rename = "expired")]
+    Expired,
+}
+
+impl JobStatus {
+    /// Every `JobStatus` variant, in declaration order.
+    pub const ALL: &'static [JobStatus] = &[
+        JobStatus::Open,
+        JobStatus::Closed,
+        JobStatus::Expired,
+    ];
+}
+
+impl<'de> Deserialize<'de> for JobStatus {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserialize_variant(deserializer, "job_status", JobStatus::ALL)
+    }
+}
+
+impl ToSql for JobStatus {
+    fn to_sql(&self) -> Result<ToSqlOutput<'_>, rusqlite::Error> {
+        Ok(ToSqlOutput::from(self.to_string()))
+    }
+}
+
+impl FromSql for JobStatus {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        let s: String = value.as_str()?.to_string();
+        match s.as_str() {
+            "open" => Ok(JobStatus::Open),
+            "closed" => Ok(JobStatus::Closed),
+            "expired" => Ok(JobStatus::Expired),
+            other => Err(InvalidEnumValue::from_sql("job_status", other)),
+        }
+    }
+}
+
+impl fmt::Display for JobStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let status_str = match self {
+            JobStatus::Open => "open",
+            JobStatus::Closed => "closed",
+            JobStatus::Expired => "expired",
+        };
+        write!(f, "{}", status_str)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::DateTime;
+    use serde_json::{json, Value};
+    use utoipa::ToSchema;
+    use super::{Job, JobUpdateRequest};
+
+    #[test]
+    fn deadline_is_documented_and_sent_as_unix_seconds() {
+        let job: Job = serde_json::from_value(json!({
+            "id": 1,
+            "employer_id": 1,
+            "title": "Software Engineer",
+            "description": "Build things.",
+            "location": "Stockholm",
+            "salary": null,
+            "employment_type": "full_time",
+            "posted_at": 1726500600,
+            "updated_at": 1726500600,
+            "deadline": 1729123199
+        }))
+        .unwrap();
+        assert_eq!(job.deadline, DateTime::from_timestamp(1729123199, 0));
+        let serialized = serde_json::to_value(&job).unwrap();
+        assert_eq!(serialized["deadline"], 1729123199);
+        let round_tripped: Job = serde_json::from_value(serialized).unwrap();
+        assert_eq!(round_tripped.deadline, job.deadline);
+
+        for (name, schema) in [Job::schema(), JobUpdateRequest::schema()] {
+            let schema = serde_json::to_value(schema).unwrap();
+            let deadline: &Value = &schema["properties"]["deadline"];
+            assert_eq!(deadline["type"], "integer", "{}", name);
+            assert!(deadline["example"].is_i64(), "{}", name);
+        }
+    }
+}
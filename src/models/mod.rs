@@ -1,30 +1,133 @@
+use std::error::Error;
+use std::fmt;
 use std::sync::Mutex;
+use rusqlite::types::FromSqlError;
+use serde::de::{self, Deserialize, Deserializer};
+use crate::utils::single_flight::SingleFlight;
 
 pub mod user;
 pub mod job;
 pub mod application;
+pub mod backup;
+pub mod id;
 
+pub use id::{ApplicationId, JobId, UserId};
 pub use user::User;
 pub use user::UserRole;
 pub use job::Job;
 pub use job::EmploymentType;
+pub use job::JobStatus;
 pub use application::Application;
 pub use application::ApplicationStatus;
 
+/// A stored enum column holding a value that is not one of the enum's variants.
+#[derive(Debug)]
+pub struct InvalidEnumValue {
+    /// Name of the enum, e.g. `user_role`.
+    pub kind: &'static str,
+    /// The value found in the database.
+    pub value: String,
+}
+
+impl InvalidEnumValue {
+    /// `FromSql` error for an unexpected `value` of the enum `kind`.
+    pub fn from_sql(kind: &'static str, value: &str) -> FromSqlError {
+        FromSqlError::Other(Box::new(InvalidEnumValue {
+            kind,
+            value: value.to_string(),
+        }))
+    }
+}
+
+impl fmt::Display for InvalidEnumValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid {} value {:?}", self.kind, self.value)
+    }
+}
+
+impl Error for InvalidEnumValue {}
+
+/// Deserialize one of `variants`, accepting any casing and `_`, `-` or space separators.
+///
+/// `"FULL_TIME"`, `"Full_Time"`, `"full-time"`, `"fulltime"` and `"FullTime"` all resolve to the variant displayed as
+/// `full_time`. Unknown values are rejected with an error naming the `kind` of enum and its valid values.
+pub fn deserialize_variant<'de, D, T>(deserializer: D, kind: &str, variants: &[T]) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Clone + fmt::Display,
+{
+    let value = String::deserialize(deserializer)?;
+    let wanted = normalize_variant(&value);
+    variants
+        .iter()
+        .find(|variant| normalize_variant(&variant.to_string()) == wanted)
+        .cloned()
+        .ok_or_else(|| {
+            let expected: Vec<String> = variants.iter().map(ToString::to_string).collect();
+            de::Error::custom(format!("unknown {} {:?}, expected one of {}", kind, value, expected.join(", ")))
+        })
+}
+
+fn normalize_variant(value: &str) -> String {
+    value
+        .chars()
+        .filter(|c| !matches!(c, '_' | '-' | ' '))
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
 /// Store for user-related data
 #[derive(Default)]
 pub struct UserStore {
+    #[allow(dead_code)]
     users: Mutex<Vec<User>>,
 }
 
 /// Store for job-related data
 #[derive(Default)]
 pub struct JobStore {
+    #[allow(dead_code)]
     jobs: Mutex<Vec<Job>>,
 }
 
+/// Coalesces concurrent reads of the same `Job` by id. A failed read is shared as its error message.
+///
+/// Create it once outside the `App` factory and share it through `Data`, so reads are coalesced across workers.
+pub type JobReads = SingleFlight<JobId, Result<Option<Job>, String>>;
+
 /// Store for application-related data
 #[derive(Default)]
 pub struct ApplicationStore {
+    #[allow(dead_code)]
     applications: Mutex<Vec<Application>>,
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use super::{ApplicationStatus, EmploymentType, UserRole};
+
+    #[test]
+    fn enums_accept_case_and_separator_variants() {
+        for alias in ["full_time", "Full_Time", "FULL_TIME", "fulltime", "full-time", "Full Time"] {
+            let value: EmploymentType = serde_json::from_value(json!(alias)).unwrap();
+            assert_eq!(value.to_string(), "full_time", "{}", alias);
+        }
+        for alias in ["job_seeker", "JobSeeker", "JOB-SEEKER", "jobseeker"] {
+            let value: UserRole = serde_json::from_value(json!(alias)).unwrap();
+            assert_eq!(value.to_string(), "job_seeker", "{}", alias);
+        }
+        for alias in ["reviewed", "Reviewed", "REVIEWED"] {
+            let value: ApplicationStatus = serde_json::from_value(json!(alias)).unwrap();
+            assert_eq!(value, ApplicationStatus::Reviewed, "{}", alias);
+        }
+    }
+
+    #[test]
+    fn enums_reject_unknown_values() {
+        let error = serde_json::from_value::<EmploymentType>(json!("freelance")).unwrap_err();
+        assert_eq!(error.to_string(), "unknown employment_type \"freelance\", expected one of full_time, part_time, contract");
+        assert!(serde_json::from_value::<UserRole>(json!("admin")).is_err());
+        assert!(serde_json::from_value::<ApplicationStatus>(json!("hired")).is_err());
+    }
+}
@@ -2,23 +2,26 @@ use std::fmt;
 use chrono::{DateTime, Utc};
 use rusqlite::{Error, ToSql};
 use rusqlite::types::{FromSql, FromSqlResult, ToSqlOutput, ValueRef};
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
 use utoipa::ToSchema;
+use crate::models::{deserialize_variant, ApplicationId, ApplicationStatus, EmploymentType, InvalidEnumValue, JobId, UserId};
+use crate::utils::FieldError;
 
 /// User object
 #[derive(Serialize, Deserialize, ToSchema, Clone, Debug)]
 pub struct User {
     /// Table id for the User.
     #[schema(example = 1)]
-    pub id: i64,
+    pub id: UserId,
     /// Full name of the user.
     #[schema(example = "John Doe")]
     pub name: String,
     /// Email address of the user.
     #[schema(example = "john.doe@example.com")]
     pub email: String,
-    /// Hashed password for the user.
-    #[schema(example = "hashed_password_here")]
+    /// Argon2 hash of the user's password. Never included in responses.
+    #[serde(default, skip_serializing)]
+    #[schema(write_only, example = "$argon2id$v=19$m=19456,t=2,p=1$c2FsdHNhbHQ$aGFzaGhhc2hoYXNoaGFzaA")]
     pub password: String,
     /// Role of the user, either `job_seeker` or `employer`.
     #[schema(example = "job_seeker")]
@@ -33,6 +36,100 @@ pub struct User {
     #[serde(rename = "updated_at")]
     #[schema(example = "2024-09-16T15:30:00Z")]
     pub updated_at: DateTime<Utc>,
+    /// Short free-form description of the user.
+    #[serde(default)]
+    #[schema(example = "Backend engineer with a passion for Rust.")]
+    pub bio: Option<String>,
+    /// URL of the user's avatar image.
+    #[serde(default)]
+    #[schema(example = "https://example.com/avatar.png")]
+    pub avatar_url: Option<String>,
+    /// Contact phone number.
+    #[serde(default)]
+    #[schema(example = "+1 (555) 123-4567")]
+    pub phone: Option<String>,
+}
+
+impl User {
+    /// Fields that can be selected through the `fields` query parameter.
+    ///
+    /// The password is deliberately left out so it can never be requested on its own.
+    pub const FIELDS: &'static [&'static str] = &[
+        "id",
+        "name",
+        "email",
+        "role",
+        "created_at",
+        "updated_at",
+        "bio",
+        "avatar_url",
+        "phone",
+    ];
+
+    /// Validate a complete `User`, e.g. one read from a backup.
+    pub fn validate(&self) -> Result<(), Vec<FieldError>> {
+        let mut errors = Vec::new();
+
+        for (field, value) in [("name", &self.name), ("email", &self.email)] {
+            if value.trim().is_empty() {
+                errors.push(FieldError {
+                    field: field.to_string(),
+                    message: format!("{} is required", field),
+                });
+            }
+        }
+        validate_profile(&self.avatar_url, &self.phone, &mut errors);
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// How complete the optional part of the user's profile is. Blank fields count as missing.
+    pub fn completeness(&self) -> ProfileCompleteness {
+        let missing: Vec<String> = [("bio", &self.bio), ("avatar_url", &self.avatar_url), ("phone", &self.phone)]
+            .into_iter()
+            .filter(|(_, value)| value.as_deref().map_or(true, |value| value.trim().is_empty()))
+            .map(|(field, _)| field.to_string())
+            .collect();
+        let filled = ProfileCompleteness::FIELDS - missing.len();
+        ProfileCompleteness {
+            user_id: self.id,
+            score: (filled * 100 / ProfileCompleteness::FIELDS) as u8,
+            missing,
+        }
+    }
+}
+
+/// How much of a user's optional profile is filled in.
+#[derive(Serialize, Deserialize, ToSchema, Clone, Debug)]
+pub struct ProfileCompleteness {
+    /// The user the score belongs to.
+    #[schema(example = 2)]
+    pub user_id: UserId,
+    /// Percentage of the optional profile fields that are filled in, from 0 to 100.
+    #[schema(example = 66)]
+    pub score: u8,
+    /// Optional profile fields that are still empty: `bio`, `avatar_url` and `phone`.
+    #[schema(example = json!(["avatar_url"]))]
+    pub missing: Vec<String>,
+}
+
+impl ProfileCompleteness {
+    /// Number of optional profile fields the score is based on.
+    pub const FIELDS: usize = 3;
+}
+
+/// An employer along with the number of jobs it currently has open.
+#[derive(Serialize, Deserialize, ToSchema, Clone, Debug)]
+pub struct Employer {
+    /// The employer. The password is never included.
+    pub user: User,
+    /// Number of the employer's jobs that are open and whose deadline has not passed.
+    #[schema(example = 3)]
+    pub open_job_count: i64,
 }
 
 /// Request to update existing `User` item.
@@ -44,16 +141,231 @@ pub struct UserUpdateRequest {
     /// Optional new value for the `User` email.
     #[schema(example = "jane.doe@example.com")]
     pub email: Option<String>,
-    /// Optional new value for the `User` password.
-    #[schema(example = "new_hashed_password_here")]
+    /// Optional new value for the `User` password, hashed before it is stored.
+    #[schema(example = "correct horse battery staple")]
     pub password: Option<String>,
     /// Optional new value for the `User` role.
     #[schema(example = "employer")]
     pub role: Option<UserRole>,
+    /// Optional new value for the `User` bio.
+    #[serde(default)]
+    #[schema(example = "Hiring manager at Example Corp.")]
+    pub bio: Option<String>,
+    /// Optional new value for the `User` avatar_url.
+    #[serde(default)]
+    #[schema(example = "https://example.com/new_avatar.png")]
+    pub avatar_url: Option<String>,
+    /// Optional new value for the `User` phone.
+    #[serde(default)]
+    #[schema(example = "+46 70 123 45 67")]
+    pub phone: Option<String>,
 }
 
-/// Enum for user roles.
+impl UserUpdateRequest {
+    /// Validate the profile fields that cannot be expressed through the type system.
+    pub fn validate(&self) -> Result<(), Vec<FieldError>> {
+        let mut errors = Vec::new();
+        validate_profile(&self.avatar_url, &self.phone, &mut errors);
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
+/// Jobs a job seeker prefers, used to tailor their recommended jobs.
+#[derive(Serialize, Deserialize, ToSchema, Clone, Debug)]
+pub struct JobPreferences {
+    /// The job seeker the preferences belong to.
+    pub user_id: UserId,
+    /// Preferred employment types. Empty when the seeker has no preference.
+    #[schema(example = json!(["full_time", "contract"]))]
+    pub employment_types: Vec<EmploymentType>,
+    /// Preferred locations, matched case-insensitively against job locations.
+    #[schema(example = json!(["Berlin", "Remote"]))]
+    pub locations: Vec<String>,
+    /// Lowest acceptable salary. Jobs whose highest listed amount is below it are not recommended.
+    #[schema(example = 60000)]
+    pub min_salary: Option<i64>,
+    /// Timestamp of when the preferences were last saved, or `null` if they never were.
+    #[serde(with = "chrono::serde::ts_seconds_option")]
+    #[schema(value_type = Option<i64>, example = 1726500600)]
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+impl JobPreferences {
+    /// Most locations a job seeker can prefer.
+    pub const MAX_LOCATIONS: usize = 20;
+}
+
+/// Request to replace a job seeker's `JobPreferences`.
+#[derive(Serialize, Deserialize, ToSchema, Clone, Debug)]
+pub struct JobPreferencesRequest {
+    /// Preferred employment types.
+    #[serde(default)]
+    #[schema(example = json!(["full_time", "contract"]))]
+    pub employment_types: Vec<EmploymentType>,
+    /// Preferred locations.
+    #[serde(default)]
+    #[schema(example = json!(["Berlin", "Remote"]))]
+    pub locations: Vec<String>,
+    /// Lowest acceptable salary.
+    #[serde(default)]
+    #[schema(example = 60000)]
+    pub min_salary: Option<i64>,
+}
+
+impl JobPreferencesRequest {
+    /// Validate the preferences the way the matching `Job` fields are validated.
+    pub fn validate(&self) -> Result<(), Vec<FieldError>> {
+        let mut errors = Vec::new();
+
+        if self.locations.iter().any(|location| location.trim().is_empty()) {
+            errors.push(FieldError {
+                field: "locations".to_string(),
+                message: "locations must not be empty".to_string(),
+            });
+        }
+        if self.locations.len() > JobPreferences::MAX_LOCATIONS {
+            errors.push(FieldError {
+                field: "locations".to_string(),
+                message: format!("at most {} locations can be preferred", JobPreferences::MAX_LOCATIONS),
+            });
+        }
+
+        if self.min_salary.is_some_and(|min_salary| min_salary < 0) {
+            errors.push(FieldError {
+                field: "min_salary".to_string(),
+                message: "min_salary must not be negative".to_string(),
+            });
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+/// Kind of an `ActivityEvent`.
+#[derive(Serialize, Deserialize, ToSchema, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ActivityKind {
+    /// The job seeker submitted an application.
+    ApplicationSubmitted,
+    /// The status of one of the job seeker's applications changed.
+    StatusChanged,
+}
+
+impl ActivityKind {
+    /// Name of the kind as used in `type` and in cursors.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ActivityKind::ApplicationSubmitted => "application_submitted",
+            ActivityKind::StatusChanged => "status_changed",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<ActivityKind> {
+        match value {
+            "application_submitted" => Some(ActivityKind::ApplicationSubmitted),
+            "status_changed" => Some(ActivityKind::StatusChanged),
+            _ => None,
+        }
+    }
+}
+
+/// An entry in a job seeker's activity feed.
 #[derive(Serialize, Deserialize, ToSchema, Clone, Debug)]
+pub struct ActivityEvent {
+    /// What happened.
+    #[serde(rename = "type")]
+    #[schema(example = "status_changed")]
+    pub kind: ActivityKind,
+    /// Id of the event, unique among events of the same `type`.
+    #[schema(example = 7)]
+    pub event_id: i64,
+    /// The application the event is about.
+    pub application_id: ApplicationId,
+    /// The job applied to.
+    pub job_id: JobId,
+    /// Title of the job, absent if the job was deleted.
+    #[schema(example = "Software Engineer")]
+    pub job_title: Option<String>,
+    /// Status the application changed to, for `status_changed` events.
+    #[schema(example = "accepted")]
+    pub status: Option<ApplicationStatus>,
+    /// When the event happened.
+    #[serde(with = "chrono::serde::ts_seconds")]
+    #[schema(value_type = i64, example = 1726500600)]
+    pub at: DateTime<Utc>,
+}
+
+impl ActivityEvent {
+    /// Cursor resuming a feed after this event.
+    pub fn cursor(&self) -> String {
+        format!("{}:{}", self.kind.as_str(), self.event_id)
+    }
+}
+
+/// Push an error for every invalid profile field.
+fn validate_profile(avatar_url: &Option<String>, phone: &Option<String>, errors: &mut Vec<FieldError>) {
+    if let Some(avatar_url) = avatar_url {
+        if !is_valid_url(avatar_url) {
+            errors.push(FieldError {
+                field: "avatar_url".to_string(),
+                message: "avatar_url must be an absolute http(s) URL".to_string(),
+            });
+        }
+    }
+
+    if let Some(phone) = phone {
+        if !is_valid_phone(phone) {
+            errors.push(FieldError {
+                field: "phone".to_string(),
+                message: "phone must contain 7 to 15 digits and only +, spaces, dashes, dots or parentheses".to_string(),
+            });
+        }
+    }
+}
+
+/// Loosely check an email address: a non-empty local part and a dotted domain separated by a single `@`.
+pub fn is_valid_email(email: &str) -> bool {
+    match email.split_once('@') {
+        Some((local, domain)) => {
+            !local.is_empty()
+                && !domain.contains('@')
+                && domain.split('.').count() > 1
+                && domain.split('.').all(|label| !label.is_empty())
+                && !email.chars().any(|c| c.is_whitespace() || c.is_control())
+        }
+        None => false,
+    }
+}
+
+/// Check that `url` is an absolute `http`/`https` URL with a host.
+fn is_valid_url(url: &str) -> bool {
+    let rest = match url.strip_prefix("https://").or_else(|| url.strip_prefix("http://")) {
+        Some(rest) => rest,
+        None => return false,
+    };
+    let host = rest.split(['/', '?', '#']).next().unwrap_or_default();
+    !host.is_empty() && !url.chars().any(char::is_whitespace)
+}
+
+/// Loosely check a phone number: 7 to 15 digits with common separators.
+fn is_valid_phone(phone: &str) -> bool {
+    let digits = phone.chars().filter(char::is_ascii_digit).count();
+    (7..=15).contains(&digits)
+        && phone
+            .chars()
+            .all(|c| c.is_ascii_digit() || matches!(c, '+' | ' ' | '-' | '.' | '(' | ')'))
+}
+
+/// Enum for user roles.
+#[derive(Serialize, ToSchema, Clone, Debug)]
 pub enum UserRole {
     #[schema(rename = "job_seeker")]
     JobSeeker,
@@ -61,8 +373,22 @@ pub enum UserRole {
     Employer,
 }
 
+impl UserRole {
+    /// Every `UserRole` variant, in declaration order.
+    pub const ALL: &'static [UserRole] = &[
+        UserRole::JobSeeker,
+        UserRole::Employer,
+    ];
+}
+
+impl<'de> Deserialize<'de> for UserRole {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserialize_variant(deserializer, "user_role", UserRole::ALL)
+    }
+}
+
 impl ToSql for UserRole {
-    fn to_sql(&self) -> Result<ToSqlOutput, Error> {
+    fn to_sql(&self) -> Result<ToSqlOutput<'_>, Error> {
         Ok(ToSqlOutput::from(self.to_string()))
     }
 }
@@ -73,7 +399,7 @@ impl FromSql for UserRole {
         match s.as_str() {
             "job_seeker" => Ok(UserRole::JobSeeker),
             "employer" => Ok(UserRole::Employer),
-            _ => Err(rusqlite::types::FromSqlError::InvalidType),
+            other => Err(InvalidEnumValue::from_sql("user_role", other)),
         }
     }
 }
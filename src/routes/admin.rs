@@ -0,0 +1,555 @@
+use std::error::Error;
+use actix_web::{get, post, HttpResponse, Responder};
+use actix_web::http::header::{ContentDisposition, DispositionParam, DispositionType};
+use actix_web::web::{self, Data, Json, Query, ServiceConfig};
+use chrono::Utc;
+use log::{error, info};
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use crate::auth::middleware::{ApiKey, RequireApiKey};
+use crate::config::features::FeatureFlags;
+use crate::config::Settings;
+use crate::db::{Pool, ReadPool, application, attachment, backup, job, note, preferences, tag, user};
+use crate::models::backup::{Backup, RestoreSummary};
+use crate::models::User;
+use crate::utils::fields::select_page;
+use crate::utils::maintenance::MaintenanceMode;
+use crate::utils::{ErrorResponse, PaginationApplication, PaginationJob, PaginationUser};
+
+/// Whether maintenance mode is on.
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct Maintenance {
+    /// Whether writes are rejected with `503`.
+    #[schema(example = true)]
+    pub enabled: bool,
+}
+
+/// Longest search term accepted by the admin search.
+const MAX_SEARCH_TERM_LENGTH: usize = 100;
+
+/// Largest number of results returned per section of the admin search.
+const MAX_SEARCH_LIMIT: i64 = 50;
+
+/// Mount the admin endpoints under `/admin`, always behind `api_key`.
+///
+/// The key is required even when other endpoints only log a missing key. Without a configured key the endpoints are
+/// not mounted at all, so they are never reachable unauthenticated.
+pub(crate) fn configure(settings: Data<Settings>, api_key: Option<ApiKey>) -> impl FnOnce(&mut ServiceConfig) {
+    move |config: &mut ServiceConfig| {
+        let Some(api_key) = api_key else {
+            return;
+        };
+        config.service(
+            web::scope("/admin")
+                .wrap(RequireApiKey(api_key))
+                .app_data(settings)
+                .service(get_config)
+                .service(get_features)
+                .service(get_backup)
+                .service(restore_backup)
+                .service(search)
+                .service(get_maintenance)
+                .service(set_maintenance),
+        );
+    }
+}
+
+/// Get the currently effective configuration.
+///
+/// This endpoint needs `api_key` authentication in order to call and is intended for administrators.
+///
+/// Return the resolved `Settings` with every secret redacted, to help troubleshoot environment issues.
+#[utoipa::path(
+    context_path = "/v1/admin",
+    tag = "admin",
+    responses(
+        (status = 200, description = "Effective configuration with secrets redacted", body = Settings),
+        (status = 401, description = "Unauthorized to get configuration", body = ErrorResponse, example = json!(ErrorResponse::Unauthorized(String::from("missing api key"))))
+    ),
+    security(
+        ("api_key" = [])
+    )
+)]
+#[get("/config")]
+pub(super) async fn get_config(settings: Data<Settings>) -> impl Responder {
+    HttpResponse::Ok().json(settings.get_ref())
+}
+
+/// Get the state of every feature flag.
+///
+/// This endpoint needs `api_key` authentication in order to call and is intended for administrators.
+///
+/// Flags are read from their `FEATURE_*` environment variables at startup, so changing one takes a restart.
+#[utoipa::path(
+    context_path = "/v1/admin",
+    tag = "admin",
+    responses(
+        (status = 200, description = "Feature flags in declaration order", body = [FeatureFlag]),
+        (status = 401, description = "Unauthorized to get feature flags", body = ErrorResponse, example = json!(ErrorResponse::Unauthorized(String::from("missing api key"))))
+    ),
+    security(
+        ("api_key" = [])
+    )
+)]
+#[get("/features")]
+pub(super) async fn get_features(features: Data<FeatureFlags>) -> impl Responder {
+    HttpResponse::Ok().json(features.flags())
+}
+
+/// Get whether maintenance mode is on.
+///
+/// This endpoint needs `api_key` authentication in order to call and is intended for administrators.
+#[utoipa::path(
+    context_path = "/v1/admin",
+    tag = "admin",
+    responses(
+        (status = 200, description = "Maintenance mode state", body = Maintenance),
+        (status = 401, description = "Unauthorized to get maintenance mode", body = ErrorResponse, example = json!(ErrorResponse::Unauthorized(String::from("missing api key"))))
+    ),
+    security(
+        ("api_key" = [])
+    )
+)]
+#[get("/maintenance")]
+pub(super) async fn get_maintenance(mode: Data<MaintenanceMode>) -> impl Responder {
+    HttpResponse::Ok().json(Maintenance { enabled: mode.is_enabled() })
+}
+
+/// Turn maintenance mode on or off.
+///
+/// This endpoint needs `api_key` authentication in order to call and is intended for administrators.
+///
+/// While maintenance mode is on, every `POST`, `PUT`, `PATCH` and `DELETE` request except this one is rejected with
+/// `503`, while reads keep working. The state is kept in memory and resets to `MAINTENANCE_MODE` on restart.
+#[utoipa::path(
+    context_path = "/v1/admin",
+    tag = "admin",
+    request_body = Maintenance,
+    responses(
+        (status = 200, description = "Maintenance mode updated", body = Maintenance),
+        (status = 401, description = "Unauthorized to set maintenance mode", body = ErrorResponse, example = json!(ErrorResponse::Unauthorized(String::from("missing api key"))))
+    ),
+    security(
+        ("api_key" = [])
+    )
+)]
+#[post("/maintenance")]
+pub(super) async fn set_maintenance(mode: Data<MaintenanceMode>, request: Json<Maintenance>) -> impl Responder {
+    mode.set(request.enabled);
+    info!("Maintenance mode {}", if request.enabled { "enabled" } else { "disabled" });
+    HttpResponse::Ok().json(Maintenance { enabled: mode.is_enabled() })
+}
+
+/// Download a JSON backup of the database.
+///
+/// This endpoint needs `api_key` authentication in order to call and is intended for administrators.
+///
+/// Return every user, job, application, application attachment, note, job tag, saved preference and application status
+/// change as a single JSON document served as a file download. User passwords are left out.
+#[utoipa::path(
+    context_path = "/v1/admin",
+    tag = "admin",
+    responses(
+        (status = 200, description = "Database backup", body = Backup),
+        (status = 401, description = "Unauthorized to back up the database", body = ErrorResponse, example = json!(ErrorResponse::Unauthorized(String::from("missing api key")))),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    ),
+    security(
+        ("api_key" = [])
+    )
+)]
+#[get("/backup")]
+pub(super) async fn get_backup(pool: Data<ReadPool>) -> impl Responder {
+    let mut conn = match pool.get() {
+        Ok(conn) => conn,
+        Err(e) => {
+            error!("Error connecting to the database: {:?}", e);
+            return HttpResponse::InternalServerError().json(ErrorResponse::InternalError(
+                "Error connecting to the database".to_string(),
+            ));
+        }
+    };
+
+    // A negative LIMIT means no limit in SQLite.
+    let backup = user::get_all(&mut conn, -1, 0).and_then(|users| {
+        Ok(Backup {
+            created_at: Utc::now(),
+            users,
+            jobs: job::get_all(&mut conn, -1, 0)?,
+            applications: application::get_all(&mut conn, -1, 0)?,
+            attachments: attachment::get_all(&mut conn)?,
+            notes: note::get_all(&mut conn)?,
+            job_tags: tag::get_all_job_tags(&mut conn)?,
+            preferences: preferences::get_all(&mut conn)?,
+            status_changes: application::get_all_status_changes(&mut conn)?,
+        })
+    });
+
+    match backup {
+        Ok(backup) => {
+            info!(
+                "Created backup with {} users, {} jobs, {} applications and {} attachments",
+                backup.users.len(),
+                backup.jobs.len(),
+                backup.applications.len(),
+                backup.attachments.len()
+            );
+            let filename = format!("backup-{}.json", backup.created_at.format("%Y%m%dT%H%M%SZ"));
+            HttpResponse::Ok()
+                .insert_header(ContentDisposition {
+                    disposition: DispositionType::Attachment,
+                    parameters: vec![DispositionParam::Filename(filename)],
+                })
+                .json(backup)
+        }
+        Err(e) => {
+            error!("Error creating backup: {:?}", e);
+            HttpResponse::InternalServerError().json(ErrorResponse::InternalError(
+                "Error creating backup".to_string(),
+            ))
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub(super) struct RestoreQuery {
+    /// Delete all existing data before restoring.
+    wipe: Option<bool>,
+}
+
+/// Restore the database from a JSON backup.
+///
+/// This endpoint needs `api_key` authentication in order to call and is intended for administrators.
+///
+/// Insert every record of a `Backup` with its original id, in a single transaction. Pass `wipe=true` to first delete
+/// every kind of record a backup contains, so the database ends up exactly as backed up. If any record is invalid or conflicts with existing data nothing is restored, and the error
+/// names the failing section and record index. Backups leave out passwords, so restored users must reset their
+/// password before they can log in.
+#[utoipa::path(
+    context_path = "/v1/admin",
+    tag = "admin",
+    params(
+        ("wipe" = Option<bool>, Query, description = "Delete all existing data before restoring"),
+    ),
+    request_body = Backup,
+    responses(
+        (status = 200, description = "Backup restored", body = RestoreSummary),
+        (status = 400, description = "Invalid backup", body = ErrorResponse, example = json!(ErrorResponse::BadRequest(String::from("jobs[3]: title is required")))),
+        (status = 401, description = "Unauthorized to restore the database", body = ErrorResponse, example = json!(ErrorResponse::Unauthorized(String::from("missing api key")))),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    ),
+    security(
+        ("api_key" = [])
+    )
+)]
+#[post("/restore")]
+pub(super) async fn restore_backup(
+    pool: Data<Pool>,
+    query: Query<RestoreQuery>,
+    body: Json<Backup>,
+) -> impl Responder {
+    let mut conn = match pool.get() {
+        Ok(conn) => conn,
+        Err(e) => {
+            error!("Error connecting to the database: {:?}", e);
+            return HttpResponse::InternalServerError().json(ErrorResponse::InternalError(
+                "Error connecting to the database".to_string(),
+            ));
+        }
+    };
+
+    match backup::restore(&mut conn, &body, query.wipe.unwrap_or(false)) {
+        Ok(counts) => HttpResponse::Ok().json(RestoreSummary {
+            users: counts.users,
+            jobs: counts.jobs,
+            applications: counts.applications,
+            attachments: counts.attachments,
+            notes: counts.notes,
+            job_tags: counts.job_tags,
+            preferences: counts.preferences,
+            status_changes: counts.status_changes,
+        }),
+        Err(e) => {
+            error!("Error restoring backup: {}", e);
+            HttpResponse::BadRequest().json(ErrorResponse::BadRequest(e.to_string()))
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub(super) struct SearchQuery {
+    q: Option<String>,
+    limit: Option<i64>,
+    users_offset: Option<i64>,
+    jobs_offset: Option<i64>,
+    applications_offset: Option<i64>,
+}
+
+/// Matches of an admin search, paginated independently per section.
+#[derive(Serialize, ToSchema)]
+pub struct SearchResults {
+    /// Users whose name or email matches. Passwords are left out.
+    pub users: PaginationUser,
+    /// Jobs whose title or description matches.
+    pub jobs: PaginationJob,
+    /// Applications whose reference matches.
+    pub applications: PaginationApplication,
+}
+
+/// Search users, jobs and applications at once.
+///
+/// This endpoint needs `api_key` authentication in order to call and is intended for administrators.
+///
+/// Return users matching by name or email, jobs matching by title or description and applications matching by
+/// reference. Matching is a substring match that ignores case and accents, so `cafe` finds `Café`. Each section is paginated with its own offset and holds
+/// at most `limit` items (10 by default, at most 50).
+#[utoipa::path(
+    context_path = "/v1/admin",
+    tag = "admin",
+    params(
+        ("q" = String, Query, description = "Search term", example = "rust"),
+        ("limit" = Option<i64>, Query, description = "Maximum number of items per section", example = 10),
+        ("users_offset" = Option<i64>, Query, description = "Offset into the matching users", example = 0),
+        ("jobs_offset" = Option<i64>, Query, description = "Offset into the matching jobs", example = 0),
+        ("applications_offset" = Option<i64>, Query, description = "Offset into the matching applications", example = 0),
+    ),
+    responses(
+        (status = 200, description = "Search results", body = SearchResults),
+        (status = 400, description = "Invalid search", body = ErrorResponse, example = json!(ErrorResponse::BadRequest(String::from("q is required")))),
+        (status = 401, description = "Unauthorized to search", body = ErrorResponse, example = json!(ErrorResponse::Unauthorized(String::from("missing api key")))),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    ),
+    security(
+        ("api_key" = [])
+    )
+)]
+#[get("/search")]
+pub(super) async fn search(pool: Data<ReadPool>, query: Query<SearchQuery>) -> impl Responder {
+    let term = query.q.as_deref().map(str::trim).unwrap_or_default();
+    if term.is_empty() {
+        return HttpResponse::BadRequest().json(ErrorResponse::BadRequest("q is required".to_string()));
+    }
+    if term.len() > MAX_SEARCH_TERM_LENGTH {
+        return HttpResponse::BadRequest().json(ErrorResponse::BadRequest(format!(
+            "q must be at most {} characters",
+            MAX_SEARCH_TERM_LENGTH
+        )));
+    }
+    let limit = query.limit.unwrap_or(10);
+    if !(1..=MAX_SEARCH_LIMIT).contains(&limit) {
+        return HttpResponse::BadRequest().json(ErrorResponse::BadRequest(format!(
+            "limit must be between 1 and {}",
+            MAX_SEARCH_LIMIT
+        )));
+    }
+    let offsets = [query.users_offset, query.jobs_offset, query.applications_offset].map(|offset| offset.unwrap_or(0));
+    if offsets.iter().any(|offset| *offset < 0) {
+        return HttpResponse::BadRequest().json(ErrorResponse::BadRequest(
+            "offsets must not be negative".to_string(),
+        ));
+    }
+
+    let mut conn = match pool.get() {
+        Ok(conn) => conn,
+        Err(e) => {
+            error!("Error connecting to the database: {:?}", e);
+            return HttpResponse::InternalServerError().json(ErrorResponse::InternalError(
+                "Error connecting to the database".to_string(),
+            ));
+        }
+    };
+
+    let results = search_all(&mut conn, term, limit, offsets);
+
+    match results {
+        Ok(results) => {
+            let user_fields = Some(User::FIELDS.iter().map(ToString::to_string).collect());
+            let mut body = serde_json::to_value(&results).unwrap_or_default();
+            body["users"] = select_page(&results.users, &user_fields);
+            HttpResponse::Ok().json(body)
+        }
+        Err(e) => {
+            error!("Error searching for {:?}: {:?}", term, e);
+            HttpResponse::InternalServerError().json(ErrorResponse::InternalError(
+                "Error searching the database".to_string(),
+            ))
+        }
+    }
+}
+
+/// Run the admin search, with `offsets` for the users, jobs and applications sections in that order.
+fn search_all(
+    conn: &mut Connection,
+    term: &str,
+    limit: i64,
+    offsets: [i64; 3],
+) -> Result<SearchResults, Box<dyn Error>> {
+    let [users_offset, jobs_offset, applications_offset] = offsets;
+    let (users, user_count) = user::search(conn, term, limit, users_offset)?;
+    let (jobs, job_count) = job::search(conn, term, limit, jobs_offset)?;
+    let (applications, application_count) = application::search(conn, term, limit, applications_offset)?;
+    Ok(SearchResults {
+        users: PaginationUser {
+            page: users_offset / limit + 1,
+            count: user_count,
+            snapshot_count: user_count,
+            items: users,
+        },
+        jobs: PaginationJob {
+            page: jobs_offset / limit + 1,
+            count: job_count,
+            snapshot_count: job_count,
+            items: jobs,
+        },
+        applications: PaginationApplication {
+            page: applications_offset / limit + 1,
+            count: application_count,
+            snapshot_count: application_count,
+            items: applications,
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{test, web, App};
+    use actix_web::http::header::{self, HeaderName};
+    use actix_web::http::StatusCode;
+    use actix_web::web::Data;
+    use serde_json::Value;
+    use crate::auth::middleware::ApiKey;
+    use crate::config::Settings;
+    use crate::utils::maintenance::MaintenanceMode;
+    use crate::utils::testing::TestDb;
+    use super::{configure, get_backup, restore_backup, search};
+
+    fn settings() -> Data<Settings> {
+        if std::env::var("DATABASE_URL").is_err() {
+            std::env::set_var("DATABASE_URL", "unused.db");
+        }
+        Data::new(Settings::from_env())
+    }
+
+    #[actix_web::test]
+    async fn admin_endpoints_require_a_configured_key() {
+        let api_key = ApiKey::new(HeaderName::from_static("x-api-key"), "secret");
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(MaintenanceMode::new(false)))
+                .configure(configure(settings(), Some(api_key))),
+        )
+        .await;
+        let response = test::call_service(&app, test::TestRequest::get().uri("/admin/maintenance").to_request()).await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        let request = test::TestRequest::get().uri("/admin/maintenance").insert_header(("x-api-key", "secret"));
+        assert_eq!(test::call_service(&app, request.to_request()).await.status(), StatusCode::OK);
+
+        let unmounted = test::init_service(
+            App::new()
+                .app_data(Data::new(MaintenanceMode::new(false)))
+                .configure(configure(settings(), None)),
+        )
+        .await;
+        let response = test::call_service(&unmounted, test::TestRequest::get().uri("/admin/maintenance").to_request()).await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[actix_web::test]
+    async fn backup_is_a_downloadable_json_document() {
+        let db = TestDb::new();
+        let employer = db.user("employer@example.com", "employer", "$argon2id$v=19$m=19456,t=2,p=1$c2FsdA$aGFzaA");
+        let seeker = db.user("seeker@example.com", "job_seeker", "!");
+        db.application(db.job(employer), seeker);
+        let app = test::init_service(
+            App::new().app_data(db.read_pool()).service(web::scope("/admin").service(get_backup)),
+        )
+        .await;
+
+        let response = test::call_service(&app, test::TestRequest::get().uri("/admin/backup").to_request()).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let disposition = response.headers().get(header::CONTENT_DISPOSITION).unwrap().to_str().unwrap();
+        assert!(disposition.starts_with("attachment; filename=\"backup-"), "{}", disposition);
+        let backup: Value = test::read_body_json(response).await;
+        for key in ["created_at", "users", "jobs", "applications"] {
+            assert!(backup.get(key).is_some(), "{}", key);
+        }
+        assert_eq!(backup["users"].as_array().unwrap().len(), 2);
+        assert_eq!(backup["jobs"].as_array().unwrap().len(), 1);
+        assert_eq!(backup["applications"].as_array().unwrap().len(), 1);
+        assert!(backup["users"].as_array().unwrap().iter().all(|user| user.get("password").is_none()));
+    }
+
+    #[actix_web::test]
+    async fn backup_round_trips_through_a_wiped_restore() {
+        let db = TestDb::new();
+        let employer = db.user("employer@example.com", "employer", "!");
+        let seeker = db.user("seeker@example.com", "job_seeker", "!");
+        let job = db.job(employer);
+        let application = db.application(job, seeker);
+        db.conn()
+            .execute_batch(&format!(
+                "INSERT INTO application_attachments (application_id, url, kind, uploaded_at)
+                     VALUES ({application}, 'https://example.com/cv.pdf', 'resume', '2024-09-16T12:00:00+00:00');
+                 INSERT INTO application_notes (application_id, author_id, body, created_at)
+                     VALUES ({application}, {employer}, 'Call back', '2024-09-16T12:00:00+00:00');
+                 INSERT INTO tags (name) VALUES ('rust');
+                 INSERT INTO job_tags (job_id, tag_id) SELECT {job}, id FROM tags WHERE name = 'rust';
+                 INSERT INTO user_preferences (user_id, employment_types, locations, min_salary, updated_at)
+                     VALUES ({seeker}, '[\"contract\"]', '[\"Berlin\"]', 50000, '2024-09-16T12:00:00+00:00');
+                 UPDATE applications SET status = 'reviewed' WHERE id = {application};"
+            ))
+            .unwrap();
+        let app = test::init_service(
+            App::new()
+                .app_data(db.pool())
+                .app_data(db.read_pool())
+                .service(web::scope("/admin").service(get_backup).service(restore_backup)),
+        )
+        .await;
+
+        let mut before: Value = test::call_and_read_body_json(&app, test::TestRequest::get().uri("/admin/backup").to_request()).await;
+        for section in ["jobs", "applications", "attachments", "notes", "job_tags", "preferences", "status_changes"] {
+            assert_eq!(before[section].as_array().unwrap().len(), 1, "{}", section);
+        }
+        let restore = test::TestRequest::post().uri("/admin/restore?wipe=true").set_json(&before).to_request();
+        assert_eq!(test::call_service(&app, restore).await.status(), StatusCode::OK);
+        let mut after: Value = test::call_and_read_body_json(&app, test::TestRequest::get().uri("/admin/backup").to_request()).await;
+
+        before["created_at"] = Value::Null;
+        after["created_at"] = Value::Null;
+        assert_eq!(before, after);
+        let tombstones: i64 = db.conn().query_row("SELECT COUNT(*) FROM job_tombstones", [], |row| row.get(0)).unwrap();
+        assert_eq!(tombstones, 0);
+    }
+
+    #[actix_web::test]
+    async fn search_ignores_case_and_accents() {
+        let db = TestDb::new();
+        let employer = db.user("zoe@example.com", "employer", "!");
+        let job = db.job(employer);
+        db.conn()
+            .execute_batch(&format!(
+                "UPDATE users SET name = 'Zoë Ångström' WHERE id = {employer};
+                 UPDATE jobs SET title = 'Barista at Café Nørd' WHERE id = {job};"
+            ))
+            .unwrap();
+        let app = test::init_service(App::new().app_data(db.read_pool()).service(search)).await;
+
+        for (q, users, jobs) in [
+            ("zoe angstrom", 1, 0),
+            ("ZOË", 1, 0),
+            ("cafe", 0, 1),
+            ("CAFÉ", 0, 1),
+            ("Barista", 0, 1),
+            ("nord", 0, 0),
+            ("nørd", 0, 1),
+        ] {
+            let encoded: String = q.bytes().map(|byte| format!("%{:02X}", byte)).collect();
+            let uri = format!("/search?q={}", encoded);
+            let results: Value = test::call_and_read_body_json(&app, test::TestRequest::get().uri(&uri).to_request()).await;
+            assert_eq!(results["users"]["count"], users, "{}", q);
+            assert_eq!(results["jobs"]["count"], jobs, "{}", q);
+        }
+    }
+}
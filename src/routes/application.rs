@@ -1,20 +1,50 @@
-use std::env;
 use actix_web::{delete, get, post, put, HttpResponse, Responder};
+use actix_web::http::header;
 use actix_web::web::{Data, Json, Path, Query, ServiceConfig};
-use chrono::Utc;
-use rusqlite::Connection;
 use serde::Deserialize;
+use chrono::Utc;
 use log::{error, info};
-use crate::db::application;
-use crate::models::application::{Application, ApplicationUpdateRequest};
-use crate::models::ApplicationStore;
-use crate::utils::{ErrorResponse, PaginationApplication};
+use rusqlite::Connection;
+use crate::config;
+use crate::db::{Pool, ReadPool, application, attachment, job, note, user};
+use crate::models::application::{Application, ApplicationStatusCount, ApplicationUpdateRequest, AttachmentRequest, NoteRequest, RatingRequest, RejectPendingRequest, RejectPendingResult};
+use crate::models::{ApplicationId, ApplicationStore, JobId, UserId, UserRole};
+use crate::utils::list::ListParams;
+use crate::utils::fields::{parse_fields, select, select_page, FieldsQuery};
+use crate::utils::pdf;
+use crate::utils::queue::{SideEffect, WorkQueue};
+use crate::utils::{ErrorResponse, PaginationApplicant, PaginationApplication, PAGINATION_DRIFT_HEADER, SKIPPED_ROWS_HEADER};
 use utoipa::ToSchema;
 
+#[derive(Deserialize, ToSchema)]
+pub struct EmployerAccessQuery {
+    /// The employer accessing the application. Must own the job the application is for.
+    pub employer_id: UserId,
+}
+
+#[derive(Deserialize)]
+pub struct JobApplicationQuery {
+    pub employer_id: UserId,
+    pub fields: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct JobApplicationsQuery {
+    #[serde(flatten)]
+    pub list: ListParams,
+    pub min_rating: Option<u8>,
+    pub snapshot_count: Option<i64>,
+}
+
+/// Fields the applications to a job can be sorted by.
+const JOB_APPLICATION_SORTS: &[&str] = &["applied_at", "rating"];
+
 #[derive(Deserialize, ToSchema)]
 pub struct ApplicationQuery {
-    pub limit: Option<i64>,
-    pub offset: Option<i64>,
+    #[serde(flatten)]
+    pub list: ListParams,
+    pub fields: Option<String>,
+    pub snapshot_count: Option<i64>,
 }
 
 pub(crate) fn configure(store: Data<ApplicationStore>) -> impl FnOnce(&mut ServiceConfig) {
@@ -23,9 +53,25 @@ pub(crate) fn configure(store: Data<ApplicationStore>) -> impl FnOnce(&mut Servi
             .app_data(store)
             .service(get_applications)
             .service(get_application_by_id)
+            .service(get_application_by_reference)
             .service(create_application)
             .service(update_application)
-            .service(delete_application);
+            .service(delete_application)
+            .service(reopen_application)
+            .service(reject_pending_applications)
+            .service(get_job_applications)
+            .service(rate_application)
+            // Registered before `get_job_application` so `summary` is not parsed as an application id.
+            .service(get_job_application_summary)
+            .service(get_job_application)
+            .service(get_user_application_summary)
+            .service(get_employer_applicants)
+            .service(get_application_attachments)
+            .service(create_application_attachment)
+            .service(delete_application_attachment)
+            .service(get_application_notes)
+            .service(get_application_pdf)
+            .service(create_application_note);
     }
 }
 
@@ -38,11 +84,17 @@ pub(crate) fn configure(store: Data<ApplicationStore>) -> impl FnOnce(&mut Servi
     context_path = "/v1",
     tag = "applications",
     params(
-        ("limit" = Option<usize>, Query, description = "Maximum number of items to return", example = 10),
+        ("limit" = Option<usize>, Query, description = "Maximum number of items to return, at most 100", example = 10),
         ("offset" = Option<usize>, Query, description = "Offset for pagination", example = 0),
+        ("snapshot_count" = Option<i64>, Query, description = "`snapshot_count` returned by the first page, used to detect drift", example = 25),
+        ("fields" = Option<String>, Query, description = "Comma separated list of fields to return", example = "id,job_id,status"),
     ),
     responses(
-        (status = 200, description = "List of applications with pagination metadata", body = PaginationApplication<Application>),
+        (status = 200, description = "List of applications with pagination metadata", body = PaginationApplication, headers(
+            ("X-Pagination-Drift" = i64, description = "Difference between the current total and `snapshot_count`, present only when they differ"),
+            ("X-Skipped-Rows" = usize, description = "Number of malformed rows left out of the page, present only when `STRICT_ROW_DECODING` is off and rows were skipped")
+        )),
+        (status = 400, description = "Unknown field requested or invalid pagination", body = ErrorResponse, example = json!(ErrorResponse::BadRequest(String::from("unknown field: foo")))),
         (status = 401, description = "Unauthorized to get applications", body = ErrorResponse, example = json!(ErrorResponse::Unauthorized(String::from("Missing API Key")))),
         (status = 500, description = "Internal server error", body = ErrorResponse)
     ),
@@ -51,9 +103,17 @@ pub(crate) fn configure(store: Data<ApplicationStore>) -> impl FnOnce(&mut Servi
     )
 )]
 #[get("/applications")]
-pub async fn get_applications(query: Query<ApplicationQuery>) -> impl Responder {
-    let db_url = env::var("DATABASE_URL").unwrap_or_else(|_| "not set".to_string());
-    let mut conn = match Connection::open(&db_url) {
+pub async fn get_applications(pool: Data<ReadPool>, query: Query<ApplicationQuery>) -> impl Responder {
+    let fields = match parse_fields(query.fields.as_deref(), Application::FIELDS) {
+        Ok(fields) => fields,
+        Err(e) => return HttpResponse::BadRequest().json(ErrorResponse::BadRequest(e)),
+    };
+    let page = match query.list.page(&[]) {
+        Ok(page) => page,
+        Err(e) => return HttpResponse::BadRequest().json(ErrorResponse::BadRequest(e)),
+    };
+
+    let mut conn = match pool.get() {
         Ok(conn) => conn,
         Err(e) => {
             error!("Error connecting to the database: {:?}", e);
@@ -63,23 +123,28 @@ pub async fn get_applications(query: Query<ApplicationQuery>) -> impl Responder
         }
     };
 
-    let limit = query.limit.unwrap_or(10) as i64;
-    let offset = query.offset.unwrap_or(0) as i64;
-
     let total_count = application::get_total_count(&mut conn).unwrap_or_else(|e| {
         error!("Error getting total count from the database: {:?}", e);
         0
     });
+    let snapshot_count = query.snapshot_count.unwrap_or(total_count);
 
-    match application::get_all(&mut conn, limit, offset) {
-        Ok(applications) => {
-            let page = (offset / limit) + 1;
+    match application::get_page(&mut conn, page.limit, page.offset, config::strict_row_decoding()) {
+        Ok((applications, skipped)) => {
             let pagination = PaginationApplication {
-                page,
+                page: page.number(),
                 count: total_count,
+                snapshot_count,
                 items: applications,
             };
-            HttpResponse::Ok().json(pagination)
+            let mut response = HttpResponse::Ok();
+            if snapshot_count != total_count {
+                response.insert_header((PAGINATION_DRIFT_HEADER, (total_count - snapshot_count).to_string()));
+            }
+            if skipped > 0 {
+                response.insert_header((SKIPPED_ROWS_HEADER, skipped.to_string()));
+            }
+            response.json(select_page(&pagination, &fields))
         }
         Err(e) => {
             error!("Error getting applications from the database: {:?}", e);
@@ -99,10 +164,12 @@ pub async fn get_applications(query: Query<ApplicationQuery>) -> impl Responder
     context_path = "/v1",
     tag = "applications",
     params(
-        ("id" = i64, Path, description = "Unique ID of the application", example = 1)
+        ("id" = i64, Path, description = "Unique ID of the application", example = 1),
+        ("fields" = Option<String>, Query, description = "Comma separated list of fields to return")
     ),
     responses(
         (status = 200, description = "Application found", body = Application),
+        (status = 400, description = "Unknown field requested", body = ErrorResponse, example = json!(ErrorResponse::BadRequest(String::from("unknown field: foo")))),
         (status = 401, description = "Unauthorized to get application", body = ErrorResponse, example = json!(ErrorResponse::Unauthorized(String::from("Missing API Key")))),
         (status = 404, description = "Application not found", body = ErrorResponse, example = json!(ErrorResponse::NotFound(String::from("Application ID not found")))),
         (status = 500, description = "Internal server error", body = ErrorResponse)
@@ -112,13 +179,24 @@ pub async fn get_applications(query: Query<ApplicationQuery>) -> impl Responder
     )
 )]
 #[get("/applications/{id}")]
-pub async fn get_application_by_id(id: Path<i64>) -> impl Responder {
+pub async fn get_application_by_id(pool: Data<ReadPool>, id: Path<ApplicationId>, query: Query<FieldsQuery>) -> impl Responder {
     let id = id.into_inner();
-    let db_url = env::var("DATABASE_URL").unwrap_or_else(|_| "not set".to_string());
-    let mut conn = Connection::open(&db_url).unwrap();
+    let fields = match parse_fields(query.fields.as_deref(), Application::FIELDS) {
+        Ok(fields) => fields,
+        Err(e) => return HttpResponse::BadRequest().json(ErrorResponse::BadRequest(e)),
+    };
+    let mut conn = match pool.get() {
+        Ok(conn) => conn,
+        Err(e) => {
+            error!("Error connecting to the database: {:?}", e);
+            return HttpResponse::InternalServerError().json(ErrorResponse::InternalError(
+                "Error connecting to the database".to_string(),
+            ));
+        }
+    };
 
     match application::get_by_id(&mut conn, id) {
-        Ok(Some(application)) => HttpResponse::Ok().json(application),
+        Ok(Some(application)) => HttpResponse::Ok().json(select(&application, &fields)),
         Ok(None) => HttpResponse::NotFound().json(ErrorResponse::NotFound(format!("Application with ID {} not found", id))),
         Err(e) => {
             error!("Error retrieving application with ID {}: {:?}", id, e);
@@ -129,11 +207,141 @@ pub async fn get_application_by_id(id: Path<i64>) -> impl Responder {
     }
 }
 
+/// Get an application of a job.
+///
+/// This endpoint requires `api_key` authentication.
+///
+/// Return the `Application` only if it was submitted to the given `Job`. Returns a 404 if the `Application` does not
+/// exist or belongs to a different `Job`, so the two cases cannot be told apart. Only the employer who posted the `Job`
+/// may fetch it, anyone else gets a 403 before the `Application` is looked up, so they cannot probe which IDs exist. Until requests carry an authenticated user, the reader is identified by the
+/// `employer_id` query parameter.
+#[utoipa::path(
+    context_path = "/v1",
+    tag = "applications",
+    params(
+        ("id" = i64, Path, description = "Unique ID of the job", example = 1),
+        ("application_id" = i64, Path, description = "Unique ID of the application", example = 1),
+        ("employer_id" = i64, Query, description = "Unique ID of the employer reading the application", example = 1),
+        ("fields" = Option<String>, Query, description = "Comma separated list of fields to return")
+    ),
+    responses(
+        (status = 200, description = "Application found", body = Application),
+        (status = 400, description = "Unknown field requested", body = ErrorResponse, example = json!(ErrorResponse::BadRequest(String::from("unknown field: foo")))),
+        (status = 401, description = "Unauthorized to get application", body = ErrorResponse, example = json!(ErrorResponse::Unauthorized(String::from("Missing API Key")))),
+        (status = 403, description = "Not the employer of the job", body = ErrorResponse, example = json!(ErrorResponse::Forbidden(String::from("Only the employer of the job can view application 1")))),
+        (status = 404, description = "Application not found for the job", body = ErrorResponse, example = json!(ErrorResponse::NotFound(String::from("Application with ID 1 not found for job 1")))),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    ),
+    security(
+        ("api_key" = [])
+    )
+)]
+#[get("/jobs/{id}/applications/{application_id}")]
+pub async fn get_job_application(pool: Data<ReadPool>, path: Path<(JobId, ApplicationId)>, query: Query<JobApplicationQuery>) -> impl Responder {
+    let (job_id, application_id) = path.into_inner();
+    let fields = match parse_fields(query.fields.as_deref(), Application::FIELDS) {
+        Ok(fields) => fields,
+        Err(e) => return HttpResponse::BadRequest().json(ErrorResponse::BadRequest(e)),
+    };
+    let mut conn = match pool.get() {
+        Ok(conn) => conn,
+        Err(e) => {
+            error!("Error connecting to the database: {:?}", e);
+            return HttpResponse::InternalServerError().json(ErrorResponse::InternalError(
+                "Error connecting to the database".to_string(),
+            ));
+        }
+    };
+
+    match job::get_by_id(&mut conn, job_id) {
+        Ok(Some(job)) if job.employer_id == query.employer_id => {}
+        Ok(Some(_)) => {
+            return HttpResponse::Forbidden().json(ErrorResponse::Forbidden(format!(
+                "Only the employer of the job can view application {}",
+                application_id
+            )))
+        }
+        Ok(None) => return HttpResponse::NotFound().json(ErrorResponse::NotFound(format!("Job with ID {} not found", job_id))),
+        Err(e) => {
+            error!("Error retrieving job with ID {}: {:?}", job_id, e);
+            return HttpResponse::InternalServerError().json(ErrorResponse::InternalError(
+                "Error retrieving job".to_string(),
+            ));
+        }
+    }
+
+    let application = match application::get_by_id(&mut conn, application_id) {
+        Ok(Some(application)) if application.job_id == job_id => application,
+        Ok(_) => {
+            return HttpResponse::NotFound().json(ErrorResponse::NotFound(format!(
+                "Application with ID {} not found for job {}",
+                application_id, job_id
+            )))
+        }
+        Err(e) => {
+            error!("Error retrieving application with ID {}: {:?}", application_id, e);
+            return HttpResponse::InternalServerError().json(ErrorResponse::InternalError(
+                "Error retrieving application".to_string(),
+            ));
+        }
+    };
+
+    HttpResponse::Ok().json(select(&application, &fields))
+}
+
+/// Get an application by its reference.
+///
+/// This endpoint requires `api_key` authentication.
+///
+/// Return the `Application` with the given human readable reference, e.g. `APP-2024-000123`, or a 404 if there is
+/// none.
+#[utoipa::path(
+    context_path = "/v1",
+    tag = "applications",
+    params(
+        ("reference" = String, Path, description = "Reference of the application", example = "APP-2024-000123")
+    ),
+    responses(
+        (status = 200, description = "Application found", body = Application),
+        (status = 401, description = "Unauthorized to get application", body = ErrorResponse, example = json!(ErrorResponse::Unauthorized(String::from("Missing API Key")))),
+        (status = 404, description = "Application not found", body = ErrorResponse, example = json!(ErrorResponse::NotFound(String::from("Application with reference APP-2024-000123 not found")))),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    ),
+    security(
+        ("api_key" = [])
+    )
+)]
+#[get("/applications/reference/{reference}")]
+pub async fn get_application_by_reference(pool: Data<ReadPool>, reference: Path<String>) -> impl Responder {
+    let reference = reference.into_inner();
+    let mut conn = match pool.get() {
+        Ok(conn) => conn,
+        Err(e) => {
+            error!("Error connecting to the database: {:?}", e);
+            return HttpResponse::InternalServerError().json(ErrorResponse::InternalError(
+                "Error connecting to the database".to_string(),
+            ));
+        }
+    };
+
+    match application::get_by_reference(&mut conn, &reference) {
+        Ok(Some(application)) => HttpResponse::Ok().json(application),
+        Ok(None) => HttpResponse::NotFound().json(ErrorResponse::NotFound(format!("Application with reference {} not found", reference))),
+        Err(e) => {
+            error!("Error retrieving application with reference {}: {:?}", reference, e);
+            HttpResponse::InternalServerError().json(ErrorResponse::InternalError(
+                "Error retrieving application".to_string(),
+            ))
+        }
+    }
+}
+
 /// Create a new application.
 ///
 /// This endpoint requires `api_key` authentication.
 ///
-/// Create a new `Application` in the database.
+/// Create a new `Application` in the database and return it with the id the database assigned. Applications to a
+/// `Job` that is closed or whose deadline has passed are rejected.
 #[utoipa::path(
     context_path = "/v1",
     tag = "applications",
@@ -142,6 +350,8 @@ pub async fn get_application_by_id(id: Path<i64>) -> impl Responder {
         (status = 201, description = "Application created successfully", body = Application),
         (status = 401, description = "Unauthorized to create application", body = ErrorResponse, example = json!(ErrorResponse::Unauthorized(String::from("Missing API Key")))),
         (status = 400, description = "Invalid application data", body = ErrorResponse, example = json!(ErrorResponse::BadRequest(String::from("Invalid application data")))),
+        (status = 404, description = "Job not found", body = ErrorResponse, example = json!(ErrorResponse::NotFound(String::from("Job with ID 1 not found")))),
+        (status = 422, description = "Job is closed or its deadline has passed", body = ErrorResponse, example = json!(ErrorResponse::UnprocessableEntity(String::from("Job with ID 1 no longer accepts applications")))),
         (status = 500, description = "Internal server error", body = ErrorResponse)
     ),
     security(
@@ -149,10 +359,8 @@ pub async fn get_application_by_id(id: Path<i64>) -> impl Responder {
     )
 )]
 #[post("/applications")]
-pub async fn create_application(application: Json<Application>) -> impl Responder {
-    let db_url = env::var("DATABASE_URL").unwrap_or_else(|_| "not set".to_string());
-    info!("DATABASE_URL = {:?}", db_url);
-    let mut conn = match Connection::open(&db_url) {
+pub async fn create_application(pool: Data<Pool>, application: Json<Application>) -> impl Responder {
+    let mut conn = match pool.get() {
         Ok(conn) => conn,
         Err(e) => {
             error!("Error connecting to the database: {:?}", e);
@@ -162,10 +370,34 @@ pub async fn create_application(application: Json<Application>) -> impl Responde
         }
     };
 
-    let application = application.into_inner();
+    let mut application = application.into_inner();
+
+    match job::get_by_id(&mut conn, application.job_id) {
+        Ok(Some(job)) if !job.accepts_applications_at(Utc::now()) => {
+            return HttpResponse::UnprocessableEntity().json(ErrorResponse::UnprocessableEntity(format!(
+                "Job with ID {} no longer accepts applications",
+                application.job_id
+            )));
+        }
+        Ok(Some(_)) => {}
+        Ok(None) => {
+            return HttpResponse::NotFound().json(ErrorResponse::NotFound(format!(
+                "Job with ID {} not found",
+                application.job_id
+            )));
+        }
+        Err(e) => {
+            error!("Error retrieving job with ID {}: {:?}", application.job_id, e);
+            return HttpResponse::InternalServerError().json(ErrorResponse::InternalError(
+                "Error retrieving job".to_string(),
+            ));
+        }
+    }
 
     match application::create(&mut conn, application.clone()) {
-        Ok(_) => {
+        Ok((id, reference)) => {
+            application.id = id;
+            application.reference = reference;
             info!("Application created successfully: {:?}", application);
             HttpResponse::Created().json(application)
         }
@@ -182,7 +414,8 @@ pub async fn create_application(application: Json<Application>) -> impl Responde
 ///
 /// This endpoint requires `api_key` authentication.
 ///
-/// Update an existing `Application` in the database.
+/// Update an existing `Application` in the database. A status change that is not allowed, such as moving an accepted
+/// application back to pending, returns 422.
 #[utoipa::path(
     context_path = "/v1",
     tag = "applications",
@@ -195,6 +428,7 @@ pub async fn create_application(application: Json<Application>) -> impl Responde
         (status = 401, description = "Unauthorized to update application", body = ErrorResponse, example = json!(ErrorResponse::Unauthorized(String::from("Missing API Key")))),
         (status = 404, description = "Application not found", body = ErrorResponse, example = json!(ErrorResponse::NotFound(String::from("Application ID not found")))),
         (status = 400, description = "Invalid application update data", body = ErrorResponse, example = json!(ErrorResponse::BadRequest(String::from("Invalid application update data")))),
+        (status = 422, description = "Status change not allowed", body = ErrorResponse, example = json!(ErrorResponse::UnprocessableEntity(String::from("Cannot change application status from accepted to pending")))),
         (status = 500, description = "Internal server error", body = ErrorResponse)
     ),
     security(
@@ -203,12 +437,12 @@ pub async fn create_application(application: Json<Application>) -> impl Responde
 )]
 #[put("/applications/{id}")]
 pub async fn update_application(
-    id: Path<i64>,
+    pool: Data<Pool>,
+    id: Path<ApplicationId>,
     application_update_request: Json<ApplicationUpdateRequest>,
 ) -> impl Responder {
     let id = id.into_inner();
-    let db_url = env::var("DATABASE_URL").unwrap_or_else(|_| "not set".to_string());
-    let mut conn = match Connection::open(&db_url) {
+    let mut conn = match pool.get() {
         Ok(conn) => conn,
         Err(e) => {
             error!("Error connecting to the database: {:?}", e);
@@ -230,15 +464,26 @@ pub async fn update_application(
         }
     };
 
-    /// Create updated_application based on ApplicationUpdateRequest
+    if let Some(status) = &application_update_request.status {
+        if !existing_application.status.can_transition_to(status) {
+            return HttpResponse::UnprocessableEntity().json(ErrorResponse::UnprocessableEntity(format!(
+                "Cannot change application status from {} to {}",
+                existing_application.status, status
+            )));
+        }
+    }
+
+    // Create updated_application based on ApplicationUpdateRequest
     let updated_application = Application {
         id: existing_application.id,
         job_seeker_id: existing_application.job_seeker_id,
         job_id: existing_application.job_id,
         cover_letter: application_update_request.cover_letter.clone(),
         resume: application_update_request.resume.clone(),
-        status: application_update_request.status.clone().unwrap_or_else(|| existing_application.status),
+        status: application_update_request.status.clone().unwrap_or(existing_application.status),
         applied_at: existing_application.applied_at,
+        reference: existing_application.reference,
+        rating: existing_application.rating,
     };
 
     match application::update(&mut conn, id, updated_application.clone()) {
@@ -272,10 +517,17 @@ pub async fn update_application(
     )
 )]
 #[delete("/applications/{id}")]
-pub async fn delete_application(id: Path<i64>) -> impl Responder {
+pub async fn delete_application(pool: Data<Pool>, id: Path<ApplicationId>) -> impl Responder {
     let id = id.into_inner();
-    let db_url = env::var("DATABASE_URL").unwrap_or_else(|_| "not set".to_string());
-    let mut conn = Connection::open(&db_url).unwrap();
+    let mut conn = match pool.get() {
+        Ok(conn) => conn,
+        Err(e) => {
+            error!("Error connecting to the database: {:?}", e);
+            return HttpResponse::InternalServerError().json(ErrorResponse::InternalError(
+                "Error connecting to the database".to_string(),
+            ));
+        }
+    };
 
     match application::delete(&mut conn, id) {
         Ok(_) => HttpResponse::NoContent().finish(),
@@ -284,4 +536,1144 @@ pub async fn delete_application(id: Path<i64>) -> impl Responder {
             HttpResponse::InternalServerError().finish()
         }
     }
-}
\ No newline at end of file
+}
+
+/// Reopen a rejected application.
+///
+/// This endpoint requires `api_key` authentication.
+///
+/// Move a `rejected` `Application` back to `reviewed` so the employer of the job can reconsider it. Applications in
+/// any other status are left unchanged and return a 422.
+#[utoipa::path(
+    context_path = "/v1",
+    tag = "applications",
+    params(
+        ("id" = i64, Path, description = "Unique ID of the application", example = 1),
+        ("employer_id" = i64, Query, description = "The employer reopening the application", example = 1)
+    ),
+    responses(
+        (status = 200, description = "Application reopened", body = Application),
+        (status = 401, description = "Unauthorized to reopen application", body = ErrorResponse, example = json!(ErrorResponse::Unauthorized(String::from("Missing API Key")))),
+        (status = 403, description = "Not the employer of the job", body = ErrorResponse, example = json!(ErrorResponse::Forbidden(String::from("Only the employer of the job can reopen application 1")))),
+        (status = 404, description = "Application not found", body = ErrorResponse, example = json!(ErrorResponse::NotFound(String::from("Application ID not found")))),
+        (status = 422, description = "Application is not rejected", body = ErrorResponse, example = json!(ErrorResponse::UnprocessableEntity(String::from("Only rejected applications can be reopened")))),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    ),
+    security(
+        ("api_key" = [])
+    )
+)]
+#[post("/applications/{id}/reopen")]
+pub async fn reopen_application(pool: Data<Pool>, id: Path<ApplicationId>, query: Query<EmployerAccessQuery>) -> impl Responder {
+    let id = id.into_inner();
+    let mut conn = match pool.get() {
+        Ok(conn) => conn,
+        Err(e) => {
+            error!("Error connecting to the database: {:?}", e);
+            return HttpResponse::InternalServerError().json(ErrorResponse::InternalError(
+                "Error connecting to the database".to_string(),
+            ));
+        }
+    };
+
+    if let Some(response) = employer_forbidden(&mut conn, id, query.employer_id, "reopen") {
+        return response;
+    }
+
+    // The status is checked by the update itself, so two concurrent reopens cannot both succeed.
+    match application::reopen(&mut conn, id) {
+        Ok(true) => info!("Reopened application with ID {}", id),
+        Ok(false) => {
+            return HttpResponse::UnprocessableEntity().json(ErrorResponse::UnprocessableEntity(
+                "Only rejected applications can be reopened".to_string(),
+            ))
+        }
+        Err(e) => {
+            error!("Error reopening application with ID {}: {:?}", id, e);
+            return HttpResponse::InternalServerError().json(ErrorResponse::InternalError(
+                "Error reopening application".to_string(),
+            ));
+        }
+    }
+
+    match application::get_by_id(&mut conn, id) {
+        Ok(Some(application)) => HttpResponse::Ok().json(application),
+        Ok(None) => HttpResponse::NotFound().json(ErrorResponse::NotFound(format!("Application with ID {} not found", id))),
+        Err(e) => {
+            error!("Error retrieving application with ID {}: {:?}", id, e);
+            HttpResponse::InternalServerError().json(ErrorResponse::InternalError(
+                "Error retrieving application".to_string(),
+            ))
+        }
+    }
+}
+
+/// Get the applications to a job.
+///
+/// This endpoint requires `api_key` authentication.
+///
+/// List the applications to the `Job` with pagination support, ordered by id unless `sort` is given. Sorting by
+/// `rating` puts unrated applications last in either order, and `min_rating` leaves out unrated applications and those
+/// rated below it. Returns a 404 if the `Job` does not exist.
+#[utoipa::path(
+    context_path = "/v1",
+    tag = "applications",
+    params(
+        ("id" = i64, Path, description = "Unique ID of the job", example = 1),
+        ("limit" = Option<i64>, Query, description = "Maximum number of applications to return, at most 100", example = 10),
+        ("offset" = Option<i64>, Query, description = "Offset for pagination", example = 0),
+        ("sort" = Option<String>, Query, description = "Field to sort by, either `applied_at` or `rating`", example = "rating"),
+        ("order" = Option<String>, Query, description = "Sort order, either `asc` (the default) or `desc`", example = "desc"),
+        ("min_rating" = Option<u8>, Query, description = "Only return applications rated at least this, from 1 to 5", example = 3),
+        ("snapshot_count" = Option<i64>, Query, description = "`snapshot_count` returned by the first page, used to detect drift", example = 25),
+    ),
+    responses(
+        (status = 200, description = "Applications to the job with pagination metadata", body = PaginationApplication, headers(
+            ("X-Pagination-Drift" = i64, description = "Difference between the current total and `snapshot_count`, present only when they differ")
+        )),
+        (status = 400, description = "Invalid pagination, sort or rating filter", body = ErrorResponse, example = json!(ErrorResponse::BadRequest(String::from("cannot sort by: status")))),
+        (status = 401, description = "Unauthorized to get applications", body = ErrorResponse, example = json!(ErrorResponse::Unauthorized(String::from("Missing API Key")))),
+        (status = 404, description = "Job not found", body = ErrorResponse, example = json!(ErrorResponse::NotFound(String::from("Job with ID 1 not found")))),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    ),
+    security(
+        ("api_key" = [])
+    )
+)]
+#[get("/jobs/{id}/applications")]
+pub async fn get_job_applications(pool: Data<ReadPool>, id: Path<JobId>, query: Query<JobApplicationsQuery>) -> impl Responder {
+    let id = id.into_inner();
+    let page = match query.list.page(JOB_APPLICATION_SORTS) {
+        Ok(page) => page,
+        Err(e) => return HttpResponse::BadRequest().json(ErrorResponse::BadRequest(e)),
+    };
+    if query.min_rating.is_some_and(|rating| !Application::RATINGS.contains(&rating)) {
+        return HttpResponse::BadRequest().json(ErrorResponse::BadRequest(
+            "min_rating must be between 1 and 5".to_string(),
+        ));
+    }
+
+    let mut conn = match pool.get() {
+        Ok(conn) => conn,
+        Err(e) => {
+            error!("Error connecting to the database: {:?}", e);
+            return HttpResponse::InternalServerError().json(ErrorResponse::InternalError(
+                "Error connecting to the database".to_string(),
+            ));
+        }
+    };
+
+    match job::get_by_id(&mut conn, id) {
+        Ok(Some(_)) => {}
+        Ok(None) => return HttpResponse::NotFound().json(ErrorResponse::NotFound(format!("Job with ID {} not found", id))),
+        Err(e) => {
+            error!("Error retrieving job with ID {}: {:?}", id, e);
+            return HttpResponse::InternalServerError().json(ErrorResponse::InternalError(
+                "Error retrieving job".to_string(),
+            ));
+        }
+    }
+
+    match application::get_for_job(&mut conn, id, query.min_rating, page.sort.as_ref(), page.limit, page.offset) {
+        Ok((applications, total_count)) => {
+            let snapshot_count = query.snapshot_count.unwrap_or(total_count);
+            let mut response = HttpResponse::Ok();
+            if snapshot_count != total_count {
+                response.insert_header((PAGINATION_DRIFT_HEADER, (total_count - snapshot_count).to_string()));
+            }
+            response.json(PaginationApplication {
+                page: page.number(),
+                count: total_count,
+                snapshot_count,
+                items: applications,
+            })
+        }
+        Err(e) => {
+            error!("Error getting applications to job with ID {}: {:?}", id, e);
+            HttpResponse::InternalServerError().json(ErrorResponse::InternalError(
+                "Error getting applications".to_string(),
+            ))
+        }
+    }
+}
+
+/// Rate an application.
+///
+/// This endpoint requires `api_key` authentication.
+///
+/// Set the employer's rating of the `Application` from 1 to 5, or clear it with `null`. Only the employer who posted
+/// the job the `Application` is for may rate it, anyone else gets a 403. Until requests carry an authenticated user,
+/// the employer is identified by `employer_id`.
+#[utoipa::path(
+    context_path = "/v1",
+    tag = "applications",
+    params(
+        ("id" = i64, Path, description = "Unique ID of the application", example = 1)
+    ),
+    request_body = RatingRequest,
+    responses(
+        (status = 200, description = "Application rated", body = Application),
+        (status = 401, description = "Unauthorized to rate application", body = ErrorResponse, example = json!(ErrorResponse::Unauthorized(String::from("Missing API Key")))),
+        (status = 403, description = "Not the employer of the application's job", body = ErrorResponse, example = json!(ErrorResponse::Forbidden(String::from("Only the employer of the job can rate application 1")))),
+        (status = 404, description = "Application not found", body = ErrorResponse, example = json!(ErrorResponse::NotFound(String::from("Application with ID 1 not found")))),
+        (status = 422, description = "Rating out of range", body = ErrorResponse, example = json!(ErrorResponse::UnprocessableEntity(String::from("rating must be between 1 and 5")))),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    ),
+    security(
+        ("api_key" = [])
+    )
+)]
+#[post("/applications/{id}/rating")]
+pub async fn rate_application(pool: Data<Pool>, id: Path<ApplicationId>, request: Json<RatingRequest>) -> impl Responder {
+    let id = id.into_inner();
+    if request.rating.is_some_and(|rating| !Application::RATINGS.contains(&rating)) {
+        return HttpResponse::UnprocessableEntity().json(ErrorResponse::UnprocessableEntity(
+            "rating must be between 1 and 5".to_string(),
+        ));
+    }
+
+    let mut conn = match pool.get() {
+        Ok(conn) => conn,
+        Err(e) => {
+            error!("Error connecting to the database: {:?}", e);
+            return HttpResponse::InternalServerError().json(ErrorResponse::InternalError(
+                "Error connecting to the database".to_string(),
+            ));
+        }
+    };
+
+    if let Some(response) = employer_forbidden(&mut conn, id, request.employer_id, "rate") {
+        return response;
+    }
+
+    let rated = application::set_rating(&mut conn, id, request.rating).and_then(|_| application::get_by_id(&mut conn, id));
+    match rated {
+        Ok(Some(application)) => {
+            info!("Rated application with ID {}: {:?}", id, request.rating);
+            HttpResponse::Ok().json(application)
+        }
+        Ok(None) => HttpResponse::NotFound().json(ErrorResponse::NotFound(format!("Application with ID {} not found", id))),
+        Err(e) => {
+            error!("Error rating application with ID {}: {:?}", id, e);
+            HttpResponse::InternalServerError().json(ErrorResponse::InternalError(
+                "Error rating application".to_string(),
+            ))
+        }
+    }
+}
+
+/// Reject the pending applications to a job.
+///
+/// This endpoint requires `api_key` authentication.
+///
+/// Move every `pending` `Application` to the `Job` to `rejected` in one transaction and email each affected job seeker,
+/// typically after the employer closed the `Job`. Applications in any other status are left unchanged. Only the
+/// employer who posted the `Job` may do this, anyone else gets a 403. Until requests carry an authenticated user, the
+/// employer is identified by `employer_id`.
+#[utoipa::path(
+    context_path = "/v1",
+    tag = "applications",
+    params(
+        ("id" = i64, Path, description = "Unique ID of the job", example = 1)
+    ),
+    request_body = RejectPendingRequest,
+    responses(
+        (status = 200, description = "Pending applications rejected", body = RejectPendingResult),
+        (status = 401, description = "Unauthorized to reject applications", body = ErrorResponse, example = json!(ErrorResponse::Unauthorized(String::from("Missing API Key")))),
+        (status = 403, description = "Not the employer of the job", body = ErrorResponse, example = json!(ErrorResponse::Forbidden(String::from("Only the employer of job 1 can reject its applications")))),
+        (status = 404, description = "Job not found", body = ErrorResponse, example = json!(ErrorResponse::NotFound(String::from("Job with ID 1 not found")))),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    ),
+    security(
+        ("api_key" = [])
+    )
+)]
+#[post("/jobs/{id}/reject-pending")]
+pub async fn reject_pending_applications(
+    pool: Data<Pool>,
+    id: Path<JobId>,
+    request: Json<RejectPendingRequest>,
+    queue: Data<WorkQueue>,
+) -> impl Responder {
+    let id = id.into_inner();
+    let mut conn = match pool.get() {
+        Ok(conn) => conn,
+        Err(e) => {
+            error!("Error connecting to the database: {:?}", e);
+            return HttpResponse::InternalServerError().json(ErrorResponse::InternalError(
+                "Error connecting to the database".to_string(),
+            ));
+        }
+    };
+
+    let existing_job = match job::get_by_id(&mut conn, id) {
+        Ok(Some(job)) => job,
+        Ok(None) => return HttpResponse::NotFound().json(ErrorResponse::NotFound(format!("Job with ID {} not found", id))),
+        Err(e) => {
+            error!("Error retrieving job with ID {}: {:?}", id, e);
+            return HttpResponse::InternalServerError().json(ErrorResponse::InternalError(
+                "Error retrieving job".to_string(),
+            ));
+        }
+    };
+    if existing_job.employer_id != request.employer_id {
+        return HttpResponse::Forbidden().json(ErrorResponse::Forbidden(format!(
+            "Only the employer of job {} can reject its applications",
+            id
+        )));
+    }
+
+    match application::reject_pending(&mut conn, id) {
+        Ok(rejected) => {
+            info!("Rejected {} pending application(s) to job with ID {}", rejected.len(), id);
+            for (application_id, email) in &rejected {
+                queue
+                    .enqueue(SideEffect::Email {
+                        to: email.clone(),
+                        subject: format!("Update on your application to {}", existing_job.title),
+                        body: format!(
+                            "Your application {} to {} was not selected to move forward.",
+                            application_id, existing_job.title
+                        ),
+                    })
+                    .await;
+            }
+            HttpResponse::Ok().json(RejectPendingResult { rejected: rejected.len() })
+        }
+        Err(e) => {
+            error!("Error rejecting pending applications to job with ID {}: {:?}", id, e);
+            HttpResponse::InternalServerError().json(ErrorResponse::InternalError(
+                "Error rejecting pending applications".to_string(),
+            ))
+        }
+    }
+}
+
+/// Get the number of applications per status for a job.
+///
+/// This endpoint requires `api_key` authentication.
+///
+/// Return one entry per `ApplicationStatus` with the number of applications to the `Job` in that status, including
+/// statuses with no applications. Returns a 404 if the `Job` does not exist.
+#[utoipa::path(
+    context_path = "/v1",
+    tag = "applications",
+    params(
+        ("id" = i64, Path, description = "Unique ID of the job", example = 1)
+    ),
+    responses(
+        (status = 200, description = "Application counts per status", body = [ApplicationStatusCount]),
+        (status = 401, description = "Unauthorized to get application summary", body = ErrorResponse, example = json!(ErrorResponse::Unauthorized(String::from("Missing API Key")))),
+        (status = 404, description = "Job not found", body = ErrorResponse, example = json!(ErrorResponse::NotFound(String::from("Job with ID 1 not found")))),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    ),
+    security(
+        ("api_key" = [])
+    )
+)]
+#[get("/jobs/{id}/applications/summary")]
+pub async fn get_job_application_summary(pool: Data<ReadPool>, id: Path<JobId>) -> impl Responder {
+    let id = id.into_inner();
+    let mut conn = match pool.get() {
+        Ok(conn) => conn,
+        Err(e) => {
+            error!("Error connecting to the database: {:?}", e);
+            return HttpResponse::InternalServerError().json(ErrorResponse::InternalError(
+                "Error connecting to the database".to_string(),
+            ));
+        }
+    };
+
+    match job::get_by_id(&mut conn, id) {
+        Ok(Some(_)) => {}
+        Ok(None) => return HttpResponse::NotFound().json(ErrorResponse::NotFound(format!("Job with ID {} not found", id))),
+        Err(e) => {
+            error!("Error retrieving job with ID {}: {:?}", id, e);
+            return HttpResponse::InternalServerError().json(ErrorResponse::InternalError(
+                "Error retrieving job".to_string(),
+            ));
+        }
+    }
+
+    match application::count_by_status_for_job(&mut conn, id) {
+        Ok(counts) => HttpResponse::Ok().json(ApplicationStatusCount::zero_filled(&counts)),
+        Err(e) => {
+            error!("Error counting applications for job with ID {}: {:?}", id, e);
+            HttpResponse::InternalServerError().json(ErrorResponse::InternalError(
+                "Error counting applications".to_string(),
+            ))
+        }
+    }
+}
+
+/// Get the number of applications per status for a user.
+///
+/// This endpoint requires `api_key` authentication.
+///
+/// Return one entry per `ApplicationStatus` with the number of applications the `User` submitted in that status,
+/// including statuses with no applications. Returns a 404 if the `User` does not exist.
+#[utoipa::path(
+    context_path = "/v1",
+    tag = "applications",
+    params(
+        ("id" = i64, Path, description = "Unique ID of the user", example = 2)
+    ),
+    responses(
+        (status = 200, description = "Application counts per status", body = [ApplicationStatusCount]),
+        (status = 401, description = "Unauthorized to get application summary", body = ErrorResponse, example = json!(ErrorResponse::Unauthorized(String::from("Missing API Key")))),
+        (status = 404, description = "User not found", body = ErrorResponse, example = json!(ErrorResponse::NotFound(String::from("User with ID 2 not found")))),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    ),
+    security(
+        ("api_key" = [])
+    )
+)]
+#[get("/users/{id}/application-summary")]
+pub async fn get_user_application_summary(pool: Data<ReadPool>, id: Path<UserId>) -> impl Responder {
+    let id = id.into_inner();
+    let mut conn = match pool.get() {
+        Ok(conn) => conn,
+        Err(e) => {
+            error!("Error connecting to the database: {:?}", e);
+            return HttpResponse::InternalServerError().json(ErrorResponse::InternalError(
+                "Error connecting to the database".to_string(),
+            ));
+        }
+    };
+
+    match user::get_by_id(&mut conn, id) {
+        Ok(Some(_)) => {}
+        Ok(None) => return HttpResponse::NotFound().json(ErrorResponse::NotFound(format!("User with ID {} not found", id))),
+        Err(e) => {
+            error!("Error retrieving user with ID {}: {:?}", id, e);
+            return HttpResponse::InternalServerError().json(ErrorResponse::InternalError(
+                "Error retrieving user".to_string(),
+            ));
+        }
+    }
+
+    match application::count_by_status_for_seeker(&mut conn, id) {
+        Ok(counts) => HttpResponse::Ok().json(ApplicationStatusCount::zero_filled(&counts)),
+        Err(e) => {
+            error!("Error counting applications for user with ID {}: {:?}", id, e);
+            HttpResponse::InternalServerError().json(ErrorResponse::InternalError(
+                "Error counting applications".to_string(),
+            ))
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct ApplicantQuery {
+    #[serde(flatten)]
+    pub list: ListParams,
+    pub snapshot_count: Option<i64>,
+}
+
+/// Get the distinct applicants of an employer.
+///
+/// This endpoint requires `api_key` authentication.
+///
+/// Return each job seeker who applied to any of the employer's jobs once, with their number of applications and the
+/// ids of the jobs they applied to, paginated by job seeker. Returns a 404 if no employer with the given id exists.
+#[utoipa::path(
+    context_path = "/v1",
+    tag = "applications",
+    params(
+        ("id" = i64, Path, description = "Unique ID of the employer", example = 1),
+        ("limit" = Option<i64>, Query, description = "Maximum number of applicants to return, at most 100", example = 10),
+        ("offset" = Option<i64>, Query, description = "Offset for pagination", example = 0),
+        ("snapshot_count" = Option<i64>, Query, description = "`snapshot_count` returned by the first page, used to detect drift", example = 25),
+    ),
+    responses(
+        (status = 200, description = "Distinct applicants with pagination metadata", body = PaginationApplicant, headers(
+            ("X-Pagination-Drift" = i64, description = "Difference between the current total and `snapshot_count`, present only when they differ")
+        )),
+        (status = 400, description = "Invalid pagination", body = ErrorResponse, example = json!(ErrorResponse::BadRequest(String::from("limit must be at least 1")))),
+        (status = 401, description = "Unauthorized to get applicants", body = ErrorResponse, example = json!(ErrorResponse::Unauthorized(String::from("Missing API Key")))),
+        (status = 404, description = "Employer not found", body = ErrorResponse, example = json!(ErrorResponse::NotFound(String::from("Employer with ID 1 not found")))),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    ),
+    security(
+        ("api_key" = [])
+    )
+)]
+#[get("/employers/{id}/applicants")]
+pub async fn get_employer_applicants(pool: Data<ReadPool>, id: Path<UserId>, query: Query<ApplicantQuery>) -> impl Responder {
+    let id = id.into_inner();
+    let page = match query.list.page(&[]) {
+        Ok(page) => page,
+        Err(e) => return HttpResponse::BadRequest().json(ErrorResponse::BadRequest(e)),
+    };
+    let mut conn = match pool.get() {
+        Ok(conn) => conn,
+        Err(e) => {
+            error!("Error connecting to the database: {:?}", e);
+            return HttpResponse::InternalServerError().json(ErrorResponse::InternalError(
+                "Error connecting to the database".to_string(),
+            ));
+        }
+    };
+
+    match user::get_by_id(&mut conn, id) {
+        Ok(Some(employer)) if matches!(employer.role, UserRole::Employer) => {}
+        Ok(_) => {
+            return HttpResponse::NotFound().json(ErrorResponse::NotFound(format!("Employer with ID {} not found", id)));
+        }
+        Err(e) => {
+            error!("Error getting employer {}: {:?}", id, e);
+            return HttpResponse::InternalServerError().json(ErrorResponse::InternalError(
+                "Error getting employer".to_string(),
+            ));
+        }
+    }
+
+    match user::get_applicants_of_employer(&mut conn, id, page.limit, page.offset) {
+        Ok((applicants, total_count)) => {
+            let snapshot_count = query.snapshot_count.unwrap_or(total_count);
+            let pagination = PaginationApplicant {
+                page: page.number(),
+                count: total_count,
+                snapshot_count,
+                items: applicants,
+            };
+
+
+            let mut response = HttpResponse::Ok();
+            if snapshot_count != total_count {
+                response.insert_header((PAGINATION_DRIFT_HEADER, (total_count - snapshot_count).to_string()));
+            }
+            response.json(pagination)
+        }
+        Err(e) => {
+            error!("Error getting applicants of employer {}: {:?}", id, e);
+            HttpResponse::InternalServerError().json(ErrorResponse::InternalError(
+                "Error getting applicants".to_string(),
+            ))
+        }
+    }
+}
+
+/// Get the attachments of an application.
+///
+/// This endpoint requires `api_key` authentication.
+///
+/// Return every file attached to the `Application`, oldest first. Returns a 404 if the `Application` does not exist.
+#[utoipa::path(
+    context_path = "/v1",
+    tag = "applications",
+    params(
+        ("id" = i64, Path, description = "Unique ID of the application", example = 1)
+    ),
+    responses(
+        (status = 200, description = "Attachments of the application", body = [ApplicationAttachment]),
+        (status = 401, description = "Unauthorized to get attachments", body = ErrorResponse, example = json!(ErrorResponse::Unauthorized(String::from("Missing API Key")))),
+        (status = 404, description = "Application not found", body = ErrorResponse, example = json!(ErrorResponse::NotFound(String::from("Application with ID 1 not found")))),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    ),
+    security(
+        ("api_key" = [])
+    )
+)]
+#[get("/applications/{id}/attachments")]
+pub async fn get_application_attachments(pool: Data<ReadPool>, id: Path<ApplicationId>) -> impl Responder {
+    let id = id.into_inner();
+    let mut conn = match pool.get() {
+        Ok(conn) => conn,
+        Err(e) => {
+            error!("Error connecting to the database: {:?}", e);
+            return HttpResponse::InternalServerError().json(ErrorResponse::InternalError(
+                "Error connecting to the database".to_string(),
+            ));
+        }
+    };
+
+    if let Some(response) = application_not_found(&mut conn, id) {
+        return response;
+    }
+
+    match attachment::get_for_application(&mut conn, id) {
+        Ok(attachments) => HttpResponse::Ok().json(attachments),
+        Err(e) => {
+            error!("Error getting attachments for application with ID {}: {:?}", id, e);
+            HttpResponse::InternalServerError().json(ErrorResponse::InternalError(
+                "Error getting attachments".to_string(),
+            ))
+        }
+    }
+}
+
+/// Attach a file to an application.
+///
+/// This endpoint requires `api_key` authentication.
+///
+/// Add a file such as a resume or portfolio to the `Application`. The legacy `resume` field of the `Application`
+/// always mirrors its oldest `resume` attachment.
+#[utoipa::path(
+    context_path = "/v1",
+    tag = "applications",
+    params(
+        ("id" = i64, Path, description = "Unique ID of the application", example = 1)
+    ),
+    request_body = AttachmentRequest,
+    responses(
+        (status = 201, description = "Attachment added", body = ApplicationAttachment),
+        (status = 401, description = "Unauthorized to add attachment", body = ErrorResponse, example = json!(ErrorResponse::Unauthorized(String::from("Missing API Key")))),
+        (status = 404, description = "Application not found", body = ErrorResponse, example = json!(ErrorResponse::NotFound(String::from("Application with ID 1 not found")))),
+        (status = 422, description = "Invalid attachment", body = ErrorResponse, example = json!(ErrorResponse::UnprocessableEntity(String::from("url is required")))),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    ),
+    security(
+        ("api_key" = [])
+    )
+)]
+#[post("/applications/{id}/attachments")]
+pub async fn create_application_attachment(pool: Data<Pool>, id: Path<ApplicationId>, request: Json<AttachmentRequest>) -> impl Responder {
+    let id = id.into_inner();
+    let mut request = request.into_inner();
+    request.url = request.url.trim().to_string();
+    if request.url.is_empty() {
+        return HttpResponse::UnprocessableEntity().json(ErrorResponse::UnprocessableEntity(
+            "url is required".to_string(),
+        ));
+    }
+
+    let mut conn = match pool.get() {
+        Ok(conn) => conn,
+        Err(e) => {
+            error!("Error connecting to the database: {:?}", e);
+            return HttpResponse::InternalServerError().json(ErrorResponse::InternalError(
+                "Error connecting to the database".to_string(),
+            ));
+        }
+    };
+
+    if let Some(response) = application_not_found(&mut conn, id) {
+        return response;
+    }
+
+    match attachment::create(&mut conn, id, request) {
+        Ok(attachment) => {
+            info!("Added attachment {} to application with ID {}", attachment.id, id);
+            HttpResponse::Created().json(attachment)
+        }
+        Err(e) => {
+            error!("Error adding attachment to application with ID {}: {:?}", id, e);
+            HttpResponse::InternalServerError().json(ErrorResponse::InternalError(
+                "Error adding attachment".to_string(),
+            ))
+        }
+    }
+}
+
+/// Remove an attachment from an application.
+///
+/// This endpoint requires `api_key` authentication.
+///
+/// Delete the attachment and refresh the legacy `resume` field of the `Application`.
+#[utoipa::path(
+    context_path = "/v1",
+    tag = "applications",
+    params(
+        ("id" = i64, Path, description = "Unique ID of the application", example = 1),
+        ("attachment_id" = i64, Path, description = "Unique ID of the attachment", example = 1)
+    ),
+    responses(
+        (status = 204, description = "Attachment removed"),
+        (status = 401, description = "Unauthorized to remove attachment", body = ErrorResponse, example = json!(ErrorResponse::Unauthorized(String::from("Missing API Key")))),
+        (status = 404, description = "Attachment not found", body = ErrorResponse, example = json!(ErrorResponse::NotFound(String::from("Attachment with ID 1 not found")))),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    ),
+    security(
+        ("api_key" = [])
+    )
+)]
+#[delete("/applications/{id}/attachments/{attachment_id}")]
+pub async fn delete_application_attachment(pool: Data<Pool>, path: Path<(ApplicationId, i64)>) -> impl Responder {
+    let (id, attachment_id) = path.into_inner();
+    let mut conn = match pool.get() {
+        Ok(conn) => conn,
+        Err(e) => {
+            error!("Error connecting to the database: {:?}", e);
+            return HttpResponse::InternalServerError().json(ErrorResponse::InternalError(
+                "Error connecting to the database".to_string(),
+            ));
+        }
+    };
+
+    match attachment::delete(&mut conn, id, attachment_id) {
+        Ok(true) => HttpResponse::NoContent().finish(),
+        Ok(false) => HttpResponse::NotFound().json(ErrorResponse::NotFound(format!(
+            "Attachment with ID {} not found",
+            attachment_id
+        ))),
+        Err(e) => {
+            error!("Error removing attachment {} from application with ID {}: {:?}", attachment_id, id, e);
+            HttpResponse::InternalServerError().json(ErrorResponse::InternalError(
+                "Error removing attachment".to_string(),
+            ))
+        }
+    }
+}
+
+/// Get the private notes on an application.
+///
+/// This endpoint requires `api_key` authentication.
+///
+/// Return the notes employers wrote about the `Application`, oldest first. Only the employer who posted the job the
+/// `Application` is for may read them, anyone else, including the job seeker, gets a 403. Until requests carry an
+/// authenticated user, the reader is identified by the `employer_id` query parameter.
+#[utoipa::path(
+    context_path = "/v1",
+    tag = "applications",
+    params(
+        ("id" = i64, Path, description = "Unique ID of the application", example = 1),
+        ("employer_id" = i64, Query, description = "Unique ID of the employer reading the notes", example = 1)
+    ),
+    responses(
+        (status = 200, description = "Notes on the application", body = [ApplicationNote]),
+        (status = 401, description = "Unauthorized to get notes", body = ErrorResponse, example = json!(ErrorResponse::Unauthorized(String::from("Missing API Key")))),
+        (status = 403, description = "Not the employer of the application's job", body = ErrorResponse, example = json!(ErrorResponse::Forbidden(String::from("Only the employer of the job can access notes on application 1")))),
+        (status = 404, description = "Application not found", body = ErrorResponse, example = json!(ErrorResponse::NotFound(String::from("Application with ID 1 not found")))),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    ),
+    security(
+        ("api_key" = [])
+    )
+)]
+#[get("/applications/{id}/notes")]
+pub async fn get_application_notes(pool: Data<ReadPool>, id: Path<ApplicationId>, query: Query<EmployerAccessQuery>) -> impl Responder {
+    let id = id.into_inner();
+    let mut conn = match pool.get() {
+        Ok(conn) => conn,
+        Err(e) => {
+            error!("Error connecting to the database: {:?}", e);
+            return HttpResponse::InternalServerError().json(ErrorResponse::InternalError(
+                "Error connecting to the database".to_string(),
+            ));
+        }
+    };
+
+    if let Some(response) = employer_forbidden(&mut conn, id, query.employer_id, "access notes on") {
+        return response;
+    }
+
+    match note::get_for_application(&mut conn, id) {
+        Ok(notes) => HttpResponse::Ok().json(notes),
+        Err(e) => {
+            error!("Error getting notes for application with ID {}: {:?}", id, e);
+            HttpResponse::InternalServerError().json(ErrorResponse::InternalError(
+                "Error getting notes".to_string(),
+            ))
+        }
+    }
+}
+
+/// Add a private note to an application.
+///
+/// This endpoint requires `api_key` authentication.
+///
+/// Add a note about the `Application` that the job seeker never sees. Only the employer who posted the job the
+/// `Application` is for may add notes, anyone else gets a 403. Until requests carry an authenticated user, the author
+/// is identified by `author_id`.
+#[utoipa::path(
+    context_path = "/v1",
+    tag = "applications",
+    params(
+        ("id" = i64, Path, description = "Unique ID of the application", example = 1)
+    ),
+    request_body = NoteRequest,
+    responses(
+        (status = 201, description = "Note added", body = ApplicationNote),
+        (status = 401, description = "Unauthorized to add note", body = ErrorResponse, example = json!(ErrorResponse::Unauthorized(String::from("Missing API Key")))),
+        (status = 403, description = "Not the employer of the application's job", body = ErrorResponse, example = json!(ErrorResponse::Forbidden(String::from("Only the employer of the job can access notes on application 1")))),
+        (status = 404, description = "Application not found", body = ErrorResponse, example = json!(ErrorResponse::NotFound(String::from("Application with ID 1 not found")))),
+        (status = 422, description = "Invalid note", body = ErrorResponse, example = json!(ErrorResponse::UnprocessableEntity(String::from("body is required")))),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    ),
+    security(
+        ("api_key" = [])
+    )
+)]
+#[post("/applications/{id}/notes")]
+pub async fn create_application_note(pool: Data<Pool>, id: Path<ApplicationId>, request: Json<NoteRequest>) -> impl Responder {
+    let id = id.into_inner();
+    let request = request.into_inner();
+    let body = request.body.trim().to_string();
+    if body.is_empty() {
+        return HttpResponse::UnprocessableEntity().json(ErrorResponse::UnprocessableEntity(
+            "body is required".to_string(),
+        ));
+    }
+
+    let mut conn = match pool.get() {
+        Ok(conn) => conn,
+        Err(e) => {
+            error!("Error connecting to the database: {:?}", e);
+            return HttpResponse::InternalServerError().json(ErrorResponse::InternalError(
+                "Error connecting to the database".to_string(),
+            ));
+        }
+    };
+
+    if let Some(response) = employer_forbidden(&mut conn, id, request.author_id, "access notes on") {
+        return response;
+    }
+
+    match note::create(&mut conn, id, request.author_id, body) {
+        Ok(note) => {
+            info!("Added note {} to application with ID {}", note.id, id);
+            HttpResponse::Created().json(note)
+        }
+        Err(e) => {
+            error!("Error adding note to application with ID {}: {:?}", id, e);
+            HttpResponse::InternalServerError().json(ErrorResponse::InternalError(
+                "Error adding note".to_string(),
+            ))
+        }
+    }
+}
+
+/// Export an application as a PDF summary.
+///
+/// This endpoint requires `api_key` authentication.
+///
+/// Render the job seeker's name, the job title, the status, the date applied and the cover letter of the `Application`
+/// as a printable PDF, served as an attachment. Only the employer who posted the job the `Application` is for may
+/// export it, anyone else gets a 403. Until requests carry an authenticated user, the employer is identified by the
+/// `employer_id` query parameter.
+#[utoipa::path(
+    context_path = "/v1",
+    tag = "applications",
+    params(
+        ("id" = i64, Path, description = "Unique ID of the application", example = 1),
+        ("employer_id" = i64, Query, description = "Unique ID of the employer exporting the application", example = 1)
+    ),
+    responses(
+        (status = 200, description = "PDF summary of the application", content_type = "application/pdf", body = Vec<u8>),
+        (status = 401, description = "Unauthorized to export application", body = ErrorResponse, example = json!(ErrorResponse::Unauthorized(String::from("Missing API Key")))),
+        (status = 403, description = "Not the employer of the application's job", body = ErrorResponse, example = json!(ErrorResponse::Forbidden(String::from("Only the employer of the job can export application 1")))),
+        (status = 404, description = "Application not found", body = ErrorResponse, example = json!(ErrorResponse::NotFound(String::from("Application with ID 1 not found")))),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    ),
+    security(
+        ("api_key" = [])
+    )
+)]
+#[get("/applications/{id}/pdf")]
+pub async fn get_application_pdf(pool: Data<ReadPool>, id: Path<ApplicationId>, query: Query<EmployerAccessQuery>) -> impl Responder {
+    let id = id.into_inner();
+    let mut conn = match pool.get() {
+        Ok(conn) => conn,
+        Err(e) => {
+            error!("Error connecting to the database: {:?}", e);
+            return HttpResponse::InternalServerError().json(ErrorResponse::InternalError(
+                "Error connecting to the database".to_string(),
+            ));
+        }
+    };
+
+    if let Some(response) = employer_forbidden(&mut conn, id, query.employer_id, "export") {
+        return response;
+    }
+
+    let summary = application::get_by_id(&mut conn, id).and_then(|application| {
+        let application = application.ok_or("application deleted during export")?;
+        let seeker = user::get_by_id(&mut conn, application.job_seeker_id)?;
+        let job = job::get_by_id(&mut conn, application.job_id)?;
+        Ok((application, seeker, job))
+    });
+    let (application, seeker, job) = match summary {
+        Ok(summary) => summary,
+        Err(e) => {
+            error!("Error retrieving application with ID {} for export: {:?}", id, e);
+            return HttpResponse::InternalServerError().json(ErrorResponse::InternalError(
+                "Error retrieving application".to_string(),
+            ));
+        }
+    };
+
+    let fields = [
+        ("Reference", application.reference.clone()),
+        ("Applicant", seeker.map_or_else(|| format!("User {}", application.job_seeker_id), |seeker| seeker.name)),
+        ("Job", job.map_or_else(|| format!("Job {}", application.job_id), |job| job.title)),
+        ("Status", application.status.to_string()),
+        ("Applied", application.applied_at.format("%Y-%m-%d").to_string()),
+    ];
+    let cover_letter = application.cover_letter.as_deref().unwrap_or("No cover letter provided.");
+    match pdf::render(&format!("Application {}", application.reference), &fields, cover_letter) {
+        Ok(bytes) => HttpResponse::Ok()
+            .content_type("application/pdf")
+            .insert_header((
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{}.pdf\"", application.reference),
+            ))
+            .body(bytes),
+        Err(e) => {
+            error!("Error rendering PDF for application with ID {}: {:?}", id, e);
+            HttpResponse::InternalServerError().json(ErrorResponse::InternalError(
+                "Error rendering PDF".to_string(),
+            ))
+        }
+    }
+}
+
+/// Respond with 404 if the application does not exist, or 403 if `user_id` is not the employer of its job.
+///
+/// `action` completes the 403 message, e.g. `access notes on`.
+fn employer_forbidden(conn: &mut Connection, id: ApplicationId, user_id: UserId, action: &str) -> Option<HttpResponse> {
+    match application::get_employer_id(conn, id) {
+        Ok(Some(employer_id)) if employer_id == user_id => None,
+        Ok(Some(_)) => Some(HttpResponse::Forbidden().json(ErrorResponse::Forbidden(format!(
+            "Only the employer of the job can {} application {}",
+            action, id
+        )))),
+        Ok(None) => Some(HttpResponse::NotFound().json(ErrorResponse::NotFound(format!("Application with ID {} not found", id)))),
+        Err(e) => {
+            error!("Error retrieving employer of application with ID {}: {:?}", id, e);
+            Some(HttpResponse::InternalServerError().json(ErrorResponse::InternalError(
+                "Error retrieving application".to_string(),
+            )))
+        }
+    }
+}
+
+/// Respond with 404 if the application does not exist, or 500 if it could not be looked up.
+fn application_not_found(conn: &mut Connection, id: ApplicationId) -> Option<HttpResponse> {
+    match application::get_by_id(conn, id) {
+        Ok(Some(_)) => None,
+        Ok(None) => Some(HttpResponse::NotFound().json(ErrorResponse::NotFound(format!("Application with ID {} not found", id)))),
+        Err(e) => {
+            error!("Error retrieving application with ID {}: {:?}", id, e);
+            Some(HttpResponse::InternalServerError().json(ErrorResponse::InternalError(
+                "Error retrieving application".to_string(),
+            )))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{test, App};
+    use actix_web::web::JsonConfig;
+    use serde_json::json;
+    use crate::utils::{json_error_handler, PaginationApplication};
+    use actix_web::http::StatusCode;
+    use actix_web::web::Data;
+    use serde_json::Value;
+    use crate::models::ApplicationStore;
+    use crate::utils::testing::TestDb;
+    use super::configure;
+
+    #[actix_web::test]
+    async fn summary_is_not_shadowed_by_the_application_route() {
+        let db = TestDb::new();
+        let employer = db.user("employer@example.com", "employer", "!");
+        let other_employer = db.user("other@example.com", "employer", "!");
+        let seeker = db.user("seeker@example.com", "job_seeker", "!");
+        let job = db.job(employer);
+        let other_job = db.job(employer);
+        let application = db.application(job, seeker);
+        let app = test::init_service(
+            App::new()
+                .app_data(db.pool())
+                .app_data(db.read_pool())
+                .configure(configure(Data::new(ApplicationStore::default()))),
+        )
+        .await;
+
+        let summary: Value = test::call_and_read_body_json(
+            &app,
+            test::TestRequest::get().uri(&format!("/jobs/{}/applications/summary", job)).to_request(),
+        )
+        .await;
+        let pending = summary.as_array().unwrap().iter().find(|count| count["status"] == "Pending").unwrap();
+        assert_eq!(pending["count"], 1);
+
+        for (uri, status) in [
+            (format!("/jobs/{}/applications/{}?employer_id={}", job, application, employer), StatusCode::OK),
+            (format!("/jobs/{}/applications/{}?employer_id={}", job, application, other_employer), StatusCode::FORBIDDEN),
+            (format!("/jobs/{}/applications/{}?employer_id={}", other_job, application, employer), StatusCode::NOT_FOUND),
+            (format!("/jobs/{}/applications/{}?employer_id={}", job, application + 1000, employer), StatusCode::NOT_FOUND),
+            (format!("/jobs/{}/applications/{}?employer_id={}", job, application + 1000, other_employer), StatusCode::FORBIDDEN),
+        ] {
+            let response = test::call_service(&app, test::TestRequest::get().uri(&uri).to_request()).await;
+            assert_eq!(response.status(), status, "{}", uri);
+        }
+    }
+
+    #[actix_web::test]
+    async fn only_the_employer_can_reopen_a_rejected_application_once() {
+        let db = TestDb::new();
+        let employer = db.user("employer@example.com", "employer", "!");
+        let other_employer = db.user("other@example.com", "employer", "!");
+        let seeker = db.user("seeker@example.com", "job_seeker", "!");
+        let application = db.application(db.job(employer), seeker);
+        db.conn().execute("UPDATE applications SET status = 'rejected' WHERE id = ?1", [application]).unwrap();
+        let app = test::init_service(
+            App::new()
+                .app_data(db.pool())
+                .app_data(db.read_pool())
+                .configure(configure(Data::new(ApplicationStore::default()))),
+        )
+        .await;
+
+        for (employer_id, status) in [
+            (other_employer, StatusCode::FORBIDDEN),
+            (employer, StatusCode::OK),
+            (employer, StatusCode::UNPROCESSABLE_ENTITY),
+        ] {
+            let uri = format!("/applications/{}/reopen?employer_id={}", application, employer_id);
+            let response = test::call_service(&app, test::TestRequest::post().uri(&uri).to_request()).await;
+            assert_eq!(response.status(), status, "{}", uri);
+        }
+
+        let status: String = db.conn()
+            .query_row("SELECT status FROM applications WHERE id = ?1", [application], |row| row.get(0))
+            .unwrap();
+        assert_eq!(status, "reviewed");
+        let changes: i64 = db.conn()
+            .query_row("SELECT COUNT(*) FROM application_status_changes WHERE application_id = ?1", [application], |row| row.get(0))
+            .unwrap();
+        assert_eq!(changes, 2);
+    }
+
+    #[actix_web::test]
+    async fn malformed_updates_get_400_and_illegal_transitions_422() {
+        let db = TestDb::new();
+        let employer = db.user("employer@example.com", "employer", "!");
+        let seeker = db.user("seeker@example.com", "job_seeker", "!");
+        let application = db.application(db.job(employer), seeker);
+        let app = test::init_service(
+            App::new()
+                .app_data(db.pool())
+                .app_data(db.read_pool())
+                .app_data(JsonConfig::default().error_handler(json_error_handler))
+                .configure(configure(Data::new(ApplicationStore::default()))),
+        )
+        .await;
+        let uri = format!("/applications/{}", application);
+
+        for (body, status) in [
+            (json!({"status": "accepted"}), StatusCode::OK),
+            (json!({"status": "pending"}), StatusCode::UNPROCESSABLE_ENTITY),
+            (json!({"status": "archived"}), StatusCode::BAD_REQUEST),
+            (json!({"status": 3}), StatusCode::BAD_REQUEST),
+        ] {
+            let response = test::call_service(&app, test::TestRequest::put().uri(&uri).set_json(&body).to_request()).await;
+            assert_eq!(response.status(), status, "{}", body);
+            if status != StatusCode::OK {
+                let error: Value = test::read_body_json(response).await;
+                let variant = if status == StatusCode::BAD_REQUEST { "BadRequest" } else { "UnprocessableEntity" };
+                assert!(error[variant].is_string(), "{}", error);
+            }
+        }
+    }
+
+    #[actix_web::test]
+    async fn applications_are_listed_as_a_pagination_application() {
+        let db = TestDb::new();
+        let employer = db.user("employer@example.com", "employer", "!");
+        let seeker = db.user("seeker@example.com", "job_seeker", "!");
+        let first = db.application(db.job(employer), seeker);
+        db.application(db.job(employer), seeker);
+        let app = test::init_service(
+            App::new()
+                .app_data(db.pool())
+                .app_data(db.read_pool())
+                .configure(configure(Data::new(ApplicationStore::default()))),
+        )
+        .await;
+
+        let response = test::call_service(&app, test::TestRequest::get().uri("/applications?limit=1").to_request()).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let page: PaginationApplication = test::read_body_json(response).await;
+        assert_eq!((page.page, page.count, page.snapshot_count), (1, 2, 2));
+        assert_eq!(page.items.len(), 1);
+        assert_eq!(page.items[0].id.0, first);
+    }
+
+    #[actix_web::test]
+    async fn employers_rate_applications_and_sort_by_rating() {
+        let db = TestDb::new();
+        let employer = db.user("employer@example.com", "employer", "!");
+        let other_employer = db.user("other@example.com", "employer", "!");
+        let job = db.job(employer);
+        let seekers = ["a@example.com", "b@example.com", "c@example.com"].map(|email| db.user(email, "job_seeker", "!"));
+        let [low, high, unrated] = seekers.map(|seeker| db.application(job, seeker));
+        let app = test::init_service(
+            App::new()
+                .app_data(db.pool())
+                .app_data(db.read_pool())
+                .configure(configure(Data::new(ApplicationStore::default()))),
+        )
+        .await;
+        let rate = |id: i64, employer_id: i64, rating: Value| {
+            test::TestRequest::post()
+                .uri(&format!("/applications/{}/rating", id))
+                .set_json(json!({"employer_id": employer_id, "rating": rating}))
+                .to_request()
+        };
+
+        for (request, status) in [
+            (rate(low, employer, json!(0)), StatusCode::UNPROCESSABLE_ENTITY),
+            (rate(low, employer, json!(6)), StatusCode::UNPROCESSABLE_ENTITY),
+            (rate(low, other_employer, json!(2)), StatusCode::FORBIDDEN),
+            (rate(low, employer, json!(2)), StatusCode::OK),
+            (rate(high, employer, json!(5)), StatusCode::OK),
+        ] {
+            assert_eq!(test::call_service(&app, request).await.status(), status);
+        }
+
+        let ids = |page: Value| page["items"].as_array().unwrap().iter().map(|item| item["id"].clone()).collect::<Vec<_>>();
+        let get = |query: &str| test::TestRequest::get().uri(&format!("/jobs/{}/applications?{}", job, query)).to_request();
+        let desc: Value = test::call_and_read_body_json(&app, get("sort=rating&order=desc")).await;
+        assert_eq!(ids(desc), [json!(high), json!(low), json!(unrated)]);
+        let asc: Value = test::call_and_read_body_json(&app, get("sort=rating&order=asc")).await;
+        assert_eq!(ids(asc), [json!(low), json!(high), json!(unrated)]);
+        let filtered: Value = test::call_and_read_body_json(&app, get("min_rating=3")).await;
+        assert_eq!(ids(filtered), [json!(high)]);
+    }
+
+    #[actix_web::test]
+    async fn created_applications_get_the_database_assigned_id() {
+        let db = TestDb::new();
+        let employer = db.user("employer@example.com", "employer", "!");
+        let job = db.job(employer);
+        let app = test::init_service(
+            App::new()
+                .app_data(db.pool())
+                .app_data(db.read_pool())
+                .configure(configure(Data::new(ApplicationStore::default()))),
+        )
+        .await;
+
+        let mut ids = Vec::new();
+        for email in ["a@example.com", "b@example.com"] {
+            let seeker = db.user(email, "job_seeker", "!");
+            let application = json!({
+                "id": 0,
+                "job_seeker_id": seeker,
+                "job_id": job,
+                "cover_letter": null,
+                "resume": null,
+                "status": "pending",
+                "applied_at": 1726500600
+            });
+            let created: Value = test::call_and_read_body_json(
+                &app,
+                test::TestRequest::post().uri("/applications").set_json(application).to_request(),
+            )
+            .await;
+            let stored: Value = test::call_and_read_body_json(
+                &app,
+                test::TestRequest::get().uri(&format!("/applications/{}", created["id"])).to_request(),
+            )
+            .await;
+            assert_eq!(stored["job_seeker_id"], seeker);
+            assert_eq!(stored["reference"], created["reference"]);
+            ids.push(created["id"].as_i64().unwrap());
+        }
+        assert!(ids[0] > 0 && ids[0] != ids[1], "{:?}", ids);
+    }
+}
@@ -1,30 +1,99 @@
-use std::env;
-use actix_web::{delete, get, post, put, HttpResponse, Responder};
+use actix_web::{delete, get, patch, post, put, HttpResponse, Responder};
 use actix_web::web::{Data, Json, Path, Query, ServiceConfig};
-use chrono::Utc;
+use actix_multipart::Multipart;
+use chrono::{DateTime, Utc};
+use futures::TryStreamExt;
 use rusqlite::Connection;
 use serde::Deserialize;
 use log::{error, info};
-use crate::db::job;
-use crate::models::job::{Job, JobUpdateRequest, EmploymentType};
-use crate::models::JobStore;
-use crate::utils::{ErrorResponse, PaginationJob};
+use crate::config;
+use crate::config::features::{Feature, FeatureFlags};
+use crate::db::{Pool, ReadPool, is_foreign_key_violation, job, preferences, tag, user};
+use crate::models::job::{deadline_error, Job, JobBulkDeleteRequest, JobCloseAllResult, JobComparison, JobBulkDeleteResult, JobDraft, JobImportReport, JobImportResult, JobImportRow, JobPreview, JobTagPatch, JobTagRequest, JobTagResult, JobUpdateRequest};
+use crate::models::{JobId, JobReads, JobStatus, JobStore, UserId, UserRole};
+use crate::utils::fields::{parse_fields, select, select_page, FieldsQuery};
+use crate::utils::list::{ListParams, Page};
+use crate::utils::markdown;
+use crate::utils::{ErrorResponse, PaginationJob, PaginationJobChange, PAGINATION_DRIFT_HEADER, SKIPPED_ROWS_HEADER, ValidationResponse};
+
+/// Maximum number of jobs that can be deleted in one bulk request.
+const MAX_BULK_DELETE: usize = 100;
+
+/// Maximum number of jobs that can be tagged in one bulk request.
+const MAX_BULK_TAG: usize = 100;
+
+/// Default and maximum number of recommended jobs returned at once.
+const DEFAULT_RECOMMENDATIONS: i64 = 10;
+const MAX_RECOMMENDATIONS: i64 = 50;
+
+/// Maximum number of jobs that can be compared at once.
+const MAX_COMPARE: usize = 5;
+
+/// Maximum size in bytes of an uploaded CSV job import.
+const MAX_IMPORT_BYTES: usize = 1024 * 1024;
+
+/// Maximum number of rows in one CSV job import.
+const MAX_IMPORT_ROWS: usize = 1000;
 
 #[derive(Deserialize)]
-pub struct JobQuery {
+pub struct RecommendationQuery {
+    /// Maximum number of jobs to return.
     pub limit: Option<i64>,
-    pub offset: Option<i64>,
 }
 
-pub(crate) fn configure(store: Data<JobStore>) -> impl FnOnce(&mut ServiceConfig) {
+#[derive(Deserialize)]
+pub struct CompareQuery {
+    /// Comma separated list of job ids.
+    pub ids: String,
+}
+
+#[derive(Deserialize)]
+pub struct ImportQuery {
+    /// Save nothing if any row fails.
+    pub all_or_nothing: Option<bool>,
+}
+
+#[derive(Deserialize)]
+pub struct ClosedJobsQuery {
+    /// Only return jobs closed at or after this Unix timestamp.
+    pub since: Option<i64>,
+}
+
+#[derive(Deserialize)]
+pub struct JobQuery {
+    #[serde(flatten)]
+    pub list: ListParams,
+    pub fields: Option<String>,
+    pub snapshot_count: Option<i64>,
+    pub updated_since: Option<String>,
+    pub exclude_applied: Option<bool>,
+    pub job_seeker_id: Option<UserId>,
+}
+
+pub(crate) fn configure(store: Data<JobStore>, reads: Data<JobReads>) -> impl FnOnce(&mut ServiceConfig) {
     move |config: &mut ServiceConfig| {
         config
             .app_data(store)
+            .app_data(reads)
             .service(get_jobs)
+            .service(get_job_locations)
+            .service(compare_jobs)
             .service(get_job_by_id)
+            .service(get_job_preview)
+            .service(validate_job)
             .service(create_job)
             .service(update_job)
-            .service(delete_job);
+            .service(delete_job)
+            .service(get_delete_impact)
+            .service(delete_jobs)
+            .service(tag_jobs)
+            .service(import_jobs)
+            .service(patch_job_tags)
+            .service(get_review_queue)
+            .service(get_application_counts)
+            .service(get_closed_jobs)
+            .service(close_all_jobs)
+            .service(get_recommended_jobs);
     }
 }
 
@@ -33,26 +102,50 @@ pub(crate) fn configure(store: Data<JobStore>) -> impl FnOnce(&mut ServiceConfig
 /// This endpoint needs `api_key` authentication in order to call.
 ///
 /// List jobs from the database with pagination support.
+///
+/// With `exclude_applied=true`, leave out the jobs the job seeker has already applied to, both from the page and from
+/// `count`. Until requests carry an authenticated user, the job seeker is identified by `job_seeker_id`, and the flag
+/// is ignored without it.
+///
+/// With `updated_since`, return the changes since that time for incremental sync instead: jobs updated after it and
+/// tombstones of jobs deleted after it, as a `PaginationJobChange` ordered by when they changed. `fields` does not
+/// apply to changes.
 #[utoipa::path(
     context_path = "/v1",
     tag = "jobs",
     params(
-        ("limit" = Option<usize>, Query, description = "Maximum number of items to return", example = 10),
+        ("limit" = Option<usize>, Query, description = "Maximum number of items to return, at most 100", example = 10),
         ("offset" = Option<usize>, Query, description = "Offset for pagination", example = 0),
+        ("snapshot_count" = Option<i64>, Query, description = "`snapshot_count` returned by the first page, used to detect drift", example = 25),
+        ("fields" = Option<String>, Query, description = "Comma separated list of fields to return", example = "id,title,location"),
+        ("exclude_applied" = Option<bool>, Query, description = "Leave out jobs the job seeker `job_seeker_id` already applied to", example = true),
+        ("job_seeker_id" = Option<i64>, Query, description = "Unique ID of the job seeker browsing the jobs", example = 2),
+        ("updated_since" = Option<String>, Query, description = "RFC 3339 timestamp; return the changes since then as a `PaginationJobChange`", example = "2024-09-16T15:30:00Z"),
     ),
     responses(
-        (status = 200, description = "List current job items with pagination metadata", body = PaginationJob<Vec<Job>>),
+        (status = 200, description = "List current job items with pagination metadata, or a `PaginationJobChange` with `updated_since`", body = PaginationJob, headers(
+            ("X-Pagination-Drift" = i64, description = "Difference between the current total and `snapshot_count`, present only when they differ"),
+            ("X-Skipped-Rows" = usize, description = "Number of malformed rows left out of the page, present only when `STRICT_ROW_DECODING` is off and rows were skipped")
+        )),
+        (status = 400, description = "Unknown field requested, invalid `updated_since` or invalid pagination", body = ErrorResponse, example = json!(ErrorResponse::BadRequest(String::from("unknown field: foo")))),
         (status = 401, description = "Unauthorized to get jobs", body = ErrorResponse, example = json!(ErrorResponse::Unauthorized(String::from("Missing API Key")))),
     ),
     security(
-        (),
         ("api_key" = [])
     )
 )]
 #[get("/jobs")]
-pub(super) async fn get_jobs(query: Query<JobQuery>) -> impl Responder {
-    let db_url = env::var("DATABASE_URL").unwrap_or_else(|_| "not set".to_string());
-    let mut conn = match Connection::open(&db_url) {
+pub(super) async fn get_jobs(pool: Data<ReadPool>, query: Query<JobQuery>) -> impl Responder {
+    let fields = match parse_fields(query.fields.as_deref(), Job::FIELDS) {
+        Ok(fields) => fields,
+        Err(e) => return HttpResponse::BadRequest().json(ErrorResponse::BadRequest(e)),
+    };
+    let page = match query.list.page(&[]) {
+        Ok(page) => page,
+        Err(e) => return HttpResponse::BadRequest().json(ErrorResponse::BadRequest(e)),
+    };
+
+    let mut conn = match pool.get() {
         Ok(conn) => conn,
         Err(e) => {
             error!("Error connecting to the database: {:?}", e);
@@ -62,23 +155,33 @@ pub(super) async fn get_jobs(query: Query<JobQuery>) -> impl Responder {
         }
     };
 
-    let limit = query.limit.unwrap_or(10) as i64;
-    let offset = query.offset.unwrap_or(0) as i64;
+    if let Some(updated_since) = query.updated_since.as_deref() {
+        return get_job_changes(&mut conn, updated_since, page, &query);
+    }
 
-    let total_count = job::get_total_count(&mut conn).unwrap_or_else(|e| {
+    let exclude_applied_by = query.job_seeker_id.filter(|_| query.exclude_applied.unwrap_or(false));
+    let total_count = job::get_total_count(&mut conn, exclude_applied_by).unwrap_or_else(|e| {
         error!("Error getting total count from the database: {:?}", e);
         0
     });
+    let snapshot_count = query.snapshot_count.unwrap_or(total_count);
 
-    match job::get_all(&mut conn, limit, offset) {
-        Ok(jobs) => {
-            let page = (offset / limit) + 1;
+    match job::get_page(&mut conn, page.limit, page.offset, exclude_applied_by, config::strict_row_decoding()) {
+        Ok((jobs, skipped)) => {
             let pagination = PaginationJob {
-                page,
+                page: page.number(),
                 count: total_count,
+                snapshot_count,
                 items: jobs,
             };
-            HttpResponse::Ok().json(pagination)
+            let mut response = HttpResponse::Ok();
+            if snapshot_count != total_count {
+                response.insert_header((PAGINATION_DRIFT_HEADER, (total_count - snapshot_count).to_string()));
+            }
+            if skipped > 0 {
+                response.insert_header((SKIPPED_ROWS_HEADER, skipped.to_string()));
+            }
+            response.json(select_page(&pagination, &fields))
         }
         Err(e) => {
             error!("Error getting jobs from the database: {:?}", e);
@@ -89,6 +192,156 @@ pub(super) async fn get_jobs(query: Query<JobQuery>) -> impl Responder {
     }
 }
 
+/// Respond with the job changes since an RFC 3339 timestamp, paginated like the job list.
+fn get_job_changes(conn: &mut Connection, updated_since: &str, page: Page, query: &JobQuery) -> HttpResponse {
+    let since = match DateTime::parse_from_rfc3339(updated_since) {
+        Ok(since) => since.with_timezone(&Utc),
+        Err(_) => {
+            return HttpResponse::BadRequest().json(ErrorResponse::BadRequest(format!(
+                "invalid updated_since: {}, expected an RFC 3339 timestamp",
+                updated_since
+            )))
+        }
+    };
+
+    match job::get_changes_since(conn, since, page.limit, page.offset) {
+        Ok((changes, total_count)) => {
+            let snapshot_count = query.snapshot_count.unwrap_or(total_count);
+            let mut response = HttpResponse::Ok();
+            if snapshot_count != total_count {
+                response.insert_header((PAGINATION_DRIFT_HEADER, (total_count - snapshot_count).to_string()));
+            }
+            response.json(PaginationJobChange {
+                page: page.number(),
+                count: total_count,
+                snapshot_count,
+                items: changes,
+            })
+        }
+        Err(e) => {
+            error!("Error getting job changes from the database: {:?}", e);
+            HttpResponse::InternalServerError().json(ErrorResponse::InternalError(
+                "Error getting job changes from the database".to_string(),
+            ))
+        }
+    }
+}
+
+/// Get the distinct job locations.
+///
+/// This endpoint needs `api_key` authentication in order to call.
+///
+/// Return every distinct `Job` location with the number of jobs posted there, sorted alphabetically. Intended for
+/// populating location filters.
+#[utoipa::path(
+    context_path = "/v1",
+    tag = "jobs",
+    responses(
+        (status = 200, description = "Distinct job locations with counts", body = [JobLocation]),
+        (status = 401, description = "Unauthorized to get job locations", body = ErrorResponse, example = json!(ErrorResponse::Unauthorized(String::from("missing api key")))),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    ),
+    security(
+        ("api_key" = [])
+    )
+)]
+#[get("/jobs/locations")]
+pub(super) async fn get_job_locations(pool: Data<ReadPool>) -> impl Responder {
+    let mut conn = match pool.get() {
+        Ok(conn) => conn,
+        Err(e) => {
+            error!("Error connecting to the database: {:?}", e);
+            return HttpResponse::InternalServerError().json(ErrorResponse::InternalError(
+                "Error connecting to the database".to_string(),
+            ));
+        }
+    };
+
+    match job::get_locations(&mut conn) {
+        Ok(locations) => HttpResponse::Ok().json(locations),
+        Err(e) => {
+            error!("Error getting job locations from the database: {:?}", e);
+            HttpResponse::InternalServerError().json(ErrorResponse::InternalError(
+                "Error getting job locations from the database".to_string(),
+            ))
+        }
+    }
+}
+
+/// Compare jobs side by side.
+///
+/// This endpoint needs `api_key` authentication in order to call.
+///
+/// Return the jobs with the given comma separated `ids` in the requested order, along with their normalized `salary`,
+/// `location` and `employment_type` and whether each of them differs between the jobs. Unknown ids are ignored, but
+/// at least two existing jobs are required. At most 5 ids are accepted.
+#[utoipa::path(
+    context_path = "/v1",
+    tag = "jobs",
+    params(
+        ("ids" = String, Query, description = "Comma separated list of job ids", example = "5,9")
+    ),
+    responses(
+        (status = 200, description = "Job comparison", body = JobComparison),
+        (status = 400, description = "Invalid comparison request", body = ErrorResponse, example = json!(ErrorResponse::BadRequest(String::from("At least two existing jobs are required to compare")))),
+        (status = 401, description = "Unauthorized to compare jobs", body = ErrorResponse, example = json!(ErrorResponse::Unauthorized(String::from("missing api key")))),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    ),
+    security(
+        ("api_key" = [])
+    )
+)]
+#[get("/jobs/compare")]
+pub(super) async fn compare_jobs(pool: Data<ReadPool>, query: Query<CompareQuery>) -> impl Responder {
+    let mut ids: Vec<JobId> = Vec::new();
+    for id in query.ids.split(',').map(str::trim).filter(|id| !id.is_empty()) {
+        match id.parse() {
+            Ok(id) if !ids.contains(&id) => ids.push(id),
+            Ok(_) => {}
+            Err(_) => {
+                return HttpResponse::BadRequest().json(ErrorResponse::BadRequest(format!("invalid job id: {}", id)));
+            }
+        }
+    }
+    if ids.len() > MAX_COMPARE {
+        return HttpResponse::BadRequest().json(ErrorResponse::BadRequest(format!(
+            "At most {} jobs can be compared at once",
+            MAX_COMPARE
+        )));
+    }
+
+    let mut conn = match pool.get() {
+        Ok(conn) => conn,
+        Err(e) => {
+            error!("Error connecting to the database: {:?}", e);
+            return HttpResponse::InternalServerError().json(ErrorResponse::InternalError(
+                "Error connecting to the database".to_string(),
+            ));
+        }
+    };
+
+    let mut jobs = Vec::with_capacity(ids.len());
+    for id in ids {
+        match job::get_by_id(&mut conn, id) {
+            Ok(Some(job)) => jobs.push(job),
+            Ok(None) => {}
+            Err(e) => {
+                error!("Error getting job {} from the database: {:?}", id, e);
+                return HttpResponse::InternalServerError().json(ErrorResponse::InternalError(
+                    "Error getting jobs from the database".to_string(),
+                ));
+            }
+        }
+    }
+    if jobs.len() < 2 {
+        return HttpResponse::BadRequest().json(ErrorResponse::BadRequest(
+            "At least two existing jobs are required to compare".to_string(),
+        ));
+    }
+
+    HttpResponse::Ok().json(JobComparison::new(jobs))
+}
+
 /// Get job by given job id.
 ///
 /// This endpoint needs `api_key` authentication in order to call.
@@ -98,28 +351,107 @@ pub(super) async fn get_jobs(query: Query<JobQuery>) -> impl Responder {
     context_path = "/v1",
     tag = "jobs",
     params(
-        ("id", description = "Unique ID of the job", example = 1)
+        ("id", description = "Unique ID of the job", example = 1),
+        ("fields" = Option<String>, Query, description = "Comma separated list of fields to return")
     ),
     responses(
         (status = 200, description = "Job found", body = Job),
+        (status = 400, description = "Unknown field requested", body = ErrorResponse, example = json!(ErrorResponse::BadRequest(String::from("unknown field: foo")))),
         (status = 401, description = "Unauthorized to get job", body = ErrorResponse, example = json!(ErrorResponse::Unauthorized(String::from("missing api key")))),
-        (status = 404, description = "Job not found", body = ErrorResponse, example = json!(ErrorResponse::NotFound(String::from("id = 1"))))
+        (status = 404, description = "Job not found", body = ErrorResponse, example = json!(ErrorResponse::NotFound(String::from("id = 1")))),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
     ),
     security(
-        (),
         ("api_key" = [])
     )
 )]
 #[get("/jobs/{id}")]
-pub(super) async fn get_job_by_id(id: Path<i64>) -> impl Responder {
+pub(super) async fn get_job_by_id(pool: Data<ReadPool>, reads: Data<JobReads>, id: Path<JobId>, query: Query<FieldsQuery>) -> impl Responder {
     let id = id.into_inner();
-    let db_url = env::var("DATABASE_URL").unwrap_or_else(|_| "not set".to_string());
-    let mut conn = Connection::open(&db_url).unwrap();
+    let fields = match parse_fields(query.fields.as_deref(), Job::FIELDS) {
+        Ok(fields) => fields,
+        Err(e) => return HttpResponse::BadRequest().json(ErrorResponse::BadRequest(e)),
+    };
 
-    if let Ok(Some(job)) = job::get_by_id(&mut conn, id) {
-        HttpResponse::Ok().json(job)
-    } else {
-        HttpResponse::NotFound().json(ErrorResponse::NotFound(format!("Job with ID {} not found", id)))
+    // Concurrent requests for the same job share one database read.
+    let job = reads.run(id, || {
+        let mut conn = pool.get().map_err(|e| format!("Error connecting to the database: {:?}", e))?;
+        job::get_by_id(&mut conn, id).map_err(|e| format!("Error retrieving job with ID {}: {:?}", id, e))
+    }).await;
+
+    match job {
+        Ok(Some(job)) => HttpResponse::Ok().json(select(&job, &fields)),
+        Ok(None) => HttpResponse::NotFound().json(ErrorResponse::NotFound(format!("Job with ID {} not found", id))),
+        Err(e) => {
+            error!("{}", e);
+            HttpResponse::InternalServerError().json(ErrorResponse::InternalError(
+                "Error retrieving job".to_string(),
+            ))
+        }
+    }
+}
+
+/// Preview how a job will appear.
+///
+/// This endpoint needs `api_key` authentication in order to call.
+///
+/// Return the `Job` together with its description rendered from Markdown, its formatted salary and its tags, without
+/// modifying anything. Return 404 not found if `Job` is not found from the database.
+#[utoipa::path(
+    context_path = "/v1",
+    tag = "jobs",
+    params(
+        ("id", description = "Unique ID of the job", example = 1)
+    ),
+    responses(
+        (status = 200, description = "Job preview", body = JobPreview),
+        (status = 401, description = "Unauthorized to preview job", body = ErrorResponse, example = json!(ErrorResponse::Unauthorized(String::from("missing api key")))),
+        (status = 404, description = "Job not found", body = ErrorResponse, example = json!(ErrorResponse::NotFound(String::from("id = 1")))),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    ),
+    security(
+        ("api_key" = [])
+    )
+)]
+#[get("/jobs/{id}/preview")]
+pub(super) async fn get_job_preview(pool: Data<ReadPool>, id: Path<JobId>) -> impl Responder {
+    let id = id.into_inner();
+    let mut conn = match pool.get() {
+        Ok(conn) => conn,
+        Err(e) => {
+            error!("Error connecting to the database: {:?}", e);
+            return HttpResponse::InternalServerError().json(ErrorResponse::InternalError(
+                "Error connecting to the database".to_string(),
+            ));
+        }
+    };
+
+    let job = match job::get_by_id(&mut conn, id) {
+        Ok(Some(job)) => job,
+        Ok(None) => {
+            return HttpResponse::NotFound().json(ErrorResponse::NotFound(format!("Job with ID {} not found", id)));
+        }
+        Err(e) => {
+            error!("Error getting job {}: {:?}", id, e);
+            return HttpResponse::InternalServerError().json(ErrorResponse::InternalError(
+                "Error getting job".to_string(),
+            ));
+        }
+    };
+
+    match tag::get_for_job(&conn, id) {
+        Ok(tags) => HttpResponse::Ok().json(JobPreview {
+            description_html: markdown::render(&job.description),
+            salary_display: job.salary_display(),
+            tags,
+            job,
+        }),
+        Err(e) => {
+            error!("Error getting tags for job {}: {:?}", id, e);
+            HttpResponse::InternalServerError().json(ErrorResponse::InternalError(
+                "Error getting job tags".to_string(),
+            ))
+        }
     }
 }
 
@@ -127,7 +459,9 @@ pub(super) async fn get_job_by_id(id: Path<i64>) -> impl Responder {
 ///
 /// This endpoint needs `api_key` authentication in order to call.
 ///
-/// Create a new `Job` in the database.
+/// Create a new `Job` in the database and return it with the id the database assigned. `title` and `description`
+/// must not be blank and are limited to `MAX_JOB_TITLE_LENGTH` and `MAX_JOB_DESCRIPTION_LENGTH` characters, and the
+/// `deadline` must not be in the past. Malformed JSON returns 400, a well-formed but invalid `Job` 422.
 #[utoipa::path(
     request_body = Job,
     context_path = "/v1",
@@ -135,18 +469,16 @@ pub(super) async fn get_job_by_id(id: Path<i64>) -> impl Responder {
     responses(
         (status = 201, description = "Job created successfully", body = Job),
         (status = 401, description = "Unauthorized to create job", body = ErrorResponse, example = json!(ErrorResponse::Unauthorized(String::from("missing api key")))),
-        (status = 400, description = "Invalid job data", body = ErrorResponse, example = json!(ErrorResponse::BadRequest(String::from("Invalid job data"))))
+        (status = 400, description = "Malformed job data", body = ErrorResponse, example = json!(ErrorResponse::BadRequest(String::from("invalid JSON body: Json deserialize error: missing field `title`")))),
+        (status = 422, description = "Invalid job data", body = ErrorResponse, example = json!(ErrorResponse::UnprocessableEntity(String::from("title is required"))))
     ),
     security(
-        (),
         ("api_key" = [])
     )
 )]
 #[post("/jobs")]
-pub(super) async fn create_job(job: Json<Job>) -> impl Responder {
-    let db_url = env::var("DATABASE_URL").unwrap_or_else(|_| "not set".to_string());
-    info!("DATABASE_URL = {:?}", db_url);
-    let mut conn = match Connection::open(&db_url) {
+pub(super) async fn create_job(pool: Data<Pool>, job: Json<Job>) -> impl Responder {
+    let mut conn = match pool.get() {
         Ok(conn) => conn,
         Err(e) => {
             error!("Error connecting to the database: {:?}", e);
@@ -156,10 +488,25 @@ pub(super) async fn create_job(job: Json<Job>) -> impl Responder {
         }
     };
 
-    let job = job.into_inner();
+    let mut job = job.into_inner();
+    job.is_new = Job::is_recent(job.posted_at);
+    job.closed_at = match job.status {
+        JobStatus::Open => None,
+        _ => Some(Utc::now()),
+    };
+
+    if let Err(errors) = job.validate_new(Utc::now()) {
+        let message = errors
+            .iter()
+            .map(|e| e.message.clone())
+            .collect::<Vec<_>>()
+            .join("; ");
+        return HttpResponse::UnprocessableEntity().json(ErrorResponse::UnprocessableEntity(message));
+    }
 
     match job::create(&mut conn, job.clone()) {
-        Ok(_) => {
+        Ok(id) => {
+            job.id = id;
             info!("Job created successfully: {:?}", job);
             HttpResponse::Created().json(job)
         }
@@ -172,11 +519,46 @@ pub(super) async fn create_job(job: Json<Job>) -> impl Responder {
     }
 }
 
+/// Validate a job posting without saving it.
+///
+/// This endpoint needs `api_key` authentication in order to call.
+///
+/// Runs the same validation as job creation and reports every failing field, including missing fields and unknown
+/// `employment_type` or `status` values, but never writes to the database.
+#[utoipa::path(
+    request_body = JobDraft,
+    context_path = "/v1",
+    tag = "jobs",
+    responses(
+        (status = 200, description = "Job posting is valid", body = ValidationResponse, example = json!({"valid": true})),
+        (status = 422, description = "Job posting is invalid", body = ValidationResponse, example = json!({"valid": false, "errors": [{"field": "title", "message": "title is required"}]})),
+        (status = 401, description = "Unauthorized to validate job", body = ErrorResponse, example = json!(ErrorResponse::Unauthorized(String::from("missing api key"))))
+    ),
+    security(
+        ("api_key" = [])
+    )
+)]
+#[post("/jobs/validate")]
+pub(super) async fn validate_job(draft: Json<JobDraft>) -> impl Responder {
+    match draft.into_inner().into_job(Utc::now()) {
+        Ok(_) => HttpResponse::Ok().json(ValidationResponse {
+            valid: true,
+            errors: Vec::new(),
+        }),
+        Err(errors) => HttpResponse::UnprocessableEntity().json(ValidationResponse {
+            valid: false,
+            errors,
+        }),
+    }
+}
+
 /// Update an existing job.
 ///
 /// This endpoint needs `api_key` authentication in order to call.
 ///
-/// Update an existing `Job` in the database.
+/// Update an existing `Job` in the database. The updated `Job` is validated like a new one, so `title` and
+/// `description` must stay non-blank and within `MAX_JOB_TITLE_LENGTH` and `MAX_JOB_DESCRIPTION_LENGTH` characters, and
+/// a new `deadline` must not be in the past.
 #[utoipa::path(
     context_path = "/v1",
     tag = "jobs",
@@ -188,21 +570,21 @@ pub(super) async fn create_job(job: Json<Job>) -> impl Responder {
         (status = 200, description = "Job updated successfully", body = Job),
         (status = 401, description = "Unauthorized to update job", body = ErrorResponse, example = json!(ErrorResponse::Unauthorized(String::from("missing api key")))),
         (status = 404, description = "Job not found", body = ErrorResponse, example = json!(ErrorResponse::NotFound(String::from("id = 1")))),
-        (status = 400, description = "Invalid job update data", body = ErrorResponse, example = json!(ErrorResponse::BadRequest(String::from("Invalid job update data"))))
+        (status = 400, description = "Invalid job update data", body = ErrorResponse, example = json!(ErrorResponse::BadRequest(String::from("Invalid job update data")))),
+        (status = 422, description = "Updated job is invalid", body = ErrorResponse, example = json!(ErrorResponse::UnprocessableEntity(String::from("title is required"))))
     ),
     security(
-        (),
         ("api_key" = [])
     )
 )]
 #[put("/jobs/{id}")]
 pub(super) async fn update_job(
-    id: Path<i64>,
+    pool: Data<Pool>,
+    id: Path<JobId>,
     job_update_request: Json<JobUpdateRequest>,
 ) -> impl Responder {
     let id = id.into_inner();
-    let db_url = env::var("DATABASE_URL").unwrap_or_else(|_| "not set".to_string());
-    let mut conn = match Connection::open(&db_url) {
+    let mut conn = match pool.get() {
         Ok(conn) => conn,
         Err(e) => {
             error!("Error connecting to the database: {:?}", e);
@@ -222,18 +604,37 @@ pub(super) async fn update_job(
         }
     };
 
+    let now = Utc::now();
+    let status = job_update_request.status.clone().unwrap_or_else(|| existing_job.status.clone());
+    let closed_at = existing_job.closed_at_after(&status, now);
     let updated_job = Job {
         id: existing_job.id,
         employer_id: existing_job.employer_id,
         title: job_update_request.title.clone().unwrap_or(existing_job.title),
         description: job_update_request.description.clone().unwrap_or(existing_job.description),
         location: job_update_request.location.clone().unwrap_or(existing_job.location),
-        salary: Some(job_update_request.salary.clone().unwrap_or(existing_job.salary.unwrap_or_default())),
+        salary: job_update_request.salary.clone().or(existing_job.salary),
         employment_type: job_update_request.employment_type.clone().unwrap_or(existing_job.employment_type),
         posted_at: existing_job.posted_at,
-        updated_at: Utc::now(),
+        updated_at: now,
+        deadline: job_update_request.deadline.or(existing_job.deadline),
+        is_new: existing_job.is_new,
+        status,
+        closed_at,
+        auto_reject_after: job_update_request.auto_reject_after.or(existing_job.auto_reject_after),
     };
 
+    let mut errors = updated_job.validate().err().unwrap_or_default();
+    errors.extend(deadline_error(job_update_request.deadline, now));
+    if !errors.is_empty() {
+        let message = errors
+            .iter()
+            .map(|e| e.message.clone())
+            .collect::<Vec<_>>()
+            .join("; ");
+        return HttpResponse::UnprocessableEntity().json(ErrorResponse::UnprocessableEntity(message));
+    }
+
     match job::update(&mut conn, id, updated_job.clone()) {
         Ok(_) => HttpResponse::Ok().json(updated_job),
         Err(e) => {
@@ -247,7 +648,8 @@ pub(super) async fn update_job(
 ///
 /// This endpoint needs `api_key` authentication in order to call.
 ///
-/// Delete an existing `Job` from the database.
+/// Delete an existing `Job` from the database. Returns 409 if applications were submitted to the `Job`, since
+/// they keep referring to it. Tags are detached from the `Job`.
 #[utoipa::path(
     context_path = "/v1",
     tag = "jobs",
@@ -258,23 +660,1110 @@ pub(super) async fn update_job(
         (status = 204, description = "Job deleted successfully"),
         (status = 401, description = "Unauthorized to delete job", body = ErrorResponse, example = json!(ErrorResponse::Unauthorized(String::from("missing api key")))),
         (status = 404, description = "Job not found", body = ErrorResponse, example = json!(ErrorResponse::NotFound(String::from("id = 1")))),
+        (status = 409, description = "Job still has applications", body = ErrorResponse, example = json!(ErrorResponse::Conflict(String::from("Job with ID 1 still has applications")))),
     ),
     security(
-        (),
         ("api_key" = [])
     )
 )]
 #[delete("/jobs/{id}")]
-pub(super) async fn delete_job(id: Path<i64>) -> impl Responder {
+pub(super) async fn delete_job(pool: Data<Pool>, id: Path<JobId>) -> impl Responder {
     let id = id.into_inner();
-    let db_url = env::var("DATABASE_URL").unwrap_or_else(|_| "not set".to_string());
-    let mut conn = Connection::open(&db_url).unwrap();
+    let mut conn = match pool.get() {
+        Ok(conn) => conn,
+        Err(e) => {
+            error!("Error connecting to the database: {:?}", e);
+            return HttpResponse::InternalServerError().json(ErrorResponse::InternalError(
+                "Error connecting to the database".to_string(),
+            ));
+        }
+    };
 
     match job::delete(&mut conn, id) {
         Ok(_) => HttpResponse::NoContent().finish(),
+        Err(e) if is_foreign_key_violation(e.as_ref()) => {
+            HttpResponse::Conflict().json(ErrorResponse::Conflict(format!("Job with ID {} still has applications", id)))
+        }
         Err(e) => {
             error!("Error deleting job with ID {}: {:?}", id, e);
             HttpResponse::InternalServerError().finish()
         }
     }
-}
\ No newline at end of file
+}
+
+/// Preview what deleting a job would affect.
+///
+/// This endpoint needs `api_key` authentication in order to call.
+///
+/// Count the applications, attachments, notes and tag links that depend on the `Job` without deleting anything. A
+/// `Job` with applications cannot be deleted, so `blocked` reports whether `DELETE /v1/jobs/{id}` would fail with 409.
+/// Tag links are removed along with the `Job`. Return 404 not found if `Job` is not found from the database.
+#[utoipa::path(
+    context_path = "/v1",
+    tag = "jobs",
+    params(
+        ("id", description = "Unique ID of the job", example = 1)
+    ),
+    responses(
+        (status = 200, description = "Records affected by deleting the job", body = JobDeleteImpact),
+        (status = 401, description = "Unauthorized to preview job deletion", body = ErrorResponse, example = json!(ErrorResponse::Unauthorized(String::from("missing api key")))),
+        (status = 404, description = "Job not found", body = ErrorResponse, example = json!(ErrorResponse::NotFound(String::from("Job with ID 1 not found")))),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    ),
+    security(
+        ("api_key" = [])
+    )
+)]
+#[get("/jobs/{id}/delete-impact")]
+pub(super) async fn get_delete_impact(pool: Data<ReadPool>, id: Path<JobId>) -> impl Responder {
+    let id = id.into_inner();
+    let mut conn = match pool.get() {
+        Ok(conn) => conn,
+        Err(e) => {
+            error!("Error connecting to the database: {:?}", e);
+            return HttpResponse::InternalServerError().json(ErrorResponse::InternalError(
+                "Error connecting to the database".to_string(),
+            ));
+        }
+    };
+
+    match job::get_by_id(&mut conn, id) {
+        Ok(Some(_)) => {}
+        Ok(None) => {
+            return HttpResponse::NotFound().json(ErrorResponse::NotFound(format!("Job with ID {} not found", id)));
+        }
+        Err(e) => {
+            error!("Error getting job {}: {:?}", id, e);
+            return HttpResponse::InternalServerError().json(ErrorResponse::InternalError(
+                "Error getting job".to_string(),
+            ));
+        }
+    }
+
+    match job::get_delete_impact(&mut conn, id) {
+        Ok(impact) => HttpResponse::Ok().json(impact),
+        Err(e) => {
+            error!("Error getting delete impact of job {}: {:?}", id, e);
+            HttpResponse::InternalServerError().json(ErrorResponse::InternalError(
+                "Error getting delete impact".to_string(),
+            ))
+        }
+    }
+}
+
+/// Delete several jobs at once.
+///
+/// This endpoint needs `api_key` authentication in order to call.
+///
+/// Delete every listed `Job` in a single transaction and report per-id results: `deleted`, `not_found` for ids that
+/// do not exist, or `conflict` for jobs kept because applications were submitted to them. A conflict does not stop the
+/// other ids from being deleted. At most 100 ids are accepted per request.
+#[utoipa::path(
+    context_path = "/v1",
+    tag = "jobs",
+    request_body = JobBulkDeleteRequest,
+    responses(
+        (status = 200, description = "Jobs deleted", body = [JobBulkDeleteResult]),
+        (status = 400, description = "Invalid bulk delete request", body = ErrorResponse, example = json!(ErrorResponse::BadRequest(String::from("At most 100 ids can be deleted at once")))),
+        (status = 401, description = "Unauthorized to delete jobs", body = ErrorResponse, example = json!(ErrorResponse::Unauthorized(String::from("missing api key")))),
+        (status = 500, description = "Bulk delete failed and was rolled back", body = ErrorResponse)
+    ),
+    security(
+        ("api_key" = [])
+    )
+)]
+#[delete("/jobs")]
+pub(super) async fn delete_jobs(pool: Data<Pool>, request: Json<JobBulkDeleteRequest>) -> impl Responder {
+    let ids = &request.ids;
+    if ids.is_empty() {
+        return HttpResponse::BadRequest().json(ErrorResponse::BadRequest(
+            "At least one id is required".to_string(),
+        ));
+    }
+    if ids.len() > MAX_BULK_DELETE {
+        return HttpResponse::BadRequest().json(ErrorResponse::BadRequest(format!(
+            "At most {} ids can be deleted at once",
+            MAX_BULK_DELETE
+        )));
+    }
+
+    let mut conn = match pool.get() {
+        Ok(conn) => conn,
+        Err(e) => {
+            error!("Error connecting to the database: {:?}", e);
+            return HttpResponse::InternalServerError().json(ErrorResponse::InternalError(
+                "Error connecting to the database".to_string(),
+            ));
+        }
+    };
+
+    match job::delete_many(&mut conn, ids) {
+        Ok(results) => {
+            let results: Vec<JobBulkDeleteResult> = results
+                .into_iter()
+                .map(|(id, status)| JobBulkDeleteResult { id, status })
+                .collect();
+            HttpResponse::Ok().json(results)
+        }
+        Err(e) => {
+            error!("Error bulk deleting jobs: {:?}", e);
+            HttpResponse::InternalServerError().json(ErrorResponse::InternalError(
+                "Error deleting jobs, no jobs were deleted".to_string(),
+            ))
+        }
+    }
+}
+
+/// Tag several jobs at once.
+///
+/// This endpoint needs `api_key` authentication in order to call.
+///
+/// Attach every listed tag to every listed `Job` in a single transaction and report per-id results. Tags are created
+/// as needed, surrounding whitespace is trimmed and tags are stored in lowercase. Ids that do not exist are reported
+/// with `tagged: false`. At most 100 ids are accepted per request.
+#[utoipa::path(
+    context_path = "/v1",
+    tag = "jobs",
+    request_body = JobTagRequest,
+    responses(
+        (status = 200, description = "Jobs tagged", body = [JobTagResult]),
+        (status = 400, description = "Invalid bulk tag request", body = ErrorResponse, example = json!(ErrorResponse::BadRequest(String::from("At least one tag is required")))),
+        (status = 401, description = "Unauthorized to tag jobs", body = ErrorResponse, example = json!(ErrorResponse::Unauthorized(String::from("missing api key")))),
+        (status = 500, description = "Bulk tag failed and was rolled back", body = ErrorResponse)
+    ),
+    security(
+        ("api_key" = [])
+    )
+)]
+#[post("/jobs/tags")]
+pub(super) async fn tag_jobs(pool: Data<Pool>, request: Json<JobTagRequest>) -> impl Responder {
+    let ids = &request.job_ids;
+    if ids.is_empty() {
+        return HttpResponse::BadRequest().json(ErrorResponse::BadRequest(
+            "At least one job id is required".to_string(),
+        ));
+    }
+    if ids.len() > MAX_BULK_TAG {
+        return HttpResponse::BadRequest().json(ErrorResponse::BadRequest(format!(
+            "At most {} jobs can be tagged at once",
+            MAX_BULK_TAG
+        )));
+    }
+
+    let mut tags: Vec<String> = request.tags.iter().map(|name| tag::normalize(name)).collect();
+    tags.sort();
+    tags.dedup();
+    if tags.is_empty() {
+        return HttpResponse::BadRequest().json(ErrorResponse::BadRequest(
+            "At least one tag is required".to_string(),
+        ));
+    }
+    if tags.iter().any(|tag| tag.is_empty()) {
+        return HttpResponse::BadRequest().json(ErrorResponse::BadRequest(
+            "Tags must not be blank".to_string(),
+        ));
+    }
+
+    let mut conn = match pool.get() {
+        Ok(conn) => conn,
+        Err(e) => {
+            error!("Error connecting to the database: {:?}", e);
+            return HttpResponse::InternalServerError().json(ErrorResponse::InternalError(
+                "Error connecting to the database".to_string(),
+            ));
+        }
+    };
+
+    match tag::tag_jobs(&mut conn, ids, &tags) {
+        Ok(results) => {
+            let results: Vec<JobTagResult> = results
+                .into_iter()
+                .map(|(id, tagged)| JobTagResult { id, tagged })
+                .collect();
+            HttpResponse::Ok().json(results)
+        }
+        Err(e) => {
+            error!("Error bulk tagging jobs: {:?}", e);
+            HttpResponse::InternalServerError().json(ErrorResponse::InternalError(
+                "Error tagging jobs, no jobs were tagged".to_string(),
+            ))
+        }
+    }
+}
+
+/// Import jobs from a CSV file.
+///
+/// This endpoint needs `api_key` authentication in order to call.
+///
+/// Upload a CSV file as the `file` field of a `multipart/form-data` request. The header row names the columns:
+/// `employer_id`, `title`, `description`, `location` and `employment_type` are required, `salary`, `deadline`,
+/// `status` and `auto_reject_after` are optional, timestamps are Unix timestamps. Every row is validated like in job
+/// creation and the valid rows are created in a single transaction. The report lists the created id or the error of
+/// each row by line number. With `all_or_nothing=true`, nothing is saved if any row fails. At most 1000 rows and
+/// 1 MiB are accepted per upload.
+#[utoipa::path(
+    context_path = "/v1",
+    tag = "jobs",
+    params(
+        ("all_or_nothing" = Option<bool>, Query, description = "Save nothing if any row fails, defaults to false")
+    ),
+    request_body(content = String, content_type = "multipart/form-data", description = "CSV file in the `file` field"),
+    responses(
+        (status = 200, description = "Import report", body = JobImportReport),
+        (status = 400, description = "Invalid upload", body = ErrorResponse, example = json!(ErrorResponse::BadRequest(String::from("A CSV file is required in the `file` field")))),
+        (status = 401, description = "Unauthorized to import jobs", body = ErrorResponse, example = json!(ErrorResponse::Unauthorized(String::from("missing api key")))),
+        (status = 500, description = "Import failed and was rolled back", body = ErrorResponse)
+    ),
+    security(
+        ("api_key" = [])
+    )
+)]
+#[post("/jobs/import")]
+pub(super) async fn import_jobs(pool: Data<Pool>, query: Query<ImportQuery>, payload: Multipart) -> impl Responder {
+    let data = match read_upload(payload).await {
+        Ok(Some(data)) => data,
+        Ok(None) => {
+            return HttpResponse::BadRequest().json(ErrorResponse::BadRequest(
+                "A CSV file is required in the `file` field".to_string(),
+            ));
+        }
+        Err(message) => return HttpResponse::BadRequest().json(ErrorResponse::BadRequest(message)),
+    };
+
+    let now = Utc::now();
+    let mut reader = csv::Reader::from_reader(data.as_slice());
+    let headers = match reader.headers() {
+        Ok(headers) => headers.clone(),
+        Err(e) => {
+            return HttpResponse::BadRequest().json(ErrorResponse::BadRequest(format!("Invalid CSV file: {}", e)));
+        }
+    };
+    let mut rows = Vec::new();
+    let mut record = csv::StringRecord::new();
+    loop {
+        let line = reader.position().line();
+        let row = match reader.read_record(&mut record) {
+            Ok(false) => break,
+            Ok(true) => record
+                .deserialize::<JobImportRow>(Some(&headers))
+                .map_err(|e| e.to_string())
+                .and_then(|row| row.into_job(now))
+                .and_then(|job| match job.validate() {
+                    Ok(()) => Ok(job),
+                    Err(errors) => Err(errors.iter().map(|e| e.message.clone()).collect::<Vec<_>>().join("; ")),
+                }),
+            Err(e) if matches!(e.kind(), csv::ErrorKind::UnequalLengths { .. }) => Err(e.to_string()),
+            Err(e) => {
+                return HttpResponse::BadRequest().json(ErrorResponse::BadRequest(format!("Invalid CSV file: {}", e)));
+            }
+        };
+        if rows.len() == MAX_IMPORT_ROWS {
+            return HttpResponse::BadRequest().json(ErrorResponse::BadRequest(format!(
+                "At most {} rows can be imported at once",
+                MAX_IMPORT_ROWS
+            )));
+        }
+        rows.push((line, row));
+    }
+
+    let all_or_nothing = query.all_or_nothing.unwrap_or(false);
+    let jobs: Vec<Job> = rows.iter().filter_map(|(_, row)| row.as_ref().ok().cloned()).collect();
+    let (created, committed) = if all_or_nothing && jobs.len() < rows.len() {
+        (Vec::new(), false)
+    } else {
+        let mut conn = match pool.get() {
+            Ok(conn) => conn,
+            Err(e) => {
+                error!("Error connecting to the database: {:?}", e);
+                return HttpResponse::InternalServerError().json(ErrorResponse::InternalError(
+                    "Error connecting to the database".to_string(),
+                ));
+            }
+        };
+        match job::create_many(&mut conn, &jobs, all_or_nothing) {
+            Ok(outcome) => outcome,
+            Err(e) => {
+                error!("Error importing jobs: {:?}", e);
+                return HttpResponse::InternalServerError().json(ErrorResponse::InternalError(
+                    "Error importing jobs, no jobs were created".to_string(),
+                ));
+            }
+        }
+    };
+
+    let mut created = created.into_iter();
+    let results: Vec<JobImportResult> = rows
+        .into_iter()
+        .map(|(line, row)| {
+            // Valid rows are not inserted at all when another row already failed in all-or-nothing mode.
+            let outcome = row.map(|_| created.next()).and_then(Option::transpose);
+            match outcome {
+                Ok(Some(id)) if committed => JobImportResult { line, id: Some(id), error: None },
+                Ok(_) => JobImportResult { line, id: None, error: None },
+                Err(error) => JobImportResult { line, id: None, error: Some(error) },
+            }
+        })
+        .collect();
+    let failed = results.iter().filter(|result| result.error.is_some()).count();
+    let report = JobImportReport {
+        created: results.iter().filter(|result| result.id.is_some()).count(),
+        failed,
+        rolled_back: !committed,
+        results,
+    };
+    info!("Imported jobs: {} created, {} failed", report.created, report.failed);
+    HttpResponse::Ok().json(report)
+}
+
+/// Read the `file` field of a multipart upload, or `None` if there is none.
+async fn read_upload(mut payload: Multipart) -> Result<Option<Vec<u8>>, String> {
+    while let Some(mut field) = payload.try_next().await.map_err(|e| e.to_string())? {
+        if field.name() != Some("file") {
+            continue;
+        }
+
+        let mut data = Vec::new();
+        while let Some(chunk) = field.try_next().await.map_err(|e| e.to_string())? {
+            if data.len() + chunk.len() > MAX_IMPORT_BYTES {
+                return Err(format!("The file must be at most {} bytes", MAX_IMPORT_BYTES));
+            }
+            data.extend_from_slice(&chunk);
+        }
+        return Ok(Some(data));
+    }
+    Ok(None)
+}
+
+/// Add and remove tags of a job.
+///
+/// This endpoint needs `api_key` authentication in order to call.
+///
+/// Apply the `add` and `remove` sets to the tags of a `Job` in a single transaction and return its resulting tags.
+/// Tags are normalized like in bulk tagging. Removing a tag the `Job` does not have is a no-op. A tag listed in both
+/// sets is rejected with 422. Return 404 not found if the `Job` does not exist.
+#[utoipa::path(
+    context_path = "/v1",
+    tag = "jobs",
+    params(
+        ("id", description = "Unique ID of the Job", example = 1)
+    ),
+    request_body = JobTagPatch,
+    responses(
+        (status = 200, description = "Resulting tags of the job", body = [String], example = json!(["remote", "rust"])),
+        (status = 400, description = "Invalid tag patch", body = ErrorResponse, example = json!(ErrorResponse::BadRequest(String::from("Tags must not be blank")))),
+        (status = 401, description = "Unauthorized to update job tags", body = ErrorResponse, example = json!(ErrorResponse::Unauthorized(String::from("missing api key")))),
+        (status = 404, description = "Job not found", body = ErrorResponse, example = json!(ErrorResponse::NotFound(String::from("Job with ID 1 not found")))),
+        (status = 422, description = "Tag both added and removed", body = ErrorResponse, example = json!(ErrorResponse::UnprocessableEntity(String::from("Tag rust is both added and removed")))),
+        (status = 500, description = "Tag patch failed and was rolled back", body = ErrorResponse)
+    ),
+    security(
+        ("api_key" = [])
+    )
+)]
+#[patch("/jobs/{id}/tags")]
+pub(super) async fn patch_job_tags(pool: Data<Pool>, id: Path<JobId>, request: Json<JobTagPatch>) -> impl Responder {
+    let job_id = id.into_inner();
+    let normalize_all = |names: &[String]| {
+        let mut tags: Vec<String> = names.iter().map(|name| tag::normalize(name)).collect();
+        tags.sort();
+        tags.dedup();
+        tags
+    };
+    let add = normalize_all(&request.add);
+    let remove = normalize_all(&request.remove);
+    if add.iter().chain(&remove).any(|tag| tag.is_empty()) {
+        return HttpResponse::BadRequest().json(ErrorResponse::BadRequest(
+            "Tags must not be blank".to_string(),
+        ));
+    }
+    if let Some(tag) = add.iter().find(|tag| remove.contains(tag)) {
+        return HttpResponse::UnprocessableEntity().json(ErrorResponse::UnprocessableEntity(format!(
+            "Tag {} is both added and removed",
+            tag
+        )));
+    }
+
+    let mut conn = match pool.get() {
+        Ok(conn) => conn,
+        Err(e) => {
+            error!("Error connecting to the database: {:?}", e);
+            return HttpResponse::InternalServerError().json(ErrorResponse::InternalError(
+                "Error connecting to the database".to_string(),
+            ));
+        }
+    };
+
+    match tag::patch_job_tags(&mut conn, job_id, &add, &remove) {
+        Ok(Some(tags)) => HttpResponse::Ok().json(tags),
+        Ok(None) => HttpResponse::NotFound().json(ErrorResponse::NotFound(format!(
+            "Job with ID {} not found",
+            job_id
+        ))),
+        Err(e) => {
+            error!("Error patching tags of job {}: {:?}", job_id, e);
+            HttpResponse::InternalServerError().json(ErrorResponse::InternalError(
+                "Error updating job tags, no tags were changed".to_string(),
+            ))
+        }
+    }
+}
+
+/// Get the review queue of an employer.
+///
+/// This endpoint needs `api_key` authentication in order to call.
+///
+/// Return the employer's jobs that have at least one pending `Application`, with the number of pending applications
+/// per job, ordered by oldest pending application first. Return 404 not found if no employer with the given id exists.
+#[utoipa::path(
+    context_path = "/v1",
+    tag = "jobs",
+    params(
+        ("id", description = "Unique ID of the employer", example = 1)
+    ),
+    responses(
+        (status = 200, description = "Review queue of the employer", body = [ReviewQueueItem]),
+        (status = 401, description = "Unauthorized to get review queue", body = ErrorResponse, example = json!(ErrorResponse::Unauthorized(String::from("missing api key")))),
+        (status = 404, description = "Employer not found", body = ErrorResponse, example = json!(ErrorResponse::NotFound(String::from("Employer with ID 1 not found")))),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    ),
+    security(
+        ("api_key" = [])
+    )
+)]
+#[get("/employers/{id}/review-queue")]
+pub(super) async fn get_review_queue(pool: Data<ReadPool>, id: Path<UserId>) -> impl Responder {
+    let id = id.into_inner();
+    let mut conn = match pool.get() {
+        Ok(conn) => conn,
+        Err(e) => {
+            error!("Error connecting to the database: {:?}", e);
+            return HttpResponse::InternalServerError().json(ErrorResponse::InternalError(
+                "Error connecting to the database".to_string(),
+            ));
+        }
+    };
+
+    match user::get_by_id(&mut conn, id) {
+        Ok(Some(employer)) if matches!(employer.role, UserRole::Employer) => {}
+        Ok(_) => {
+            return HttpResponse::NotFound().json(ErrorResponse::NotFound(format!("Employer with ID {} not found", id)));
+        }
+        Err(e) => {
+            error!("Error getting employer {}: {:?}", id, e);
+            return HttpResponse::InternalServerError().json(ErrorResponse::InternalError(
+                "Error getting employer".to_string(),
+            ));
+        }
+    }
+
+    match job::get_review_queue(&mut conn, id) {
+        Ok(queue) => HttpResponse::Ok().json(queue),
+        Err(e) => {
+            error!("Error getting review queue for employer {}: {:?}", id, e);
+            HttpResponse::InternalServerError().json(ErrorResponse::InternalError(
+                "Error getting review queue".to_string(),
+            ))
+        }
+    }
+}
+
+/// Get application counts for the jobs of an employer.
+///
+/// This endpoint needs `api_key` authentication in order to call.
+///
+/// Return every `Job` of the employer with its number of applications, ordered by job id. Jobs without applications
+/// are included with a count of zero. Return 404 not found if no employer with the given id exists.
+#[utoipa::path(
+    context_path = "/v1",
+    tag = "jobs",
+    params(
+        ("id", description = "Unique ID of the employer", example = 1)
+    ),
+    responses(
+        (status = 200, description = "Application counts per job", body = [JobApplicationCount]),
+        (status = 401, description = "Unauthorized to get application counts", body = ErrorResponse, example = json!(ErrorResponse::Unauthorized(String::from("missing api key")))),
+        (status = 404, description = "Employer not found", body = ErrorResponse, example = json!(ErrorResponse::NotFound(String::from("Employer with ID 1 not found")))),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    ),
+    security(
+        ("api_key" = [])
+    )
+)]
+#[get("/employers/{id}/jobs/application-counts")]
+pub(super) async fn get_application_counts(pool: Data<ReadPool>, id: Path<UserId>) -> impl Responder {
+    let id = id.into_inner();
+    let mut conn = match pool.get() {
+        Ok(conn) => conn,
+        Err(e) => {
+            error!("Error connecting to the database: {:?}", e);
+            return HttpResponse::InternalServerError().json(ErrorResponse::InternalError(
+                "Error connecting to the database".to_string(),
+            ));
+        }
+    };
+
+    match user::get_by_id(&mut conn, id) {
+        Ok(Some(employer)) if matches!(employer.role, UserRole::Employer) => {}
+        Ok(_) => {
+            return HttpResponse::NotFound().json(ErrorResponse::NotFound(format!("Employer with ID {} not found", id)));
+        }
+        Err(e) => {
+            error!("Error getting employer {}: {:?}", id, e);
+            return HttpResponse::InternalServerError().json(ErrorResponse::InternalError(
+                "Error getting employer".to_string(),
+            ));
+        }
+    }
+
+    match job::get_application_counts(&mut conn, id) {
+        Ok(counts) => HttpResponse::Ok().json(counts),
+        Err(e) => {
+            error!("Error getting application counts for employer {}: {:?}", id, e);
+            HttpResponse::InternalServerError().json(ErrorResponse::InternalError(
+                "Error getting application counts".to_string(),
+            ))
+        }
+    }
+}
+
+/// Get the jobs an employer has closed.
+///
+/// This endpoint needs `api_key` authentication in order to call.
+///
+/// Return the employer's closed or expired jobs, most recently closed first, optionally limited to jobs closed at or
+/// after `since`. Return 404 not found if no employer with the given id exists.
+#[utoipa::path(
+    context_path = "/v1",
+    tag = "jobs",
+    params(
+        ("id", description = "Unique ID of the employer", example = 1),
+        ("since" = Option<i64>, Query, description = "Only return jobs closed at or after this Unix timestamp", example = 1726500600)
+    ),
+    responses(
+        (status = 200, description = "Closed jobs of the employer", body = [Job]),
+        (status = 400, description = "Invalid since timestamp", body = ErrorResponse, example = json!(ErrorResponse::BadRequest(String::from("invalid since: 99999999999999")))),
+        (status = 401, description = "Unauthorized to get closed jobs", body = ErrorResponse, example = json!(ErrorResponse::Unauthorized(String::from("missing api key")))),
+        (status = 404, description = "Employer not found", body = ErrorResponse, example = json!(ErrorResponse::NotFound(String::from("Employer with ID 1 not found")))),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    ),
+    security(
+        ("api_key" = [])
+    )
+)]
+#[get("/employers/{id}/closed-jobs")]
+pub(super) async fn get_closed_jobs(pool: Data<ReadPool>, id: Path<UserId>, query: Query<ClosedJobsQuery>) -> impl Responder {
+    let id = id.into_inner();
+    let since = match query.since {
+        Some(since) => match DateTime::from_timestamp(since, 0) {
+            Some(since) => since,
+            None => {
+                return HttpResponse::BadRequest().json(ErrorResponse::BadRequest(format!("invalid since: {}", since)));
+            }
+        },
+        None => DateTime::UNIX_EPOCH,
+    };
+
+    let mut conn = match pool.get() {
+        Ok(conn) => conn,
+        Err(e) => {
+            error!("Error connecting to the database: {:?}", e);
+            return HttpResponse::InternalServerError().json(ErrorResponse::InternalError(
+                "Error connecting to the database".to_string(),
+            ));
+        }
+    };
+
+    match user::get_by_id(&mut conn, id) {
+        Ok(Some(employer)) if matches!(employer.role, UserRole::Employer) => {}
+        Ok(_) => {
+            return HttpResponse::NotFound().json(ErrorResponse::NotFound(format!("Employer with ID {} not found", id)));
+        }
+        Err(e) => {
+            error!("Error getting employer {}: {:?}", id, e);
+            return HttpResponse::InternalServerError().json(ErrorResponse::InternalError(
+                "Error getting employer".to_string(),
+            ));
+        }
+    }
+
+    match job::get_closed_for_employer(&mut conn, id, since) {
+        Ok(jobs) => HttpResponse::Ok().json(jobs),
+        Err(e) => {
+            error!("Error getting closed jobs for employer {}: {:?}", id, e);
+            HttpResponse::InternalServerError().json(ErrorResponse::InternalError(
+                "Error getting closed jobs".to_string(),
+            ))
+        }
+    }
+}
+
+/// Close every open job of an employer.
+///
+/// This endpoint needs `api_key` authentication in order to call.
+///
+/// Move every `open` `Job` of the employer to `closed` in one transaction, setting `closed_at`, e.g. when the employer
+/// pauses hiring. Closed and expired jobs are left unchanged, and pending applications are not rejected. Until requests
+/// carry an authenticated user, the employer is identified by the path. Return 404 not found if no employer with the
+/// given id exists.
+#[utoipa::path(
+    context_path = "/v1",
+    tag = "jobs",
+    params(
+        ("id", description = "Unique ID of the employer", example = 1)
+    ),
+    responses(
+        (status = 200, description = "Open jobs closed", body = JobCloseAllResult),
+        (status = 401, description = "Unauthorized to close jobs", body = ErrorResponse, example = json!(ErrorResponse::Unauthorized(String::from("missing api key")))),
+        (status = 404, description = "Employer not found", body = ErrorResponse, example = json!(ErrorResponse::NotFound(String::from("Employer with ID 1 not found")))),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    ),
+    security(
+        ("api_key" = [])
+    )
+)]
+#[post("/employers/{id}/jobs/close-all")]
+pub(super) async fn close_all_jobs(pool: Data<Pool>, id: Path<UserId>) -> impl Responder {
+    let id = id.into_inner();
+    let mut conn = match pool.get() {
+        Ok(conn) => conn,
+        Err(e) => {
+            error!("Error connecting to the database: {:?}", e);
+            return HttpResponse::InternalServerError().json(ErrorResponse::InternalError(
+                "Error connecting to the database".to_string(),
+            ));
+        }
+    };
+
+    match user::get_by_id(&mut conn, id) {
+        Ok(Some(employer)) if matches!(employer.role, UserRole::Employer) => {}
+        Ok(_) => {
+            return HttpResponse::NotFound().json(ErrorResponse::NotFound(format!("Employer with ID {} not found", id)));
+        }
+        Err(e) => {
+            error!("Error getting employer {}: {:?}", id, e);
+            return HttpResponse::InternalServerError().json(ErrorResponse::InternalError(
+                "Error getting employer".to_string(),
+            ));
+        }
+    }
+
+    match job::close_all_for_employer(&mut conn, id, Utc::now()) {
+        Ok(closed) => {
+            info!("Closed {} open job(s) of employer {}", closed, id);
+            HttpResponse::Ok().json(JobCloseAllResult { closed })
+        }
+        Err(e) => {
+            error!("Error closing the jobs of employer {}: {:?}", id, e);
+            HttpResponse::InternalServerError().json(ErrorResponse::InternalError(
+                "Error closing jobs".to_string(),
+            ))
+        }
+    }
+}
+
+/// Get jobs recommended to a job seeker.
+///
+/// This endpoint needs `api_key` authentication in order to call.
+///
+/// Return open jobs similar to the jobs the seeker applied to, ranked by how many tags they share plus whether the
+/// employment type and location match, and whether they match the seeker's saved job preferences. Jobs the seeker
+/// already applied to, jobs past their deadline, jobs paying less than the preferred `min_salary` and jobs matching
+/// nothing are left out. Intended for the seeker themselves. Return 404 not found if no job seeker with the given id
+/// exists, or if `FEATURE_JOB_RECOMMENDATIONS` is off.
+#[utoipa::path(
+    context_path = "/v1",
+    tag = "jobs",
+    params(
+        ("id", description = "Unique ID of the job seeker", example = 2),
+        ("limit" = Option<i64>, Query, description = "Maximum number of jobs to return, at most 50", example = 10)
+    ),
+    responses(
+        (status = 200, description = "Recommended jobs, best match first", body = [RecommendedJob]),
+        (status = 401, description = "Unauthorized to get recommended jobs", body = ErrorResponse, example = json!(ErrorResponse::Unauthorized(String::from("missing api key")))),
+        (status = 404, description = "Job seeker not found", body = ErrorResponse, example = json!(ErrorResponse::NotFound(String::from("Job seeker with ID 2 not found")))),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    ),
+    security(
+        ("api_key" = [])
+    )
+)]
+#[get("/users/{id}/recommended-jobs")]
+pub(super) async fn get_recommended_jobs(
+    pool: Data<ReadPool>,
+    features: Data<FeatureFlags>,
+    id: Path<UserId>,
+    query: Query<RecommendationQuery>,
+) -> impl Responder {
+    if !features.is_enabled(Feature::JobRecommendations) {
+        return HttpResponse::NotFound().json(ErrorResponse::NotFound("Job recommendations are not enabled".to_string()));
+    }
+    let id = id.into_inner();
+    let limit = query.limit.unwrap_or(DEFAULT_RECOMMENDATIONS).clamp(1, MAX_RECOMMENDATIONS);
+    let mut conn = match pool.get() {
+        Ok(conn) => conn,
+        Err(e) => {
+            error!("Error connecting to the database: {:?}", e);
+            return HttpResponse::InternalServerError().json(ErrorResponse::InternalError(
+                "Error connecting to the database".to_string(),
+            ));
+        }
+    };
+
+    match user::get_by_id(&mut conn, id) {
+        Ok(Some(seeker)) if matches!(seeker.role, UserRole::JobSeeker) => {}
+        Ok(_) => {
+            return HttpResponse::NotFound().json(ErrorResponse::NotFound(format!("Job seeker with ID {} not found", id)));
+        }
+        Err(e) => {
+            error!("Error getting job seeker {}: {:?}", id, e);
+            return HttpResponse::InternalServerError().json(ErrorResponse::InternalError(
+                "Error getting job seeker".to_string(),
+            ));
+        }
+    }
+
+    let min_salary = match preferences::get(&mut conn, id) {
+        Ok(preferences) => preferences.and_then(|preferences| preferences.min_salary),
+        Err(e) => {
+            error!("Error getting job preferences of job seeker {}: {:?}", id, e);
+            return HttpResponse::InternalServerError().json(ErrorResponse::InternalError(
+                "Error getting job preferences".to_string(),
+            ));
+        }
+    };
+
+    match job::get_recommended(&mut conn, id, min_salary, Utc::now(), limit) {
+        Ok(jobs) => HttpResponse::Ok().json(jobs),
+        Err(e) => {
+            error!("Error getting recommended jobs for job seeker {}: {:?}", id, e);
+            HttpResponse::InternalServerError().json(ErrorResponse::InternalError(
+                "Error getting recommended jobs".to_string(),
+            ))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{test, App};
+    use actix_web::http::StatusCode;
+    use serde_json::Value;
+    use crate::utils::testing::TestDb;
+    use serde_json::json;
+    use actix_web::web::JsonConfig;
+    use crate::utils::json_error_handler;
+    use utoipa::OpenApi;
+    use actix_web::web::Data;
+    use crate::models::{JobReads, JobStore};
+    use super::{configure, create_job, delete_job, delete_jobs, get_delete_impact, import_jobs, update_job, validate_job};
+
+    #[actix_web::test]
+    async fn spec_requires_an_api_key_to_create_jobs() {
+        #[derive(OpenApi)]
+        #[openapi(paths(super::create_job, super::get_jobs))]
+        struct Spec;
+
+        let spec = serde_json::to_value(Spec::openapi()).unwrap();
+        assert_eq!(spec["paths"]["/v1/jobs"]["post"]["security"], json!([{ "api_key": [] }]));
+        assert_eq!(spec["paths"]["/v1/jobs"]["get"]["security"], json!([{ "api_key": [] }]));
+    }
+
+    #[actix_web::test]
+    async fn jobs_with_applications_cannot_be_deleted() {
+        let db = TestDb::new();
+        let employer = db.user("employer@example.com", "employer", "!");
+        let seeker = db.user("seeker@example.com", "job_seeker", "!");
+        let applied = db.job(employer);
+        let unapplied = db.job(employer);
+        db.application(applied, seeker);
+        let app = test::init_service(
+            App::new().app_data(db.pool()).app_data(db.read_pool()).service(get_delete_impact).service(delete_job),
+        )
+        .await;
+
+        for (job, blocked, status) in [(applied, true, StatusCode::CONFLICT), (unapplied, false, StatusCode::NO_CONTENT)] {
+            let impact: Value = test::call_and_read_body_json(
+                &app,
+                test::TestRequest::get().uri(&format!("/jobs/{}/delete-impact", job)).to_request(),
+            )
+            .await;
+            assert_eq!(impact["blocked"], blocked, "job {}", job);
+            let response = test::call_service(&app, test::TestRequest::delete().uri(&format!("/jobs/{}", job)).to_request()).await;
+            assert_eq!(response.status(), status, "job {}", job);
+        }
+    }
+
+    #[actix_web::test]
+    async fn bulk_delete_reports_each_id() {
+        let db = TestDb::new();
+        let employer = db.user("employer@example.com", "employer", "!");
+        let seeker = db.user("seeker@example.com", "job_seeker", "!");
+        let applied = db.job(employer);
+        let unapplied = db.job(employer);
+        db.application(applied, seeker);
+        let app = test::init_service(App::new().app_data(db.pool()).service(delete_jobs)).await;
+
+        let request = test::TestRequest::delete().uri("/jobs").set_json(json!({"ids": [unapplied, 99, applied]}));
+        let results: Value = test::call_and_read_body_json(&app, request.to_request()).await;
+        assert_eq!(
+            results,
+            json!([
+                {"id": unapplied, "status": "deleted"},
+                {"id": 99, "status": "not_found"},
+                {"id": applied, "status": "conflict"},
+            ])
+        );
+    }
+
+    #[actix_web::test]
+    async fn updates_are_validated_like_new_jobs() {
+        let db = TestDb::new();
+        let employer = db.user("employer@example.com", "employer", "!");
+        let job = db.job(employer);
+        let app = test::init_service(App::new().app_data(db.pool()).service(update_job)).await;
+
+        for (body, status) in [
+            (json!({"title": ""}), StatusCode::UNPROCESSABLE_ENTITY),
+            (json!({"description": "x".repeat(10_001)}), StatusCode::UNPROCESSABLE_ENTITY),
+            (json!({"title": "Staff Engineer"}), StatusCode::OK),
+        ] {
+            let request = test::TestRequest::put().uri(&format!("/jobs/{}", job)).set_json(&body);
+            assert_eq!(test::call_service(&app, request.to_request()).await.status(), status, "{}", body);
+        }
+        let title: String = db.conn().query_row("SELECT title FROM jobs WHERE id = ?1", [job], |row| row.get(0)).unwrap();
+        assert_eq!(title, "Staff Engineer");
+    }
+
+    #[actix_web::test]
+    async fn validate_reports_every_failing_field_without_saving() {
+        let db = TestDb::new();
+        let app = test::init_service(App::new().app_data(db.pool()).service(validate_job)).await;
+        let valid = json!({
+            "employer_id": 1,
+            "title": "Software Engineer",
+            "description": "Build things.",
+            "location": "Stockholm",
+            "salary": "$120,000 - $150,000",
+            "employment_type": "full_time"
+        });
+
+        let response = test::call_service(&app, test::TestRequest::post().uri("/jobs/validate").set_json(&valid).to_request()).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let body: Value = test::read_body_json(response).await;
+        assert_eq!(body["valid"], true);
+
+        let mut invalid = valid.clone();
+        invalid["salary"] = json!("lots");
+        invalid["employment_type"] = json!("freelance");
+        let response = test::call_service(&app, test::TestRequest::post().uri("/jobs/validate").set_json(&invalid).to_request()).await;
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        let body: Value = test::read_body_json(response).await;
+        let fields: Vec<&str> = body["errors"].as_array().unwrap().iter().map(|error| error["field"].as_str().unwrap()).collect();
+        assert_eq!(fields, ["employment_type", "salary"]);
+
+        let missing = json!({"employer_id": 1, "description": "Build things.", "location": "Stockholm"});
+        let response = test::call_service(&app, test::TestRequest::post().uri("/jobs/validate").set_json(&missing).to_request()).await;
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        let body: Value = test::read_body_json(response).await;
+        assert_eq!(body["valid"], false);
+        assert_eq!(body["errors"], json!([
+            {"field": "title", "message": "title is required"},
+            {"field": "employment_type", "message": "employment_type is required"}
+        ]));
+
+        let jobs: i64 = db.conn().query_row("SELECT COUNT(*) FROM jobs", [], |row| row.get(0)).unwrap();
+        assert_eq!(jobs, 0);
+    }
+
+    #[actix_web::test]
+    async fn malformed_jobs_get_400_and_invalid_ones_422() {
+        let db = TestDb::new();
+        let employer = db.user("employer@example.com", "employer", "!");
+        let app = test::init_service(
+            App::new()
+                .app_data(db.pool())
+                .app_data(JsonConfig::default().error_handler(json_error_handler))
+                .service(create_job),
+        )
+        .await;
+        let job = |deadline: i64| json!({
+            "id": 0,
+            "employer_id": employer,
+            "title": "Software Engineer",
+            "description": "Build things.",
+            "location": "Stockholm",
+            "salary": null,
+            "employment_type": "full_time",
+            "posted_at": 1726500600,
+            "updated_at": 1726500600,
+            "deadline": deadline
+        });
+
+        let malformed = test::TestRequest::post()
+            .uri("/jobs")
+            .insert_header(("content-type", "application/json"))
+            .set_payload("{\"title\": ");
+        let response = test::call_service(&app, malformed.to_request()).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let body: Value = test::read_body_json(response).await;
+        assert!(body["BadRequest"].as_str().unwrap().starts_with("invalid JSON body"));
+
+        let past = test::TestRequest::post().uri("/jobs").set_json(job(1_000_000_000));
+        let response = test::call_service(&app, past.to_request()).await;
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        let body: Value = test::read_body_json(response).await;
+        assert_eq!(body, json!({"UnprocessableEntity": "deadline must not be in the past"}));
+
+        let future = test::TestRequest::post().uri("/jobs").set_json(job(4_000_000_000));
+        assert_eq!(test::call_service(&app, future.to_request()).await.status(), StatusCode::CREATED);
+    }
+
+    #[actix_web::test]
+    async fn only_the_requested_fields_are_returned() {
+        let db = TestDb::new();
+        let employer = db.user("employer@example.com", "employer", "!");
+        let job = db.job(employer);
+        let app = test::init_service(
+            App::new()
+                .app_data(db.pool())
+                .app_data(db.read_pool())
+                .configure(configure(Data::new(JobStore::default()), Data::new(JobReads::default()))),
+        )
+        .await;
+
+        let page: Value = test::call_and_read_body_json(
+            &app,
+            test::TestRequest::get().uri("/jobs?fields=id,title,location").to_request(),
+        )
+        .await;
+        assert_eq!(page["items"], json!([{"id": job, "title": "Software Engineer", "location": "Stockholm"}]));
+
+        let by_id: Value = test::call_and_read_body_json(
+            &app,
+            test::TestRequest::get().uri(&format!("/jobs/{}?fields=title", job)).to_request(),
+        )
+        .await;
+        assert_eq!(by_id, json!({"title": "Software Engineer"}));
+
+        for uri in ["/jobs?fields=id,salaryy".to_string(), format!("/jobs/{}?fields=password", job)] {
+            let response = test::call_service(&app, test::TestRequest::get().uri(&uri).to_request()).await;
+            assert_eq!(response.status(), StatusCode::BAD_REQUEST, "{}", uri);
+        }
+    }
+
+    #[actix_web::test]
+    async fn csv_import_reports_the_bad_row_by_line() {
+        let db = TestDb::new();
+        let employer = db.user("employer@example.com", "employer", "!");
+        let app = test::init_service(App::new().app_data(db.pool()).service(import_jobs)).await;
+        let csv = format!(
+            "employer_id,title,description,location,salary,employment_type\n\
+             {employer},Backend Engineer,Build APIs.,Stockholm,,full_time\n\
+             {employer},Designer,Draw things.,Berlin,,freelance\n\
+             {employer},Data Engineer,Move data.,Oslo,,contract\n"
+        );
+        let upload = |uri: &str| {
+            test::TestRequest::post()
+                .uri(uri)
+                .insert_header(("content-type", "multipart/form-data; boundary=BOUNDARY"))
+                .set_payload(format!(
+                    "--BOUNDARY\r\nContent-Disposition: form-data; name=\"file\"; filename=\"jobs.csv\"\r\n\
+                     Content-Type: text/csv\r\n\r\n{csv}\r\n--BOUNDARY--\r\n"
+                ))
+                .to_request()
+        };
+        let job_count = || db.conn().query_row("SELECT COUNT(*) FROM jobs", [], |row| row.get::<_, i64>(0)).unwrap();
+
+        let report: Value = test::call_and_read_body_json(&app, upload("/jobs/import?all_or_nothing=true")).await;
+        assert_eq!((report["created"].clone(), report["failed"].clone()), (json!(0), json!(1)));
+        assert_eq!(report["rolled_back"], true);
+        assert_eq!(job_count(), 0);
+
+        let report: Value = test::call_and_read_body_json(&app, upload("/jobs/import")).await;
+        assert_eq!((report["created"].clone(), report["failed"].clone()), (json!(2), json!(1)));
+        assert_eq!(report["rolled_back"], false);
+        let results = report["results"].as_array().unwrap();
+        assert_eq!(results.iter().map(|result| result["line"].clone()).collect::<Vec<_>>(), [json!(2), json!(3), json!(4)]);
+        assert!(results[0]["id"].is_number() && results[2]["id"].is_number());
+        assert!(results[1]["id"].is_null());
+        assert!(results[1]["error"].as_str().unwrap().contains("freelance"), "{}", results[1]);
+        assert_eq!(job_count(), 2);
+    }
+
+    #[actix_web::test]
+    async fn reads_go_to_the_read_pool_and_writes_to_the_primary() {
+        let primary = TestDb::new();
+        let replica = TestDb::new();
+        let employer = primary.user("employer@example.com", "employer", "!");
+        replica.user("employer@example.com", "employer", "!");
+        replica.job(employer);
+        replica.job(employer);
+        let app = test::init_service(
+            App::new()
+                .app_data(primary.pool())
+                .app_data(replica.read_pool())
+                .configure(configure(Data::new(JobStore::default()), Data::new(JobReads::default()))),
+        )
+        .await;
+        let job_count = |db: &TestDb| db.conn().query_row("SELECT COUNT(*) FROM jobs", [], |row| row.get::<_, i64>(0)).unwrap();
+
+        let page: Value = test::call_and_read_body_json(&app, test::TestRequest::get().uri("/jobs").to_request()).await;
+        assert_eq!(page["count"], 2);
+
+        let job = json!({
+            "id": 0,
+            "employer_id": employer,
+            "title": "Software Engineer",
+            "description": "Build things.",
+            "location": "Stockholm",
+            "salary": null,
+            "employment_type": "full_time",
+            "posted_at": 1726500600,
+            "updated_at": 1726500600
+        });
+        let response = test::call_service(&app, test::TestRequest::post().uri("/jobs").set_json(job).to_request()).await;
+        assert_eq!(response.status(), StatusCode::CREATED);
+        assert_eq!((job_count(&primary), job_count(&replica)), (1, 2));
+
+        let page: Value = test::call_and_read_body_json(&app, test::TestRequest::get().uri("/jobs").to_request()).await;
+        assert_eq!(page["count"], 2);
+    }
+
+    #[actix_web::test]
+    async fn created_jobs_get_the_database_assigned_id() {
+        let db = TestDb::new();
+        let employer = db.user("employer@example.com", "employer", "!");
+        let app = test::init_service(
+            App::new()
+                .app_data(db.pool())
+                .app_data(db.read_pool())
+                .configure(configure(Data::new(JobStore::default()), Data::new(JobReads::default()))),
+        )
+        .await;
+
+        let mut ids = Vec::new();
+        for title in ["Backend Engineer", "Frontend Engineer"] {
+            let job = json!({
+                "id": 0,
+                "employer_id": employer,
+                "title": title,
+                "description": "Build things.",
+                "location": "Stockholm",
+                "salary": null,
+                "employment_type": "full_time",
+                "posted_at": 1726500600,
+                "updated_at": 1726500600
+            });
+            let created: Value =
+                test::call_and_read_body_json(&app, test::TestRequest::post().uri("/jobs").set_json(job).to_request()).await;
+            let stored: Value = test::call_and_read_body_json(
+                &app,
+                test::TestRequest::get().uri(&format!("/jobs/{}", created["id"])).to_request(),
+            )
+            .await;
+            assert_eq!(stored["title"], title);
+            ids.push(created["id"].as_i64().unwrap());
+        }
+        assert!(ids[0] > 0 && ids[0] != ids[1], "{:?}", ids);
+    }
+}
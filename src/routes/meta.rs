@@ -0,0 +1,167 @@
+use actix_web::{get, HttpResponse, Responder};
+use actix_web::web::{Data, ServiceConfig};
+use chrono::{DateTime, Utc};
+use log::error;
+use serde::Serialize;
+use utoipa::ToSchema;
+use crate::db::Pool;
+use crate::models::application::AttachmentKind;
+use crate::models::{ApplicationStatus, EmploymentType, JobStatus, UserRole};
+use crate::utils::init_db::{schema_version, SCHEMA_VERSION};
+use crate::utils::ErrorResponse;
+
+/// Valid values for the enums used in request and response bodies.
+#[derive(Serialize, ToSchema)]
+pub struct EnumValues {
+    /// Valid `employment_type` values.
+    #[schema(example = json!(["full_time", "part_time", "contract"]))]
+    pub employment_type: Vec<String>,
+    /// Valid `job_status` values.
+    #[schema(example = json!(["open", "closed", "expired"]))]
+    pub job_status: Vec<String>,
+    /// Valid `application_status` values.
+    #[schema(example = json!(["pending", "reviewed", "accepted", "rejected"]))]
+    pub application_status: Vec<String>,
+    /// Valid `attachment_kind` values.
+    #[schema(example = json!(["resume", "portfolio", "other"]))]
+    pub attachment_kind: Vec<String>,
+    /// Valid `user_role` values.
+    #[schema(example = json!(["job_seeker", "employer"]))]
+    pub user_role: Vec<String>,
+}
+
+/// Health of the service and its database schema.
+#[derive(Serialize, ToSchema)]
+pub struct Health {
+    /// `ok`, or `migrations_pending` when the database schema is older than this build expects.
+    #[schema(example = "ok")]
+    pub status: String,
+    /// Version of the running build.
+    #[schema(example = "0.1.0")]
+    pub version: String,
+    /// Schema version of the database, i.e. the number of migrations applied.
+    #[schema(example = 8)]
+    pub schema_version: usize,
+    /// Schema version this build expects.
+    #[schema(example = 8)]
+    pub expected_schema_version: usize,
+}
+
+/// Metadata of the running build.
+#[derive(Serialize, ToSchema)]
+pub struct Version {
+    /// Crate version.
+    #[schema(example = "0.1.0")]
+    pub version: String,
+    /// Git commit the build was made from, or `unknown` if it could not be determined.
+    #[schema(example = "0270aeb5c8a4f1e2d3b4a5968778695a4b3c2d1e")]
+    pub commit: String,
+    /// Timestamp of when the build was made.
+    #[serde(with = "chrono::serde::ts_seconds")]
+    #[schema(value_type = i64, example = 1726500600)]
+    pub built_at: DateTime<Utc>,
+}
+
+pub(crate) fn configure() -> impl FnOnce(&mut ServiceConfig) {
+    move |config: &mut ServiceConfig| {
+        config
+            .service(get_health)
+            .service(get_version)
+            .service(get_enums);
+    }
+}
+
+/// Get the valid values of every enum.
+///
+/// This endpoint needs `api_key` authentication in order to call.
+///
+/// Return the values clients can send for each enum, derived from the enums themselves so they stay in sync.
+#[utoipa::path(
+    context_path = "/v1",
+    tag = "meta",
+    responses(
+        (status = 200, description = "Valid enum values", body = EnumValues),
+        (status = 401, description = "Unauthorized to get enum values", body = ErrorResponse, example = json!(ErrorResponse::Unauthorized(String::from("missing api key"))))
+    ),
+    security(
+        ("api_key" = [])
+    )
+)]
+#[get("/meta/enums")]
+pub(super) async fn get_enums() -> impl Responder {
+    HttpResponse::Ok().json(EnumValues {
+        employment_type: EmploymentType::ALL.iter().map(ToString::to_string).collect(),
+        job_status: JobStatus::ALL.iter().map(ToString::to_string).collect(),
+        application_status: ApplicationStatus::ALL.iter().map(ToString::to_string).collect(),
+        attachment_kind: AttachmentKind::ALL.iter().map(ToString::to_string).collect(),
+        user_role: UserRole::ALL.iter().map(ToString::to_string).collect(),
+    })
+}
+
+/// Get the version of the running build.
+///
+/// Return the crate version, the git commit and the time the build was made, to confirm which build is deployed.
+#[utoipa::path(
+    context_path = "/v1",
+    tag = "meta",
+    responses(
+        (status = 200, description = "Build metadata", body = Version)
+    )
+)]
+#[get("/version")]
+pub(super) async fn get_version() -> impl Responder {
+    let built_at = env!("BUILD_TIMESTAMP").parse().unwrap_or_default();
+    HttpResponse::Ok().json(Version {
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        commit: env!("GIT_COMMIT_HASH").to_string(),
+        built_at: DateTime::from_timestamp(built_at, 0).unwrap_or_default(),
+    })
+}
+
+/// Get the health of the service.
+///
+/// Report the build version and the database schema version. Return 503 service unavailable if the database has
+/// migrations pending, and 500 if it cannot be read.
+#[utoipa::path(
+    context_path = "/v1",
+    tag = "meta",
+    responses(
+        (status = 200, description = "Service is healthy", body = Health),
+        (status = 503, description = "Migrations pending", body = Health, example = json!({
+            "status": "migrations_pending",
+            "version": "0.1.0",
+            "schema_version": 7,
+            "expected_schema_version": 8
+        })),
+        (status = 500, description = "Database unavailable", body = ErrorResponse)
+    )
+)]
+#[get("/health")]
+pub(super) async fn get_health(pool: Data<Pool>) -> impl Responder {
+    let version = pool
+        .get()
+        .map_err(|e| e.to_string())
+        .and_then(|conn| schema_version(&conn).map_err(|e| e.to_string()));
+    let schema_version = match version {
+        Ok(version) => version,
+        Err(e) => {
+            error!("Health check failed to read the schema version: {:?}", e);
+            return HttpResponse::InternalServerError().json(ErrorResponse::InternalError(
+                "Database unavailable".to_string(),
+            ));
+        }
+    };
+
+    let pending = schema_version < SCHEMA_VERSION;
+    let health = Health {
+        status: if pending { "migrations_pending" } else { "ok" }.to_string(),
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        schema_version,
+        expected_schema_version: SCHEMA_VERSION,
+    };
+    if pending {
+        HttpResponse::ServiceUnavailable().json(health)
+    } else {
+        HttpResponse::Ok().json(health)
+    }
+}
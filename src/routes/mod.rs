@@ -1,3 +1,6 @@
 pub mod user;
 pub mod job;
-pub mod application;
\ No newline at end of file
+pub mod application;
+pub mod admin;
+pub mod meta;
+pub mod auth;
\ No newline at end of file
@@ -1,19 +1,54 @@
-use std::env;
+use std::collections::BTreeMap;
 use actix_web::{delete, get, post, put, HttpResponse, Responder};
 use actix_web::web::{Data, Json, Path, Query, ServiceConfig};
-use rusqlite::Connection;
 use serde::Deserialize;
 use log::{error, info};
-use crate::db::application::get_by_id;
-use crate::db::user;
-use crate::models::{User, UserStore};
-use crate::models::user::UserUpdateRequest;
-use crate::utils::{ErrorResponse, PaginationUser};
+use rusqlite::Connection;
+use chrono::Utc;
+use crate::auth::password;
+use crate::config;
+use crate::db::{application, Pool, ReadPool, is_not_null_violation, is_unique_violation, preferences, user};
+use crate::models::{JobId, User, UserId, UserRole, UserStore};
+use crate::models::user::{is_valid_email, ActivityKind, JobPreferences, JobPreferencesRequest, UserUpdateRequest};
+use crate::utils::list::{ListParams, DEFAULT_LIMIT, MAX_LIMIT};
+use crate::utils::fields::{parse_fields, select, select_page, FieldsQuery};
+use crate::utils::{ErrorResponse, PaginationActivity, PaginationEmployer, PaginationUser, PAGINATION_DRIFT_HEADER, SKIPPED_ROWS_HEADER};
+
+/// Maximum number of users that can be fetched by id in one request.
+const MAX_BATCH_IDS: usize = 100;
+
+/// Status reported for a job the job seeker has not applied to.
+const NOT_APPLIED: &str = "not_applied";
 
 #[derive(Deserialize)]
-pub struct UserQuery {
+pub struct ApplicationStatusQuery {
+    /// Comma separated list of job ids.
+    pub job_ids: String,
+}
+
+#[derive(Deserialize)]
+pub struct ActivityQuery {
+    /// Maximum number of events to return.
     pub limit: Option<i64>,
-    pub offset: Option<i64>,
+    /// `next_cursor` of the previous page.
+    pub cursor: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct UserQuery {
+    pub ids: Option<String>,
+    #[serde(flatten)]
+    pub list: ListParams,
+    pub fields: Option<String>,
+    pub snapshot_count: Option<i64>,
+}
+
+#[derive(Deserialize)]
+pub struct EmployerQuery {
+    pub has_open_jobs: Option<bool>,
+    #[serde(flatten)]
+    pub list: ListParams,
+    pub snapshot_count: Option<i64>,
 }
 
 pub(crate) fn configure(store: Data<UserStore>) -> impl FnOnce(&mut ServiceConfig) {
@@ -22,8 +57,15 @@ pub(crate) fn configure(store: Data<UserStore>) -> impl FnOnce(&mut ServiceConfi
             .app_data(store)
             .service(get_users)
             .service(get_user_by_id)
+            .service(get_employers)
             .service(create_user)
             .service(update_user)
+            .service(ensure_user)
+            .service(get_completeness)
+            .service(get_preferences)
+            .service(set_preferences)
+            .service(get_activity)
+            .service(get_application_statuses)
             .service(delete_user);
     }
 }
@@ -33,26 +75,43 @@ pub(crate) fn configure(store: Data<UserStore>) -> impl FnOnce(&mut ServiceConfi
 /// This endpoint needs `api_key` authentication in order to call.
 ///
 /// List users from the database with pagination support.
+///
+/// When `ids` is given, return a plain array of the users with those ids instead, ordered by id and without
+/// passwords. Unknown ids are ignored and at most 100 ids are accepted.
 #[utoipa::path(
     context_path = "/v1",
     tag = "users",
     params(
-        ("limit" = Option<usize>, Query, description = "Maximum number of items to return", example = 10),
+        ("ids" = Option<String>, Query, description = "Comma separated list of user ids to fetch", example = "1,2,3"),
+        ("limit" = Option<usize>, Query, description = "Maximum number of items to return, at most 100", example = 10),
         ("offset" = Option<usize>, Query, description = "Offset for pagination", example = 0),
+        ("snapshot_count" = Option<i64>, Query, description = "`snapshot_count` returned by the first page, used to detect drift", example = 25),
+        ("fields" = Option<String>, Query, description = "Comma separated list of fields to return", example = "id,name,email"),
     ),
     responses(
-        (status = 200, description = "List current user items with pagination metadata", body = PaginationUser<Vec<User>>),
+        (status = 200, description = "List current user items with pagination metadata", body = PaginationUser, headers(
+            ("X-Pagination-Drift" = i64, description = "Difference between the current total and `snapshot_count`, present only when they differ"),
+            ("X-Skipped-Rows" = usize, description = "Number of malformed rows left out of the page, present only when `STRICT_ROW_DECODING` is off and rows were skipped")
+        )),
+        (status = 400, description = "Unknown field requested, invalid ids or invalid pagination", body = ErrorResponse, example = json!(ErrorResponse::BadRequest(String::from("unknown field: foo")))),
         (status = 401, description = "Unauthorized to get users", body = ErrorResponse, example = json!(ErrorResponse::Unauthorized(String::from("Missing API Key")))),
     ),
     security(
-        (),
         ("api_key" = [])
     )
 )]
 #[get("/users")]
-pub(super) async fn get_users(query: Query<UserQuery>) -> impl Responder {
-    let db_url = env::var("DATABASE_URL").unwrap_or_else(|_| "not set".to_string());
-    let mut conn = match Connection::open(&db_url) {
+pub(super) async fn get_users(pool: Data<ReadPool>, query: Query<UserQuery>) -> impl Responder {
+    let fields = match parse_fields(query.fields.as_deref(), User::FIELDS) {
+        Ok(fields) => fields,
+        Err(e) => return HttpResponse::BadRequest().json(ErrorResponse::BadRequest(e)),
+    };
+    let page = match query.list.page(&[]) {
+        Ok(page) => page,
+        Err(e) => return HttpResponse::BadRequest().json(ErrorResponse::BadRequest(e)),
+    };
+
+    let mut conn = match pool.get() {
         Ok(conn) => conn,
         Err(e) => {
             error!("Error connecting to the database: {:?}", e);
@@ -62,23 +121,32 @@ pub(super) async fn get_users(query: Query<UserQuery>) -> impl Responder {
         }
     };
 
-    let limit = query.limit.unwrap_or(10) as i64;
-    let offset = query.offset.unwrap_or(0) as i64;
+    if let Some(ids) = query.ids.as_deref() {
+        return get_users_by_ids(&mut conn, ids, fields);
+    }
 
     let total_count = user::get_total_count(&mut conn).unwrap_or_else(|e| {
         error!("Error getting total count from the database: {:?}", e);
         0
     });
+    let snapshot_count = query.snapshot_count.unwrap_or(total_count);
 
-    match user::get_all(&mut conn, limit, offset) {
-        Ok(users) => {
-            let page = (offset / limit) + 1;
+    match user::get_page(&mut conn, page.limit, page.offset, config::strict_row_decoding()) {
+        Ok((users, skipped)) => {
             let pagination = PaginationUser {
-                page,
+                page: page.number(),
                 count: total_count,
+                snapshot_count,
                 items: users,
             };
-            HttpResponse::Ok().json(pagination)
+            let mut response = HttpResponse::Ok();
+            if snapshot_count != total_count {
+                response.insert_header((PAGINATION_DRIFT_HEADER, (total_count - snapshot_count).to_string()));
+            }
+            if skipped > 0 {
+                response.insert_header((SKIPPED_ROWS_HEADER, skipped.to_string()));
+            }
+            response.json(select_page(&pagination, &fields))
         }
         Err(e) => {
             error!("Error getting users from the database: {:?}", e);
@@ -89,6 +157,112 @@ pub(super) async fn get_users(query: Query<UserQuery>) -> impl Responder {
     }
 }
 
+/// Respond with the users matching a comma separated list of ids.
+fn get_users_by_ids(conn: &mut Connection, ids: &str, fields: Option<Vec<String>>) -> HttpResponse {
+    let ids = match ids
+        .split(',')
+        .map(str::trim)
+        .filter(|id| !id.is_empty())
+        .map(|id| id.parse::<UserId>().map_err(|_| format!("invalid id: {}", id)))
+        .collect::<Result<Vec<_>, _>>()
+    {
+        Ok(ids) => ids,
+        Err(e) => return HttpResponse::BadRequest().json(ErrorResponse::BadRequest(e)),
+    };
+    if ids.len() > MAX_BATCH_IDS {
+        return HttpResponse::BadRequest().json(ErrorResponse::BadRequest(format!(
+            "At most {} ids can be fetched at once",
+            MAX_BATCH_IDS
+        )));
+    }
+
+    match user::get_by_ids(conn, &ids) {
+        Ok(users) => {
+            let users: Vec<_> = users.iter().map(|user| select(user, &fields)).collect();
+            HttpResponse::Ok().json(users)
+        }
+        Err(e) => {
+            error!("Error getting users by id from the database: {:?}", e);
+            HttpResponse::InternalServerError().json(ErrorResponse::InternalError(
+                "Error getting users from the database".to_string(),
+            ))
+        }
+    }
+}
+
+/// Get employers with their number of open jobs.
+///
+/// This endpoint needs `api_key` authentication in order to call.
+///
+/// Return each employer once, ordered by id, with the number of its jobs that are open and whose deadline has not
+/// passed. When `has_open_jobs` is `true`, only employers with at least one open job are returned. Passwords are
+/// never included.
+#[utoipa::path(
+    context_path = "/v1",
+    tag = "users",
+    params(
+        ("has_open_jobs" = Option<bool>, Query, description = "Only return employers with at least one open job", example = true),
+        ("limit" = Option<i64>, Query, description = "Maximum number of employers to return, at most 100", example = 10),
+        ("offset" = Option<i64>, Query, description = "Offset for pagination", example = 0),
+        ("snapshot_count" = Option<i64>, Query, description = "`snapshot_count` returned by the first page, used to detect drift", example = 25),
+    ),
+    responses(
+        (status = 200, description = "Employers with pagination metadata", body = PaginationEmployer, headers(
+            ("X-Pagination-Drift" = i64, description = "Difference between the current total and `snapshot_count`, present only when they differ")
+        )),
+        (status = 400, description = "Invalid pagination", body = ErrorResponse, example = json!(ErrorResponse::BadRequest(String::from("limit must be at least 1")))),
+        (status = 401, description = "Unauthorized to get employers", body = ErrorResponse, example = json!(ErrorResponse::Unauthorized(String::from("Missing API Key")))),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    ),
+    security(
+        ("api_key" = [])
+    )
+)]
+#[get("/employers")]
+pub(super) async fn get_employers(pool: Data<ReadPool>, query: Query<EmployerQuery>) -> impl Responder {
+    let page = match query.list.page(&[]) {
+        Ok(page) => page,
+        Err(e) => return HttpResponse::BadRequest().json(ErrorResponse::BadRequest(e)),
+    };
+
+    let mut conn = match pool.get() {
+        Ok(conn) => conn,
+        Err(e) => {
+            error!("Error connecting to the database: {:?}", e);
+            return HttpResponse::InternalServerError().json(ErrorResponse::InternalError(
+                "Error connecting to the database".to_string(),
+            ));
+        }
+    };
+
+    let has_open_jobs = query.has_open_jobs.unwrap_or(false);
+
+    match user::get_employers(&mut conn, has_open_jobs, Utc::now(), page.limit, page.offset) {
+        Ok((employers, total_count)) => {
+            let snapshot_count = query.snapshot_count.unwrap_or(total_count);
+            let pagination = PaginationEmployer {
+                page: page.number(),
+                count: total_count,
+                snapshot_count,
+                items: employers,
+            };
+
+
+            let mut response = HttpResponse::Ok();
+            if snapshot_count != total_count {
+                response.insert_header((PAGINATION_DRIFT_HEADER, (total_count - snapshot_count).to_string()));
+            }
+            response.json(pagination)
+        }
+        Err(e) => {
+            error!("Error getting employers from the database: {:?}", e);
+            HttpResponse::InternalServerError().json(ErrorResponse::InternalError(
+                "Error getting employers from the database".to_string(),
+            ))
+        }
+    }
+}
+
 /// Get user by given user id.
 ///
 /// This endpoint needs `api_key` authentication in order to call.
@@ -98,26 +272,38 @@ pub(super) async fn get_users(query: Query<UserQuery>) -> impl Responder {
     context_path = "/v1",
     tag = "users",
     params(
-        ("id", description = "Unique ID of the user", example = 1)
+        ("id", description = "Unique ID of the user", example = 1),
+        ("fields" = Option<String>, Query, description = "Comma separated list of fields to return")
     ),
     responses(
         (status = 200, description = "User found", body = User),
+        (status = 400, description = "Unknown field requested", body = ErrorResponse, example = json!(ErrorResponse::BadRequest(String::from("unknown field: foo")))),
         (status = 401, description = "Unauthorized to get user", body = ErrorResponse, example = json!(ErrorResponse::Unauthorized(String::from("missing api key")))),
         (status = 404, description = "User not found", body = ErrorResponse, example = json!(ErrorResponse::NotFound(String::from("id = 1"))))
     ),
     security(
-        (),
         ("api_key" = [])
     )
 )]
 #[get("/users/{id}")]
-pub(super) async fn get_user_by_id(id: Path<i64>) -> impl Responder {
+pub(super) async fn get_user_by_id(pool: Data<ReadPool>, id: Path<UserId>, query: Query<FieldsQuery>) -> impl Responder {
     let id = id.into_inner();
-    let db_url = env::var("DATABASE_URL").unwrap_or_else(|_| "not set".to_string());
-    let mut conn = Connection::open(&db_url).unwrap();
+    let fields = match parse_fields(query.fields.as_deref(), User::FIELDS) {
+        Ok(fields) => fields,
+        Err(e) => return HttpResponse::BadRequest().json(ErrorResponse::BadRequest(e)),
+    };
+    let mut conn = match pool.get() {
+        Ok(conn) => conn,
+        Err(e) => {
+            error!("Error connecting to the database: {:?}", e);
+            return HttpResponse::InternalServerError().json(ErrorResponse::InternalError(
+                "Error connecting to the database".to_string(),
+            ));
+        }
+    };
 
     if let Ok(Some(user)) = user::get_by_id(&mut conn, id) {
-        HttpResponse::Ok().json(user)
+        HttpResponse::Ok().json(select(&user, &fields))
     } else {
         HttpResponse::NotFound().body(format!("User with ID {} not found", id))
     }
@@ -127,7 +313,8 @@ pub(super) async fn get_user_by_id(id: Path<i64>) -> impl Responder {
 ///
 /// This endpoint needs `api_key` authentication in order to call.
 ///
-/// Create a new `User` in the database.
+/// Create a new `User` in the database and return it with the id the database assigned. The password is never
+/// included in the response.
 #[utoipa::path(
     request_body = User,
     context_path = "/v1",
@@ -135,18 +322,16 @@ pub(super) async fn get_user_by_id(id: Path<i64>) -> impl Responder {
     responses(
         (status = 201, description = "User created successfully", body = User),
         (status = 401, description = "Unauthorized to create user", body = ErrorResponse, example = json!(ErrorResponse::Unauthorized(String::from("missing api key")))),
-        (status = 400, description = "Invalid user data", body = ErrorResponse, example = json!(ErrorResponse::BadRequest(String::from("Invalid user data"))))
+        (status = 400, description = "Malformed user data", body = ErrorResponse, example = json!(ErrorResponse::BadRequest(String::from("Json deserialize error: missing field `email`")))),
+        (status = 422, description = "Invalid user data", body = ErrorResponse, example = json!(ErrorResponse::UnprocessableEntity(String::from("phone must contain 7 to 15 digits and only +, spaces, dashes, dots or parentheses"))))
     ),
     security(
-        (),
         ("api_key" = [])
     )
 )]
 #[post("/users")]
-pub(super) async fn create_user(user: Json<UserUpdateRequest>) -> impl Responder {
-    let db_url = env::var("DATABASE_URL").unwrap_or_else(|_| "not set".to_string());
-    info!("DATABASE_URL = {:?}", db_url);
-    let mut conn = match Connection::open(&db_url) {
+pub(super) async fn create_user(pool: Data<Pool>, user: Json<UserUpdateRequest>) -> impl Responder {
+    let mut conn = match pool.get() {
         Ok(conn) => conn,
         Err(e) => {
             error!("Error connecting to the database: {:?}", e);
@@ -158,10 +343,35 @@ pub(super) async fn create_user(user: Json<UserUpdateRequest>) -> impl Responder
 
     let user = user.into_inner();
 
-    match user::create(&mut conn, user.clone()) {
-        Ok(_) => {
-            info!("User created successfully: {:?}", user);
-            HttpResponse::Created().json(user)
+    if let Err(errors) = user.validate() {
+        let message = errors
+            .iter()
+            .map(|e| e.message.clone())
+            .collect::<Vec<_>>()
+            .join("; ");
+        return HttpResponse::UnprocessableEntity().json(ErrorResponse::UnprocessableEntity(message));
+    }
+
+    let mut user = user;
+    if let Err(e) = hash_password(&mut user) {
+        error!("Error hashing the password: {:?}", e);
+        return HttpResponse::InternalServerError().json(ErrorResponse::InternalError(
+            "Error creating user".to_string(),
+        ));
+    }
+
+    // Respond with the stored user so the id is the one the database assigned.
+    match user::create(&mut conn, user).and_then(|id| user::get_by_id(&mut conn, id)) {
+        Ok(Some(user)) => {
+            info!("User created successfully: {}", user.id);
+            let fields = Some(User::FIELDS.iter().map(|field| field.to_string()).collect());
+            HttpResponse::Created().json(select(&user, &fields))
+        }
+        Ok(None) => {
+            error!("Created user could not be read back");
+            HttpResponse::InternalServerError().json(ErrorResponse::InternalError(
+                "Error creating user".to_string(),
+            ))
         }
         Err(e) => {
             error!("Error creating user: {:?}", e);
@@ -187,21 +397,32 @@ context_path = "/v1",
     responses(
         (status = 200, description = "User updated successfully", body = User),
         (status = 401, description = "Unauthorized to update user", body = ErrorResponse, example = json!(ErrorResponse::Unauthorized(String::from("missing api key")))),
-        (status = 404, description = "User not found", body = ErrorResponse, example = json!(ErrorResponse::NotFound(String::from("id = 1"))))
+        (status = 400, description = "Malformed user update data", body = ErrorResponse, example = json!(ErrorResponse::BadRequest(String::from("Json deserialize error: invalid type: integer `1`, expected a string")))),
+        (status = 422, description = "Invalid user update data", body = ErrorResponse, example = json!(ErrorResponse::UnprocessableEntity(String::from("avatar_url must be an absolute http(s) URL")))),
+        (status = 404, description = "User not found", body = ErrorResponse, example = json!(ErrorResponse::NotFound(String::from("id = 1")))),
+        (status = 409, description = "Email already used by another user", body = ErrorResponse, example = json!(ErrorResponse::AlreadyExists(String::from("A user with this email already exists"))))
     ),
     security(
-        (),
         ("api_key" = [])
     )
 )]
 #[put("/users/{id}")]
 pub(super) async fn update_user(
-    id: Path<i64>,
+    pool: Data<Pool>,
+    id: Path<UserId>,
     user_update_request: Json<UserUpdateRequest>,
 ) -> impl Responder {
     let id = id.into_inner();
-    let db_url = env::var("DATABASE_URL").unwrap_or_else(|_| "not set".to_string());
-    let mut conn = match Connection::open(&db_url) {
+    if let Err(errors) = user_update_request.validate() {
+        let message = errors
+            .iter()
+            .map(|e| e.message.clone())
+            .collect::<Vec<_>>()
+            .join("; ");
+        return HttpResponse::UnprocessableEntity().json(ErrorResponse::UnprocessableEntity(message));
+    }
+
+    let mut conn = match pool.get() {
         Ok(conn) => conn,
         Err(e) => {
             error!("Error connecting to the database: {:?}", e);
@@ -221,15 +442,26 @@ pub(super) async fn update_user(
         }
     };
 
+    let new_password = match user_update_request.password.as_deref().map(password::hash).transpose() {
+        Ok(new_password) => new_password,
+        Err(e) => {
+            error!("Error hashing the password: {:?}", e);
+            return HttpResponse::InternalServerError().finish();
+        }
+    };
+
     // Create a new user with updated fields
     let updated_user = User {
         id: existing_user.id,
         name: user_update_request.name.clone().unwrap_or(existing_user.name),
         email: user_update_request.email.clone().unwrap_or(existing_user.email),
-        password: user_update_request.password.clone().unwrap_or(existing_user.password),
+        password: new_password.unwrap_or(existing_user.password),
         role: user_update_request.role.clone().unwrap_or(existing_user.role),
         created_at: Default::default(),
         updated_at: Default::default(),
+        bio: user_update_request.bio.clone().or(existing_user.bio),
+        avatar_url: user_update_request.avatar_url.clone().or(existing_user.avatar_url),
+        phone: user_update_request.phone.clone().or(existing_user.phone),
     };
 
     // Call the update function
@@ -238,6 +470,11 @@ pub(super) async fn update_user(
             info!("Updated user...");
             HttpResponse::Ok().finish()
         }
+        Err(e) if is_unique_violation(e.as_ref(), "users.email") => {
+            HttpResponse::Conflict().json(ErrorResponse::AlreadyExists(
+                "A user with this email already exists".to_string(),
+            ))
+        }
         Err(e) => {
             eprintln!("Error updating user: {:?}", e);
             HttpResponse::InternalServerError().finish()
@@ -245,6 +482,455 @@ pub(super) async fn update_user(
     }
 }
 
+/// Create or update a user by email.
+///
+/// This endpoint needs `api_key` authentication in order to call.
+///
+/// Idempotently provision the `User` with the given email, e.g. on first login through an external identity provider.
+/// If no user has the email it is created from the body, which must then include `name` and `password`, and 201 is
+/// returned. Otherwise the fields the body sets are updated and 200 is returned. The body may leave out `email`, and
+/// must match the path when it includes it. The password is never included in the response.
+#[utoipa::path(
+    context_path = "/v1",
+    tag = "users",
+    params(
+        ("email" = String, Path, description = "Email address of the user", example = "jane.doe@example.com")
+    ),
+    request_body = UserUpdateRequest,
+    responses(
+        (status = 200, description = "Existing user updated", body = User),
+        (status = 201, description = "User created", body = User),
+        (status = 400, description = "Malformed user data", body = ErrorResponse, example = json!(ErrorResponse::BadRequest(String::from("Json deserialize error: invalid type: integer `1`, expected a string")))),
+        (status = 401, description = "Unauthorized to provision user", body = ErrorResponse, example = json!(ErrorResponse::Unauthorized(String::from("missing api key")))),
+        (status = 422, description = "Invalid email or user data", body = ErrorResponse, example = json!(ErrorResponse::UnprocessableEntity(String::from("name and password are required to create a user")))),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    ),
+    security(
+        ("api_key" = [])
+    )
+)]
+#[put("/users/by-email/{email}")]
+pub(super) async fn ensure_user(pool: Data<Pool>, email: Path<String>, request: Json<UserUpdateRequest>) -> impl Responder {
+    let email = email.into_inner();
+    let mut request = request.into_inner();
+    if !is_valid_email(&email) {
+        return HttpResponse::UnprocessableEntity().json(ErrorResponse::UnprocessableEntity(format!(
+            "invalid email: {}",
+            email
+        )));
+    }
+    if request.email.as_ref().is_some_and(|body_email| *body_email != email) {
+        return HttpResponse::UnprocessableEntity().json(ErrorResponse::UnprocessableEntity(
+            "email in the body must match the path".to_string(),
+        ));
+    }
+    if let Err(errors) = request.validate() {
+        let message = errors
+            .iter()
+            .map(|e| e.message.clone())
+            .collect::<Vec<_>>()
+            .join("; ");
+        return HttpResponse::UnprocessableEntity().json(ErrorResponse::UnprocessableEntity(message));
+    }
+
+    let mut conn = match pool.get() {
+        Ok(conn) => conn,
+        Err(e) => {
+            error!("Error connecting to the database: {:?}", e);
+            return HttpResponse::InternalServerError().json(ErrorResponse::InternalError(
+                "Error connecting to the database".to_string(),
+            ));
+        }
+    };
+
+    if let Err(e) = hash_password(&mut request) {
+        error!("Error hashing the password: {:?}", e);
+        return HttpResponse::InternalServerError().json(ErrorResponse::InternalError(
+            "Error provisioning user".to_string(),
+        ));
+    }
+
+    let fields = Some(User::FIELDS.iter().map(|field| field.to_string()).collect());
+    match user::upsert_by_email(&mut conn, &email, request) {
+        Ok((user, true)) => {
+            info!("Provisioned user {} with email {}", user.id, email);
+            HttpResponse::Created().json(select(&user, &fields))
+        }
+        Ok((user, false)) => {
+            info!("Updated user {} with email {}", user.id, email);
+            HttpResponse::Ok().json(select(&user, &fields))
+        }
+        Err(e) if is_not_null_violation(e.as_ref()) => HttpResponse::UnprocessableEntity().json(
+            ErrorResponse::UnprocessableEntity("name and password are required to create a user".to_string()),
+        ),
+        Err(e) => {
+            error!("Error provisioning user with email {}: {:?}", email, e);
+            HttpResponse::InternalServerError().json(ErrorResponse::InternalError(
+                "Error provisioning user".to_string(),
+            ))
+        }
+    }
+}
+
+/// Get how complete a user's profile is.
+///
+/// This endpoint needs `api_key` authentication in order to call.
+///
+/// Return a score from 0 to 100 for how many of the optional profile fields `bio`, `avatar_url` and `phone` are filled
+/// in, along with the ones still missing. Blank fields count as missing. Return 404 not found if no user with the
+/// given id exists.
+#[utoipa::path(
+    context_path = "/v1",
+    tag = "users",
+    params(
+        ("id", description = "Unique ID of the user", example = 2)
+    ),
+    responses(
+        (status = 200, description = "Profile completeness of the user", body = ProfileCompleteness),
+        (status = 401, description = "Unauthorized to get profile completeness", body = ErrorResponse, example = json!(ErrorResponse::Unauthorized(String::from("missing api key")))),
+        (status = 404, description = "User not found", body = ErrorResponse, example = json!(ErrorResponse::NotFound(String::from("User with ID 2 not found")))),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    ),
+    security(
+        ("api_key" = [])
+    )
+)]
+#[get("/users/{id}/completeness")]
+pub(super) async fn get_completeness(pool: Data<ReadPool>, id: Path<UserId>) -> impl Responder {
+    let id = id.into_inner();
+    let mut conn = match pool.get() {
+        Ok(conn) => conn,
+        Err(e) => {
+            error!("Error connecting to the database: {:?}", e);
+            return HttpResponse::InternalServerError().json(ErrorResponse::InternalError(
+                "Error connecting to the database".to_string(),
+            ));
+        }
+    };
+
+    match user::get_by_id(&mut conn, id) {
+        Ok(Some(user)) => HttpResponse::Ok().json(user.completeness()),
+        Ok(None) => HttpResponse::NotFound().json(ErrorResponse::NotFound(format!("User with ID {} not found", id))),
+        Err(e) => {
+            error!("Error getting user with ID {}: {:?}", id, e);
+            HttpResponse::InternalServerError().json(ErrorResponse::InternalError(
+                "Error getting user".to_string(),
+            ))
+        }
+    }
+}
+
+/// Get a job seeker's job preferences.
+///
+/// This endpoint needs `api_key` authentication in order to call.
+///
+/// Return the employment types, locations and salary floor the seeker prefers. A seeker who never saved preferences
+/// gets empty ones with `updated_at` set to `null`. Intended for the seeker themselves. Return 404 not found if no job
+/// seeker with the given id exists.
+#[utoipa::path(
+    context_path = "/v1",
+    tag = "users",
+    params(
+        ("id", description = "Unique ID of the job seeker", example = 2)
+    ),
+    responses(
+        (status = 200, description = "Job preferences of the seeker", body = JobPreferences),
+        (status = 401, description = "Unauthorized to get job preferences", body = ErrorResponse, example = json!(ErrorResponse::Unauthorized(String::from("missing api key")))),
+        (status = 404, description = "Job seeker not found", body = ErrorResponse, example = json!(ErrorResponse::NotFound(String::from("Job seeker with ID 2 not found")))),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    ),
+    security(
+        ("api_key" = [])
+    )
+)]
+#[get("/users/{id}/preferences")]
+pub(super) async fn get_preferences(pool: Data<ReadPool>, id: Path<UserId>) -> impl Responder {
+    let id = id.into_inner();
+    let mut conn = match pool.get() {
+        Ok(conn) => conn,
+        Err(e) => {
+            error!("Error connecting to the database: {:?}", e);
+            return HttpResponse::InternalServerError().json(ErrorResponse::InternalError(
+                "Error connecting to the database".to_string(),
+            ));
+        }
+    };
+
+    if let Some(response) = job_seeker_not_found(&mut conn, id) {
+        return response;
+    }
+
+    match preferences::get(&mut conn, id) {
+        Ok(Some(preferences)) => HttpResponse::Ok().json(preferences),
+        Ok(None) => HttpResponse::Ok().json(JobPreferences {
+            user_id: id,
+            employment_types: Vec::new(),
+            locations: Vec::new(),
+            min_salary: None,
+            updated_at: None,
+        }),
+        Err(e) => {
+            error!("Error getting job preferences of job seeker {}: {:?}", id, e);
+            HttpResponse::InternalServerError().json(ErrorResponse::InternalError(
+                "Error getting job preferences".to_string(),
+            ))
+        }
+    }
+}
+
+/// Save a job seeker's job preferences.
+///
+/// This endpoint needs `api_key` authentication in order to call.
+///
+/// Replace the employment types, locations and salary floor the seeker prefers. They tailor
+/// `GET /v1/users/{id}/recommended-jobs`: matching jobs rank higher and jobs paying less than `min_salary` are left
+/// out. Intended for the seeker themselves. Return 404 not found if no job seeker with the given id exists.
+#[utoipa::path(
+    context_path = "/v1",
+    tag = "users",
+    params(
+        ("id", description = "Unique ID of the job seeker", example = 2)
+    ),
+    request_body = JobPreferencesRequest,
+    responses(
+        (status = 200, description = "Job preferences saved", body = JobPreferences),
+        (status = 400, description = "Malformed job preferences", body = ErrorResponse, example = json!(ErrorResponse::BadRequest(String::from("Json deserialize error: unknown employment_type \"remote\", expected one of full_time, part_time, contract")))),
+        (status = 401, description = "Unauthorized to save job preferences", body = ErrorResponse, example = json!(ErrorResponse::Unauthorized(String::from("missing api key")))),
+        (status = 404, description = "Job seeker not found", body = ErrorResponse, example = json!(ErrorResponse::NotFound(String::from("Job seeker with ID 2 not found")))),
+        (status = 422, description = "Invalid job preferences", body = ErrorResponse, example = json!(ErrorResponse::UnprocessableEntity(String::from("min_salary must not be negative")))),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    ),
+    security(
+        ("api_key" = [])
+    )
+)]
+#[put("/users/{id}/preferences")]
+pub(super) async fn set_preferences(pool: Data<Pool>, id: Path<UserId>, request: Json<JobPreferencesRequest>) -> impl Responder {
+    let id = id.into_inner();
+    let request = request.into_inner();
+    if let Err(errors) = request.validate() {
+        let message = errors
+            .iter()
+            .map(|e| e.message.clone())
+            .collect::<Vec<_>>()
+            .join("; ");
+        return HttpResponse::UnprocessableEntity().json(ErrorResponse::UnprocessableEntity(message));
+    }
+
+    let mut conn = match pool.get() {
+        Ok(conn) => conn,
+        Err(e) => {
+            error!("Error connecting to the database: {:?}", e);
+            return HttpResponse::InternalServerError().json(ErrorResponse::InternalError(
+                "Error connecting to the database".to_string(),
+            ));
+        }
+    };
+
+    if let Some(response) = job_seeker_not_found(&mut conn, id) {
+        return response;
+    }
+
+    match preferences::set(&mut conn, id, request) {
+        Ok(preferences) => {
+            info!("Saved job preferences of job seeker {}", id);
+            HttpResponse::Ok().json(preferences)
+        }
+        Err(e) => {
+            error!("Error saving job preferences of job seeker {}: {:?}", id, e);
+            HttpResponse::InternalServerError().json(ErrorResponse::InternalError(
+                "Error saving job preferences".to_string(),
+            ))
+        }
+    }
+}
+
+/// Get a job seeker's activity feed.
+///
+/// This endpoint needs `api_key` authentication in order to call.
+///
+/// Return the applications the seeker submitted and the status changes of those applications as one feed, newest
+/// first, each event with a `type` of `application_submitted` or `status_changed`. Notifications are not stored yet,
+/// so they are not part of the feed. Pass `next_cursor` as `cursor` to get the next page; it is absent on the last
+/// page. Intended for the seeker themselves. Return 404 not found if no job seeker with the given id exists.
+#[utoipa::path(
+    context_path = "/v1",
+    tag = "users",
+    params(
+        ("id", description = "Unique ID of the job seeker", example = 2),
+        ("limit" = Option<i64>, Query, description = "Maximum number of events to return, at most 100", example = 10),
+        ("cursor" = Option<String>, Query, description = "`next_cursor` of the previous page", example = "status_changed:7")
+    ),
+    responses(
+        (status = 200, description = "Activity of the job seeker, newest first", body = PaginationActivity),
+        (status = 400, description = "Invalid limit or cursor", body = ErrorResponse, example = json!(ErrorResponse::BadRequest(String::from("invalid cursor: foo")))),
+        (status = 401, description = "Unauthorized to get activity", body = ErrorResponse, example = json!(ErrorResponse::Unauthorized(String::from("missing api key")))),
+        (status = 404, description = "Job seeker not found", body = ErrorResponse, example = json!(ErrorResponse::NotFound(String::from("Job seeker with ID 2 not found")))),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    ),
+    security(
+        ("api_key" = [])
+    )
+)]
+#[get("/users/{id}/activity")]
+pub(super) async fn get_activity(pool: Data<ReadPool>, id: Path<UserId>, query: Query<ActivityQuery>) -> impl Responder {
+    let id = id.into_inner();
+    let limit = query.limit.unwrap_or(DEFAULT_LIMIT);
+    if limit < 1 {
+        return HttpResponse::BadRequest().json(ErrorResponse::BadRequest("limit must be at least 1".to_string()));
+    }
+    let limit = limit.min(MAX_LIMIT);
+    let after = match query.cursor.as_deref() {
+        None => None,
+        Some(cursor) => match cursor
+            .split_once(':')
+            .and_then(|(kind, event_id)| Some((ActivityKind::parse(kind)?, event_id.parse::<i64>().ok()?)))
+        {
+            Some(after) => Some(after),
+            None => {
+                return HttpResponse::BadRequest().json(ErrorResponse::BadRequest(format!("invalid cursor: {}", cursor)));
+            }
+        },
+    };
+
+    let mut conn = match pool.get() {
+        Ok(conn) => conn,
+        Err(e) => {
+            error!("Error connecting to the database: {:?}", e);
+            return HttpResponse::InternalServerError().json(ErrorResponse::InternalError(
+                "Error connecting to the database".to_string(),
+            ));
+        }
+    };
+
+    if let Some(response) = job_seeker_not_found(&mut conn, id) {
+        return response;
+    }
+
+    // One extra event tells whether there is a next page.
+    match user::get_activity(&mut conn, id, after, limit + 1) {
+        Ok(mut items) => {
+            let next_cursor = if items.len() as i64 > limit {
+                items.truncate(limit as usize);
+                items.last().map(|event| event.cursor())
+            } else {
+                None
+            };
+            HttpResponse::Ok().json(PaginationActivity { limit, next_cursor, items })
+        }
+        Err(e) => {
+            error!("Error getting activity of job seeker {}: {:?}", id, e);
+            HttpResponse::InternalServerError().json(ErrorResponse::InternalError(
+                "Error getting activity".to_string(),
+            ))
+        }
+    }
+}
+
+/// Get a job seeker's application status for several jobs at once.
+///
+/// This endpoint needs `api_key` authentication in order to call.
+///
+/// Return an object mapping each of `job_ids` to the status of the seeker's application to that job, or
+/// `not_applied`, e.g. to mark jobs on a job board. A job applied to more than once reports its latest application.
+/// Unknown job ids are reported as `not_applied` and at most 100 ids are accepted. Intended for the seeker themselves.
+/// Return 404 not found if no job seeker with the given id exists.
+#[utoipa::path(
+    context_path = "/v1",
+    tag = "users",
+    params(
+        ("id", description = "Unique ID of the job seeker", example = 2),
+        ("job_ids" = String, Query, description = "Comma separated list of job ids", example = "1,2,3")
+    ),
+    responses(
+        (status = 200, description = "Application status by job id", body = BTreeMap<String, String>, example = json!({"1": "pending", "2": "not_applied", "3": "accepted"})),
+        (status = 400, description = "Invalid or too many job ids", body = ErrorResponse, example = json!(ErrorResponse::BadRequest(String::from("invalid job id: foo")))),
+        (status = 401, description = "Unauthorized to get application statuses", body = ErrorResponse, example = json!(ErrorResponse::Unauthorized(String::from("missing api key")))),
+        (status = 404, description = "Job seeker not found", body = ErrorResponse, example = json!(ErrorResponse::NotFound(String::from("Job seeker with ID 2 not found")))),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    ),
+    security(
+        ("api_key" = [])
+    )
+)]
+#[get("/users/{id}/application-status")]
+pub(super) async fn get_application_statuses(
+    pool: Data<ReadPool>,
+    id: Path<UserId>,
+    query: Query<ApplicationStatusQuery>,
+) -> impl Responder {
+    let id = id.into_inner();
+    let mut job_ids: Vec<JobId> = Vec::new();
+    for job_id in query.job_ids.split(',').map(str::trim).filter(|job_id| !job_id.is_empty()) {
+        match job_id.parse() {
+            Ok(job_id) if !job_ids.contains(&job_id) => job_ids.push(job_id),
+            Ok(_) => {}
+            Err(_) => {
+                return HttpResponse::BadRequest().json(ErrorResponse::BadRequest(format!("invalid job id: {}", job_id)));
+            }
+        }
+    }
+    if job_ids.len() > MAX_BATCH_IDS {
+        return HttpResponse::BadRequest().json(ErrorResponse::BadRequest(format!(
+            "At most {} job ids can be looked up at once",
+            MAX_BATCH_IDS
+        )));
+    }
+
+    let mut conn = match pool.get() {
+        Ok(conn) => conn,
+        Err(e) => {
+            error!("Error connecting to the database: {:?}", e);
+            return HttpResponse::InternalServerError().json(ErrorResponse::InternalError(
+                "Error connecting to the database".to_string(),
+            ));
+        }
+    };
+
+    if let Some(response) = job_seeker_not_found(&mut conn, id) {
+        return response;
+    }
+
+    match application::get_statuses_for_jobs(&mut conn, id, &job_ids) {
+        Ok(applied) => {
+            let mut statuses: BTreeMap<JobId, String> =
+                job_ids.iter().map(|&job_id| (job_id, NOT_APPLIED.to_string())).collect();
+            for (job_id, status) in applied {
+                statuses.insert(job_id, status.to_string());
+            }
+            HttpResponse::Ok().json(statuses)
+        }
+        Err(e) => {
+            error!("Error getting application statuses of job seeker {}: {:?}", id, e);
+            HttpResponse::InternalServerError().json(ErrorResponse::InternalError(
+                "Error getting application statuses".to_string(),
+            ))
+        }
+    }
+}
+
+/// Respond with 404 if no job seeker with the id exists, or 500 if the user could not be looked up.
+fn job_seeker_not_found(conn: &mut Connection, id: UserId) -> Option<HttpResponse> {
+    match user::get_by_id(conn, id) {
+        Ok(Some(user)) if matches!(user.role, UserRole::JobSeeker) => None,
+        Ok(_) => Some(HttpResponse::NotFound().json(ErrorResponse::NotFound(format!("Job seeker with ID {} not found", id)))),
+        Err(e) => {
+            error!("Error getting job seeker {}: {:?}", id, e);
+            Some(HttpResponse::InternalServerError().json(ErrorResponse::InternalError(
+                "Error getting job seeker".to_string(),
+            )))
+        }
+    }
+}
+
+/// Replace the password `request` sets, if any, with its hash so the plain text is never stored.
+fn hash_password(request: &mut UserUpdateRequest) -> Result<(), argon2::password_hash::Error> {
+    if let Some(plain) = request.password.as_mut() {
+        *plain = password::hash(plain)?;
+    }
+    Ok(())
+}
+
 /// Delete a user by id.
 ///
 /// This endpoint needs `api_key` authentication in order to call.
@@ -262,15 +948,21 @@ pub(super) async fn update_user(
         (status = 404, description = "User not found", body = ErrorResponse, example = json!(ErrorResponse::NotFound(String::from("id = 1"))))
     ),
     security(
-        (),
         ("api_key" = [])
     )
 )]
 #[delete("/users/{id}")]
-pub(super) async fn delete_user(id: Path<i32>) -> impl Responder {
-    let id = id.into_inner() as i64;
-    let db_url = env::var("DATABASE_URL").unwrap_or_else(|_| "not set".to_string());
-    let mut conn = Connection::open(&db_url).unwrap();
+pub(super) async fn delete_user(pool: Data<Pool>, id: Path<UserId>) -> impl Responder {
+    let id = id.into_inner();
+    let mut conn = match pool.get() {
+        Ok(conn) => conn,
+        Err(e) => {
+            error!("Error connecting to the database: {:?}", e);
+            return HttpResponse::InternalServerError().json(ErrorResponse::InternalError(
+                "Error connecting to the database".to_string(),
+            ));
+        }
+    };
 
     match user::delete(&mut conn, id) {
         Ok(_) => HttpResponse::Ok().finish(),
@@ -279,4 +971,157 @@ pub(super) async fn delete_user(id: Path<i32>) -> impl Responder {
             HttpResponse::InternalServerError().finish()
         }
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use actix_web::{test, App};
+    use actix_web::http::StatusCode;
+    use actix_web::web::Data;
+    use serde_json::{json, Value};
+    use crate::models::UserStore;
+    use crate::utils::testing::TestDb;
+    use super::configure;
+
+    #[actix_web::test]
+    async fn responses_never_include_passwords() {
+        let db = TestDb::new();
+        let employer = db.user("employer@example.com", "employer", "$argon2id$v=19$m=19456,t=2,p=1$c2FsdA$aGFzaA");
+        let app = test::init_service(
+            App::new()
+                .app_data(db.pool())
+                .app_data(db.read_pool())
+                .configure(configure(Data::new(UserStore::default()))),
+        )
+        .await;
+
+        let list: Value = test::call_and_read_body_json(&app, test::TestRequest::get().uri("/users").to_request()).await;
+        let user = &list["items"][0];
+        assert_eq!(user["email"], "employer@example.com");
+        assert!(user.get("password").is_none());
+
+        let detail: Value =
+            test::call_and_read_body_json(&app, test::TestRequest::get().uri(&format!("/users/{}", employer)).to_request()).await;
+        assert_eq!(detail["id"], employer);
+        assert!(detail.get("password").is_none());
+
+        let employers: Value = test::call_and_read_body_json(&app, test::TestRequest::get().uri("/employers").to_request()).await;
+        assert_eq!(employers["items"][0]["user"]["id"], employer);
+        assert!(employers["items"][0]["user"].get("password").is_none());
+    }
+
+    #[actix_web::test]
+    async fn completeness_scores_the_optional_profile_fields() {
+        let db = TestDb::new();
+        let empty = db.user("empty@example.com", "job_seeker", "!");
+        let full = db.user("full@example.com", "job_seeker", "!");
+        db.conn()
+            .execute(
+                "UPDATE users SET bio = 'Rustacean', avatar_url = 'https://example.com/a.png', phone = '+46 70 123 45 67'
+                 WHERE id = ?1",
+                [full],
+            )
+            .unwrap();
+        let app = test::init_service(
+            App::new()
+                .app_data(db.pool())
+                .app_data(db.read_pool())
+                .configure(configure(Data::new(UserStore::default()))),
+        )
+        .await;
+
+        let completeness = |id: i64| test::TestRequest::get().uri(&format!("/users/{}/completeness", id)).to_request();
+        let body: Value = test::call_and_read_body_json(&app, completeness(empty)).await;
+        assert_eq!(body, json!({"user_id": empty, "score": 0, "missing": ["bio", "avatar_url", "phone"]}));
+        let body: Value = test::call_and_read_body_json(&app, completeness(full)).await;
+        assert_eq!(body, json!({"user_id": full, "score": 100, "missing": []}));
+        let response = test::call_service(&app, completeness(full + 1)).await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[actix_web::test]
+    async fn updating_to_a_taken_email_conflicts_and_changes_nothing() {
+        let db = TestDb::new();
+        let a = db.user("a@example.com", "job_seeker", "!");
+        db.user("b@example.com", "job_seeker", "!");
+        let app = test::init_service(
+            App::new()
+                .app_data(db.pool())
+                .app_data(db.read_pool())
+                .configure(configure(Data::new(UserStore::default()))),
+        )
+        .await;
+
+        let update = test::TestRequest::put()
+            .uri(&format!("/users/{}", a))
+            .set_json(json!({"name": "Renamed", "email": "b@example.com"}));
+        let response = test::call_service(&app, update.to_request()).await;
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+        let body: Value = test::read_body_json(response).await;
+        assert_eq!(body, json!({"AlreadyExists": "A user with this email already exists"}));
+
+        let user: Value =
+            test::call_and_read_body_json(&app, test::TestRequest::get().uri(&format!("/users/{}", a)).to_request()).await;
+        assert_eq!(user["email"], "a@example.com");
+        assert_ne!(user["name"], "Renamed");
+    }
+
+    #[actix_web::test]
+    async fn ensure_user_creates_then_updates_by_email() {
+        let db = TestDb::new();
+        let app = test::init_service(
+            App::new()
+                .app_data(db.pool())
+                .app_data(db.read_pool())
+                .configure(configure(Data::new(UserStore::default()))),
+        )
+        .await;
+        let ensure = |email: &str, body: Value| {
+            test::TestRequest::put().uri(&format!("/users/by-email/{}", email)).set_json(body).to_request()
+        };
+
+        let response = test::call_service(&app, ensure("jane@example.com", json!({"name": "Jane"}))).await;
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        let response = test::call_service(&app, ensure("not-an-email", json!({"name": "Jane", "password": "secret"}))).await;
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+        let body = json!({"name": "Jane", "password": "secret", "role": "job_seeker"});
+        let response = test::call_service(&app, ensure("jane@example.com", body)).await;
+        assert_eq!(response.status(), StatusCode::CREATED);
+        let created: Value = test::read_body_json(response).await;
+        assert_eq!(created["email"], "jane@example.com");
+
+        let response = test::call_service(&app, ensure("jane@example.com", json!({"name": "Jane Doe"}))).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let updated: Value = test::read_body_json(response).await;
+        assert_eq!(updated["id"], created["id"]);
+        assert_eq!(updated["name"], "Jane Doe");
+        assert!(updated.get("password").is_none());
+    }
+
+    #[actix_web::test]
+    async fn created_users_get_the_database_assigned_id() {
+        let db = TestDb::new();
+        let app = test::init_service(
+            App::new()
+                .app_data(db.pool())
+                .app_data(db.read_pool())
+                .configure(configure(Data::new(UserStore::default()))),
+        )
+        .await;
+
+        let mut ids = Vec::new();
+        for email in ["jane@example.com", "john@example.com"] {
+            let user = json!({"id": 0, "name": "Jane", "email": email, "password": "secret", "role": "job_seeker"});
+            let created: Value =
+                test::call_and_read_body_json(&app, test::TestRequest::post().uri("/users").set_json(user).to_request()).await;
+            let stored: Value = test::call_and_read_body_json(
+                &app,
+                test::TestRequest::get().uri(&format!("/users/{}", created["id"])).to_request(),
+            )
+            .await;
+            assert_eq!(stored["email"], email);
+            ids.push(created["id"].as_i64().unwrap());
+        }
+        assert!(ids[0] > 0 && ids[0] != ids[1], "{:?}", ids);
+    }
+}
@@ -0,0 +1,86 @@
+use std::error::Error;
+use std::time::Duration;
+use chrono::{DateTime, Utc};
+use log::{error, info};
+use crate::db::{application, Pool};
+use crate::utils::background::BackgroundTasks;
+use crate::utils::readiness::Readiness;
+
+/// Seconds between checks for the database while it is still being initialized.
+const NOT_READY_RETRY_SECS: u64 = 1;
+
+/// Start the auto-reject pass in `tasks`, running it every `interval_secs` seconds once `readiness` is marked ready.
+///
+/// Each pass rejects the applications still pending on jobs whose `auto_reject_after` has passed. The task returns
+/// between two passes when `tasks` shuts down.
+pub fn start(tasks: &BackgroundTasks, pool: Pool, readiness: Readiness, interval_secs: u64) {
+    let mut stop = tasks.stop_signal();
+    tasks.spawn("auto_reject", async move {
+        loop {
+            let delay = if readiness.is_ready() {
+                if let Err(e) = run(&pool, Utc::now()) {
+                    error!("Failed to auto-reject applications: {}", e);
+                }
+                interval_secs
+            } else {
+                NOT_READY_RETRY_SECS
+            };
+            if !stop.sleep(Duration::from_secs(delay)).await {
+                break;
+            }
+        }
+    });
+    info!("Auto-reject enabled, running every {} seconds", interval_secs);
+}
+
+/// Reject the pending applications past their job's `auto_reject_after` at `now`, returning how many were rejected.
+fn run(pool: &Pool, now: DateTime<Utc>) -> Result<usize, Box<dyn Error>> {
+    let mut conn = pool.get()?;
+    let rejected = application::auto_reject(&mut conn, now)?;
+    for (application_id, job_id) in &rejected {
+        info!("Auto-rejected application {} to job {} at {}", application_id, job_id, now.to_rfc3339());
+    }
+    Ok(rejected.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+    use actix_web::rt::time;
+    use crate::utils::background::BackgroundTasks;
+    use crate::utils::readiness::Readiness;
+    use crate::utils::testing::TestDb;
+    use super::start;
+
+    #[actix_web::test]
+    async fn rejects_once_ready_and_stops_on_shutdown() {
+        let db = TestDb::new();
+        let employer = db.user("employer@example.com", "employer", "!");
+        let seeker = db.user("seeker@example.com", "job_seeker", "!");
+        let job = db.job(employer);
+        let application = db.application(job, seeker);
+        db.conn()
+            .execute("UPDATE jobs SET auto_reject_after = '2024-09-16T12:00:00+00:00' WHERE id = ?1", [job])
+            .unwrap();
+        let status = || -> String {
+            db.conn().query_row("SELECT status FROM applications WHERE id = ?1", [application], |row| row.get(0)).unwrap()
+        };
+
+        let tasks = BackgroundTasks::default();
+        let readiness = Readiness::default();
+        start(&tasks, db.pool().get_ref().clone(), readiness.clone(), 3600);
+        time::sleep(Duration::from_millis(200)).await;
+        assert_eq!(status(), "pending");
+
+        readiness.mark_ready();
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while status() != "rejected" && Instant::now() < deadline {
+            time::sleep(Duration::from_millis(100)).await;
+        }
+        assert_eq!(status(), "rejected");
+
+        let started = Instant::now();
+        tasks.shutdown(Duration::from_secs(30)).await;
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+}
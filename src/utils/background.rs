@@ -0,0 +1,154 @@
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use actix_web::rt::{time, System};
+use futures::channel::oneshot;
+use futures::future::{self, AbortHandle, Abortable, Either};
+use log::{debug, info, warn};
+use tokio::sync::watch;
+
+struct RegisteredTask {
+    name: String,
+    abort: AbortHandle,
+    done: oneshot::Receiver<()>,
+}
+
+/// Registry of spawned background tasks, drained during graceful shutdown.
+///
+/// Tasks run on the system arbiter rather than on an HTTP worker, so they outlive the worker that spawned them
+/// and are only stopped by [`BackgroundTasks::shutdown`].
+#[derive(Clone)]
+pub struct BackgroundTasks {
+    tasks: Arc<Mutex<Vec<RegisteredTask>>>,
+    stopping: Arc<watch::Sender<bool>>,
+}
+
+impl Default for BackgroundTasks {
+    fn default() -> Self {
+        BackgroundTasks {
+            tasks: Arc::default(),
+            stopping: Arc::new(watch::Sender::new(false)),
+        }
+    }
+}
+
+/// Tells periodic tasks that shutdown has started, so they can return between two runs.
+#[derive(Clone)]
+pub struct StopSignal(watch::Receiver<bool>);
+
+impl StopSignal {
+    /// Sleep for `duration`, returning `false` early if shutdown has started.
+    pub async fn sleep(&mut self, duration: Duration) -> bool {
+        let sleep = Box::pin(time::sleep(duration));
+        let stopping = Box::pin(self.0.wait_for(|stopping| *stopping));
+        matches!(future::select(sleep, stopping).await, Either::Left(_))
+    }
+}
+
+impl BackgroundTasks {
+    /// Signal for periodic tasks to watch, so [`BackgroundTasks::shutdown`] does not have to abort them.
+    pub fn stop_signal(&self) -> StopSignal {
+        StopSignal(self.stopping.subscribe())
+    }
+
+    /// Spawn `task` in the background and register it for shutdown.
+    pub fn spawn<F>(&self, name: &str, task: F)
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        let (abort, registration) = AbortHandle::new_pair();
+        let (done_tx, done) = oneshot::channel();
+        let task_name = name.to_string();
+
+        System::current().arbiter().spawn(async move {
+            if Abortable::new(task, registration).await.is_err() {
+                debug!("Background task {} aborted", task_name);
+            }
+            let _ = done_tx.send(());
+        });
+
+        let mut tasks = self.tasks.lock().unwrap();
+        // Forget tasks that already finished so the registry doesn't grow forever.
+        tasks.retain_mut(|task| matches!(task.done.try_recv(), Ok(None)));
+        tasks.push(RegisteredTask {
+            name: name.to_string(),
+            abort,
+            done,
+        });
+    }
+
+    /// Signal shutdown, then wait for every registered task to finish, aborting whatever is still running after
+    /// `timeout`.
+    pub async fn shutdown(&self, timeout: Duration) {
+        self.stopping.send_replace(true);
+        let tasks = std::mem::take(&mut *self.tasks.lock().unwrap());
+        if tasks.is_empty() {
+            return;
+        }
+
+        info!("Waiting up to {:?} for {} background task(s) to finish", timeout, tasks.len());
+        let mut aborts = Vec::with_capacity(tasks.len());
+        let mut pending = Vec::with_capacity(tasks.len());
+        for task in tasks {
+            aborts.push((task.name, task.abort));
+            pending.push(task.done);
+        }
+
+        if time::timeout(timeout, future::join_all(pending)).await.is_err() {
+            for (name, abort) in aborts {
+                warn!("Aborting background task {} after shutdown timeout", name);
+                abort.abort();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+    use std::time::{Duration, Instant};
+    use actix_web::rt::time;
+    use super::BackgroundTasks;
+
+    #[actix_web::test]
+    async fn shutdown_waits_for_registered_tasks_to_complete() {
+        let tasks = BackgroundTasks::default();
+        let finished = Arc::new(AtomicBool::new(false));
+        let flag = finished.clone();
+        tasks.spawn("email", async move {
+            time::sleep(Duration::from_millis(100)).await;
+            flag.store(true, Ordering::SeqCst);
+        });
+
+        tasks.shutdown(Duration::from_secs(5)).await;
+        assert!(finished.load(Ordering::SeqCst));
+    }
+
+    #[actix_web::test]
+    async fn shutdown_aborts_tasks_still_running_after_the_timeout() {
+        let tasks = BackgroundTasks::default();
+        let finished = Arc::new(AtomicBool::new(false));
+        let flag = finished.clone();
+        tasks.spawn("stuck", async move {
+            time::sleep(Duration::from_secs(3600)).await;
+            flag.store(true, Ordering::SeqCst);
+        });
+
+        let started = Instant::now();
+        tasks.shutdown(Duration::from_millis(100)).await;
+        assert!(started.elapsed() < Duration::from_secs(5));
+        assert!(!finished.load(Ordering::SeqCst));
+    }
+
+    #[actix_web::test]
+    async fn shutdown_stops_periodic_tasks_between_runs() {
+        let tasks = BackgroundTasks::default();
+        let mut stop = tasks.stop_signal();
+        tasks.spawn("periodic", async move { while stop.sleep(Duration::from_secs(3600)).await {} });
+
+        let started = Instant::now();
+        tasks.shutdown(Duration::from_secs(30)).await;
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+}
@@ -0,0 +1,53 @@
+use actix_web::body::{BodySize, MessageBody};
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::http::header::{ContentEncoding, CONTENT_ENCODING};
+use actix_web::middleware::Next;
+use actix_web::Error;
+
+/// Keep responses smaller than `min_bytes` uncompressed, as compressing them costs more CPU than it saves bandwidth.
+///
+/// Marks small responses with `Content-Encoding: identity`, which `actix_web::middleware::Compress` leaves alone, so
+/// it must be wrapped inside `Compress`. Bodies of unknown size, such as streams, are always left to `Compress`. Wrap
+/// it with `actix_web::middleware::from_fn`, capturing `compress_min_bytes` from `Settings`.
+pub async fn skip_small_compression<B: MessageBody>(
+    min_bytes: u64,
+    req: ServiceRequest,
+    next: Next<B>,
+) -> Result<ServiceResponse<B>, Error> {
+    let mut res = next.call(req).await?;
+    if let BodySize::Sized(size) = res.response().body().size() {
+        if size < min_bytes && !res.headers().contains_key(CONTENT_ENCODING) {
+            res.headers_mut().insert(CONTENT_ENCODING, ContentEncoding::Identity.to_header_value());
+        }
+    }
+    Ok(res)
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{test, web, App, HttpResponse};
+    use actix_web::http::header::{ACCEPT_ENCODING, CONTENT_ENCODING};
+    use actix_web::middleware::{from_fn, Compress};
+    use super::skip_small_compression;
+
+    #[actix_web::test]
+    async fn only_responses_above_the_threshold_are_compressed() {
+        let app = test::init_service(
+            App::new()
+                .wrap(from_fn(|req, next| skip_small_compression(1024, req, next)))
+                .wrap(Compress::default())
+                .route("/small", web::get().to(|| async { HttpResponse::Ok().body("ok") }))
+                .route("/large", web::get().to(|| async { HttpResponse::Ok().body("job ".repeat(1024)) })),
+        )
+        .await;
+        let get = |uri: &str| test::TestRequest::get().uri(uri).insert_header((ACCEPT_ENCODING, "gzip")).to_request();
+
+        let small = test::call_service(&app, get("/small")).await;
+        assert_ne!(small.headers().get(CONTENT_ENCODING).map(|value| value.to_str().unwrap()), Some("gzip"));
+        assert_eq!(test::read_body(small).await, "ok");
+
+        let large = test::call_service(&app, get("/large")).await;
+        assert_eq!(large.headers().get(CONTENT_ENCODING).unwrap(), "gzip");
+        assert!(test::read_body(large).await.len() < 1024);
+    }
+}
@@ -0,0 +1,89 @@
+use std::rc::Rc;
+use actix_web::body::{EitherBody, MessageBody};
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::http::header::{CONTENT_LENGTH, TRANSFER_ENCODING};
+use actix_web::http::Method;
+use actix_web::middleware::Next;
+use actix_web::{Error, HttpMessage, HttpResponse};
+use crate::utils::ErrorResponse;
+
+/// Reject `POST`, `PUT`, `PATCH` and `DELETE` requests with a body that is not `application/json` with `415
+/// Unsupported Media Type`.
+///
+/// Requests without a body, such as `POST /v1/employers/{id}/jobs/close-all` or `DELETE /v1/jobs/{id}`, pass, as does
+/// the multipart upload to exactly `import_path`, the full path of `POST /v1/jobs/import`. Wrap it with
+/// `actix_web::middleware::from_fn`, capturing that path.
+pub async fn require_json<B: MessageBody>(
+    import_path: Rc<str>,
+    req: ServiceRequest,
+    next: Next<B>,
+) -> Result<ServiceResponse<EitherBody<B>>, Error> {
+    let is_write = matches!(*req.method(), Method::POST | Method::PUT | Method::PATCH | Method::DELETE);
+    let has_body = req.headers().contains_key(TRANSFER_ENCODING)
+        || req
+            .headers()
+            .get(CONTENT_LENGTH)
+            .and_then(|length| length.to_str().ok())
+            .is_some_and(|length| length.trim() != "0");
+    if is_write && has_body && req.path() != &*import_path {
+        let is_json = matches!(req.mime_type(), Ok(Some(mime)) if mime.essence_str() == "application/json");
+        if !is_json {
+            let content_type = req.content_type().to_string();
+            let response = HttpResponse::UnsupportedMediaType().json(ErrorResponse::UnsupportedMediaType(format!(
+                "Content-Type must be application/json, got {:?}",
+                content_type
+            )));
+            return Ok(req.into_response(response).map_into_right_body());
+        }
+    }
+
+    next.call(req).await.map(ServiceResponse::map_into_left_body)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+    use actix_web::{test, web, App, HttpResponse};
+    use actix_web::http::header::ContentType;
+    use actix_web::http::StatusCode;
+    use actix_web::middleware::from_fn;
+    use super::require_json;
+
+    #[actix_web::test]
+    async fn rejects_non_json_bodies_on_deletes() {
+        let app = test::init_service(
+            App::new()
+                .wrap(from_fn(|req, next| require_json(Rc::from("/jobs/import"), req, next)))
+                .route("/jobs", web::delete().to(HttpResponse::Ok))
+                .route("/jobs/{id}", web::delete().to(HttpResponse::NoContent)),
+        )
+        .await;
+
+        let text = test::TestRequest::delete().uri("/jobs").insert_header(ContentType::plaintext()).set_payload("ids=1");
+        assert_eq!(test::call_service(&app, text.to_request()).await.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+        let json = test::TestRequest::delete().uri("/jobs").set_json(serde_json::json!({"ids": [1]}));
+        assert_eq!(test::call_service(&app, json.to_request()).await.status(), StatusCode::OK);
+        let empty = test::TestRequest::delete().uri("/jobs/1");
+        assert_eq!(test::call_service(&app, empty.to_request()).await.status(), StatusCode::NO_CONTENT);
+    }
+
+    #[actix_web::test]
+    async fn only_the_import_accepts_other_bodies() {
+        let import_path: Rc<str> = Rc::from("/v1/jobs/import");
+        let app = test::init_service(
+            App::new()
+                .wrap(from_fn(move |req, next| require_json(import_path.clone(), req, next)))
+                .route("/v1/jobs/import", web::post().to(HttpResponse::Ok))
+                .route("/v1/users/{id}/jobs/import", web::post().to(HttpResponse::Ok)),
+        )
+        .await;
+
+        for (path, status) in [
+            ("/v1/jobs/import", StatusCode::OK),
+            ("/v1/users/1/jobs/import", StatusCode::UNSUPPORTED_MEDIA_TYPE),
+        ] {
+            let request = test::TestRequest::post().uri(path).insert_header(ContentType::plaintext()).set_payload("id,title");
+            assert_eq!(test::call_service(&app, request.to_request()).await.status(), status, "{}", path);
+        }
+    }
+}
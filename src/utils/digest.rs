@@ -0,0 +1,129 @@
+use std::error::Error;
+use std::time::Duration;
+use chrono::{DateTime, TimeDelta, Utc};
+use log::{error, info};
+use crate::db::{digest::{self, DigestRow}, Pool};
+use crate::models::UserId;
+use crate::utils::background::BackgroundTasks;
+use crate::utils::queue::{SideEffect, WorkQueue};
+use crate::utils::readiness::Readiness;
+
+/// Seconds between checks for the database while it is still being initialized.
+const NOT_READY_RETRY_SECS: u64 = 1;
+
+/// Summary email sent to an employer about applications received since the previous digest.
+struct Digest {
+    email: String,
+    name: String,
+    jobs: Vec<(String, i64)>,
+}
+
+/// Start the application digest in `tasks`, running it every `interval_secs` seconds once `readiness` is marked ready.
+///
+/// The end of each digest window is stored in the database, so restarts neither skip applications nor send them
+/// twice. The task returns between two runs when `tasks` shuts down.
+pub fn start(tasks: &BackgroundTasks, pool: Pool, readiness: Readiness, interval_secs: u64, queue: WorkQueue) {
+    let mut stop = tasks.stop_signal();
+    tasks.spawn("digest", async move {
+        while !readiness.is_ready() {
+            if !stop.sleep(Duration::from_secs(NOT_READY_RETRY_SECS)).await {
+                return;
+            }
+        }
+
+        let mut failed = false;
+        loop {
+            // A failed run is retried after a full interval rather than straight away, as it is not recorded.
+            let delay = if failed {
+                Ok(Duration::from_secs(interval_secs))
+            } else {
+                time_until_due(&pool, interval_secs)
+            };
+            let delay = delay.unwrap_or_else(|e| {
+                error!("Failed to read the last digest run: {}", e);
+                Duration::from_secs(interval_secs)
+            });
+            if !stop.sleep(delay).await {
+                return;
+            }
+
+            let digests = match run(&pool, interval_secs, Utc::now()) {
+                Ok(digests) => digests,
+                Err(e) => {
+                    error!("Failed to send application digest: {}", e);
+                    failed = true;
+                    continue;
+                }
+            };
+            failed = false;
+            info!("Sending application digest to {} employer(s)", digests.len());
+            for digest in digests {
+                send(&queue, digest).await;
+            }
+        }
+    });
+    info!("Application digest enabled, running every {} seconds", interval_secs);
+}
+
+fn time_until_due(pool: &Pool, interval_secs: u64) -> Result<Duration, Box<dyn Error>> {
+    let conn = pool.get()?;
+    let due = match digest::get_last_run(&conn)? {
+        Some(last_run_at) => last_run_at + interval(interval_secs),
+        None => return Ok(Duration::ZERO),
+    };
+    Ok((due - Utc::now()).to_std().unwrap_or(Duration::ZERO))
+}
+
+/// Collect one digest per employer whose jobs received applications since the last run, up to `now`, and record
+/// the run.
+///
+/// The first run covers the `interval_secs` seconds before `now`.
+fn run(pool: &Pool, interval_secs: u64, now: DateTime<Utc>) -> Result<Vec<Digest>, Box<dyn Error>> {
+    let mut conn = pool.get()?;
+    let tx = conn.transaction()?;
+    let since = digest::get_last_run(&tx)?.unwrap_or(now - interval(interval_secs));
+    let digests = group_by_employer(digest::get_new_applications(&tx, since, now)?);
+    digest::set_last_run(&tx, now)?;
+    tx.commit()?;
+    Ok(digests)
+}
+
+fn group_by_employer(rows: Vec<DigestRow>) -> Vec<Digest> {
+    let mut digests: Vec<(UserId, Digest)> = Vec::new();
+    for row in rows {
+        match digests.last_mut() {
+            Some((employer_id, digest)) if *employer_id == row.employer_id => {
+                digest.jobs.push((row.job_title, row.new_applications));
+            }
+            _ => digests.push((
+                row.employer_id,
+                Digest {
+                    email: row.employer_email,
+                    name: row.employer_name,
+                    jobs: vec![(row.job_title, row.new_applications)],
+                },
+            )),
+        }
+    }
+    digests.into_iter().map(|(_, digest)| digest).collect()
+}
+
+/// Hand `digest` to the work queue for delivery as an email.
+async fn send(queue: &WorkQueue, digest: Digest) {
+    let total: i64 = digest.jobs.iter().map(|(_, count)| count).sum();
+    let mut body = format!("Hi {},\n\nYour jobs received {} new application(s):\n", digest.name, total);
+    for (title, count) in &digest.jobs {
+        body.push_str(&format!("- {}: {}\n", title, count));
+    }
+    queue
+        .enqueue(SideEffect::Email {
+            to: digest.email,
+            subject: format!("{} new application(s)", total),
+            body,
+        })
+        .await;
+}
+
+fn interval(interval_secs: u64) -> TimeDelta {
+    TimeDelta::seconds(interval_secs as i64)
+}
@@ -0,0 +1,96 @@
+use actix_web::body::{self, BoxBody, MessageBody};
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::http::header::{HeaderValue, CONTENT_TYPE};
+use actix_web::middleware::Next;
+use actix_web::web::Query;
+use actix_web::Error;
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+
+#[derive(Deserialize)]
+struct EnvelopeQuery {
+    envelope: Option<bool>,
+}
+
+/// Wrap successful JSON responses in a `{ "data": ..., "meta": ... }` envelope when called with `?envelope=true`.
+///
+/// For paginated lists `data` holds the items and `meta` the pagination info (`page`, `count`, `snapshot_count`). For
+/// single resources `meta` is empty. Error responses and every other request are passed through unchanged.
+pub async fn wrap_in_envelope(
+    req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<BoxBody>, Error> {
+    let wanted = Query::<EnvelopeQuery>::from_query(req.query_string())
+        .ok()
+        .and_then(|query| query.envelope)
+        .unwrap_or(false);
+
+    let res = next.call(req).await?.map_into_boxed_body();
+    let is_json = res
+        .headers()
+        .get(CONTENT_TYPE)
+        .is_some_and(|content_type| content_type.as_bytes().starts_with(b"application/json"));
+    if !wanted || !res.status().is_success() || !is_json {
+        return Ok(res);
+    }
+
+    let (req, res) = res.into_parts();
+    let (mut res, body) = res.into_parts();
+    let bytes = body::to_bytes(body).await.map_err(Error::from)?;
+    let wrapped = match serde_json::from_slice::<Value>(&bytes) {
+        Ok(value) => serde_json::to_vec(&envelope(value))?,
+        Err(_) => bytes.to_vec(),
+    };
+
+    res.headers_mut().insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+    Ok(ServiceResponse::new(req, res.set_body(BoxBody::new(wrapped))))
+}
+
+fn envelope(value: Value) -> Value {
+    match value {
+        Value::Object(mut page) if page.contains_key("items") && page.contains_key("page") => {
+            let data = page.remove("items").unwrap_or(Value::Null);
+            json!({ "data": data, "meta": page })
+        }
+        value => json!({ "data": value, "meta": Map::new() }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{test, web, App, HttpResponse};
+    use actix_web::middleware::from_fn;
+    use serde_json::{json, Value};
+    use crate::utils::ErrorResponse;
+    use super::wrap_in_envelope;
+
+    #[actix_web::test]
+    async fn wraps_only_when_asked() {
+        let app = test::init_service(
+            App::new()
+                .wrap(from_fn(wrap_in_envelope))
+                .route("/jobs", web::get().to(|| async {
+                    HttpResponse::Ok().json(json!({"page": 1, "count": 1, "snapshot_count": 1, "items": [{"id": 1}]}))
+                }))
+                .route("/jobs/1", web::get().to(|| async { HttpResponse::Ok().json(json!({"id": 1})) }))
+                .route("/jobs/2", web::get().to(|| async {
+                    HttpResponse::NotFound().json(ErrorResponse::NotFound("id = 2".to_string()))
+                })),
+        )
+        .await;
+        let get = |uri: &str| test::TestRequest::get().uri(uri).to_request();
+
+        let list: Value = test::call_and_read_body_json(&app, get("/jobs")).await;
+        assert_eq!(list, json!({"page": 1, "count": 1, "snapshot_count": 1, "items": [{"id": 1}]}));
+        let list: Value = test::call_and_read_body_json(&app, get("/jobs?envelope=true")).await;
+        assert_eq!(list, json!({"data": [{"id": 1}], "meta": {"page": 1, "count": 1, "snapshot_count": 1}}));
+
+        let job: Value = test::call_and_read_body_json(&app, get("/jobs/1?envelope=false")).await;
+        assert_eq!(job, json!({"id": 1}));
+        let job: Value = test::call_and_read_body_json(&app, get("/jobs/1?envelope=true")).await;
+        assert_eq!(job, json!({"data": {"id": 1}, "meta": {}}));
+
+        let error: Value = test::call_and_read_body_json(&app, get("/jobs/2?envelope=true")).await;
+        assert_eq!(error, json!({"NotFound": "id = 2"}));
+    }
+}
@@ -0,0 +1,61 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+/// Query parameters for endpoints that return a single resource.
+#[derive(Deserialize)]
+pub struct FieldsQuery {
+    pub fields: Option<String>,
+}
+
+/// Parse a comma separated `fields` query parameter against an allow-list.
+///
+/// Returns `Ok(None)` when no selection was requested, or an error naming the first unknown field.
+pub fn parse_fields(fields: Option<&str>, allowed: &[&str]) -> Result<Option<Vec<String>>, String> {
+    let fields = match fields {
+        Some(fields) if !fields.trim().is_empty() => fields,
+        _ => return Ok(None),
+    };
+
+    let mut selected = Vec::new();
+    for field in fields.split(',').map(str::trim).filter(|f| !f.is_empty()) {
+        if !allowed.contains(&field) {
+            return Err(format!("unknown field: {}", field));
+        }
+        if !selected.iter().any(|f| f == field) {
+            selected.push(field.to_string());
+        }
+    }
+    Ok(Some(selected))
+}
+
+/// Serialize a resource, keeping only the selected fields when a selection was requested.
+pub fn select<T: Serialize>(value: &T, fields: &Option<Vec<String>>) -> Value {
+    let value = serde_json::to_value(value).unwrap_or(Value::Null);
+    match fields {
+        Some(fields) => select_fields(value, fields),
+        None => value,
+    }
+}
+
+/// Serialize a page, applying the field selection to every element of its `items` array.
+pub fn select_page<T: Serialize>(page: &T, fields: &Option<Vec<String>>) -> Value {
+    let mut page = serde_json::to_value(page).unwrap_or(Value::Null);
+    if let (Some(fields), Some(Value::Array(items))) = (fields, page.get_mut("items")) {
+        let selected = items.drain(..).map(|item| select_fields(item, fields)).collect();
+        *items = selected;
+    }
+    page
+}
+
+fn select_fields(value: Value, fields: &[String]) -> Value {
+    match value {
+        Value::Object(object) => {
+            let selected: Map<String, Value> = object
+                .into_iter()
+                .filter(|(key, _)| fields.contains(key))
+                .collect();
+            Value::Object(selected)
+        }
+        other => other,
+    }
+}
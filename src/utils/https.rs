@@ -0,0 +1,73 @@
+use std::rc::Rc;
+use actix_web::body::{EitherBody, MessageBody};
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::http::header::{HeaderValue, LOCATION, STRICT_TRANSPORT_SECURITY};
+use actix_web::middleware::Next;
+use actix_web::{Error, HttpResponse};
+
+/// `Strict-Transport-Security` value sent on HTTPS responses: one year, including subdomains.
+const HSTS: &str = "max-age=31536000; includeSubDomains";
+
+/// Redirect plain HTTP requests to HTTPS with `308 Permanent Redirect` and send HSTS on HTTPS responses.
+///
+/// The scheme is taken from `Forwarded` or `X-Forwarded-Proto` when present, so this works behind a TLS-terminating
+/// proxy. Requests to exactly `health_path` are never redirected, as load balancers usually probe over plain HTTP.
+/// Does nothing unless `enabled`. Wrap it with `actix_web::middleware::from_fn`, capturing `force_https` from
+/// `Settings` and the full path of the health check.
+pub async fn force_https<B: MessageBody>(
+    enabled: bool,
+    health_path: Rc<str>,
+    req: ServiceRequest,
+    next: Next<B>,
+) -> Result<ServiceResponse<EitherBody<B>>, Error> {
+    if !enabled || req.path() == &*health_path {
+        return next.call(req).await.map(ServiceResponse::map_into_left_body);
+    }
+
+    let (is_https, location) = {
+        let info = req.connection_info();
+        let location = format!("https://{}{}", info.host(), req.uri());
+        (info.scheme() == "https", location)
+    };
+    if !is_https {
+        let response = HttpResponse::PermanentRedirect()
+            .insert_header((LOCATION, location))
+            .finish();
+        return Ok(req.into_response(response).map_into_right_body());
+    }
+
+    let mut res = next.call(req).await?;
+    res.headers_mut().insert(STRICT_TRANSPORT_SECURITY, HeaderValue::from_static(HSTS));
+    Ok(res.map_into_left_body())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+    use actix_web::{test, web, App, HttpResponse};
+    use actix_web::http::header::STRICT_TRANSPORT_SECURITY;
+    use actix_web::http::StatusCode;
+    use actix_web::middleware::from_fn;
+    use super::force_https;
+
+    #[actix_web::test]
+    async fn only_the_health_check_is_served_over_http() {
+        let health_path: Rc<str> = Rc::from("/v1/health");
+        let app = test::init_service(
+            App::new()
+                .wrap(from_fn(move |req, next| force_https(true, health_path.clone(), req, next)))
+                .route("/v1/health", web::get().to(HttpResponse::Ok))
+                .route("/v1/jobs/health", web::get().to(HttpResponse::Ok)),
+        )
+        .await;
+
+        let health = test::TestRequest::get().uri("/v1/health").to_request();
+        assert_eq!(test::call_service(&app, health).await.status(), StatusCode::OK);
+        let other = test::TestRequest::get().uri("/v1/jobs/health").to_request();
+        assert_eq!(test::call_service(&app, other).await.status(), StatusCode::PERMANENT_REDIRECT);
+        let https = test::TestRequest::get().uri("/v1/jobs/health").insert_header(("x-forwarded-proto", "https"));
+        let response = test::call_service(&app, https.to_request()).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(response.headers().contains_key(STRICT_TRANSPORT_SECURITY));
+    }
+}
@@ -1,14 +1,144 @@
 use std::env;
 use dotenv::dotenv;
+use log::info;
+use crate::auth::password;
+use crate::db;
 use rusqlite::{Connection, Result};
 
+/// Schema changes applied on top of the base tables, in order.
+///
+/// The schema version stored in `PRAGMA user_version` is the number of migrations applied, so new
+/// migrations must only ever be appended to this list.
+const MIGRATIONS: &[&str] = &[
+    "ALTER TABLE jobs ADD COLUMN deadline TEXT;",
+    "ALTER TABLE users ADD COLUMN bio TEXT;
+     ALTER TABLE users ADD COLUMN avatar_url TEXT;
+     ALTER TABLE users ADD COLUMN phone TEXT;",
+    "ALTER TABLE applications ADD COLUMN reference TEXT;
+     CREATE UNIQUE INDEX idx_application_reference ON applications(reference);",
+    "CREATE TABLE tags (
+         id INTEGER PRIMARY KEY,
+         name TEXT NOT NULL UNIQUE
+     );
+     CREATE TABLE job_tags (
+         job_id INTEGER NOT NULL,
+         tag_id INTEGER NOT NULL,
+         PRIMARY KEY (job_id, tag_id),
+         FOREIGN KEY (job_id) REFERENCES jobs(id) ON DELETE CASCADE,
+         FOREIGN KEY (tag_id) REFERENCES tags(id) ON DELETE CASCADE
+     );",
+    "INSERT OR IGNORE INTO job_tags (job_id, tag_id)
+         SELECT job_tags.job_id, canonical.id
+         FROM job_tags
+         JOIN tags ON tags.id = job_tags.tag_id
+         JOIN (SELECT MIN(id) AS id, lower(trim(name)) AS name FROM tags GROUP BY lower(trim(name))) AS canonical
+             ON canonical.name = lower(trim(tags.name));
+     DELETE FROM job_tags WHERE tag_id NOT IN (SELECT MIN(id) FROM tags GROUP BY lower(trim(name)));
+     DELETE FROM tags WHERE id NOT IN (SELECT MIN(id) FROM tags GROUP BY lower(trim(name)));
+     UPDATE tags SET name = lower(trim(name));
+     CREATE UNIQUE INDEX idx_tags_normalized_name ON tags(lower(trim(name)));",
+    "ALTER TABLE jobs ADD COLUMN status TEXT NOT NULL DEFAULT 'open' CHECK(status IN ('open', 'closed', 'expired'));
+     ALTER TABLE jobs ADD COLUMN closed_at TEXT;",
+    "CREATE TABLE application_attachments (
+         id INTEGER PRIMARY KEY,
+         application_id INTEGER NOT NULL,
+         url TEXT NOT NULL,
+         kind TEXT CHECK(kind IN ('resume', 'portfolio', 'other')) NOT NULL,
+         uploaded_at TEXT NOT NULL,
+         FOREIGN KEY (application_id) REFERENCES applications(id) ON DELETE CASCADE
+     );
+     CREATE INDEX idx_application_attachments_application_id ON application_attachments(application_id);
+     INSERT INTO application_attachments (application_id, url, kind, uploaded_at)
+         SELECT id, resume, 'resume', applied_at FROM applications WHERE resume IS NOT NULL AND TRIM(resume) <> '';",
+    "CREATE TABLE digest_runs (
+         id INTEGER PRIMARY KEY CHECK(id = 1),
+         last_run_at TEXT NOT NULL
+     );",
+    "ALTER TABLE jobs ADD COLUMN auto_reject_after TEXT;",
+    "CREATE TABLE job_tombstones (
+         job_id INTEGER PRIMARY KEY,
+         deleted_at TEXT NOT NULL
+     );
+     CREATE INDEX idx_job_tombstones_deleted_at ON job_tombstones(deleted_at);
+     CREATE INDEX idx_jobs_updated_at ON jobs(updated_at);
+     CREATE TRIGGER jobs_tombstone AFTER DELETE ON jobs
+     BEGIN
+         INSERT OR REPLACE INTO job_tombstones (job_id, deleted_at)
+         VALUES (old.id, strftime('%Y-%m-%dT%H:%M:%f+00:00', 'now'));
+     END;",
+    "CREATE TABLE application_notes (
+         id INTEGER PRIMARY KEY,
+         application_id INTEGER NOT NULL,
+         author_id INTEGER NOT NULL,
+         body TEXT NOT NULL,
+         created_at TEXT NOT NULL,
+         FOREIGN KEY (application_id) REFERENCES applications(id) ON DELETE CASCADE,
+         FOREIGN KEY (author_id) REFERENCES users(id)
+     );
+     CREATE INDEX idx_application_notes_application_id ON application_notes(application_id);",
+    "ALTER TABLE applications ADD COLUMN rating INTEGER CHECK(rating BETWEEN 1 AND 5);",
+    "CREATE TABLE user_preferences (
+         user_id INTEGER PRIMARY KEY,
+         employment_types TEXT NOT NULL DEFAULT '[]',
+         locations TEXT NOT NULL DEFAULT '[]',
+         min_salary INTEGER CHECK(min_salary >= 0),
+         updated_at TEXT NOT NULL,
+         FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+     );",
+    "CREATE TABLE application_status_changes (
+         id INTEGER PRIMARY KEY,
+         application_id INTEGER NOT NULL,
+         status TEXT NOT NULL,
+         changed_at TEXT NOT NULL,
+         FOREIGN KEY (application_id) REFERENCES applications(id) ON DELETE CASCADE
+     );
+     CREATE INDEX idx_application_status_changes_application_id ON application_status_changes(application_id);
+     CREATE TRIGGER application_status_change AFTER UPDATE OF status ON applications
+     WHEN old.status IS NOT new.status
+     BEGIN
+         INSERT INTO application_status_changes (application_id, status, changed_at)
+         VALUES (new.id, new.status, strftime('%Y-%m-%dT%H:%M:%f+00:00', 'now'));
+     END;",
+    // Renamed `application` to `applications`. Databases still using the old name are now renamed before any
+    // migration runs, so this is kept only to preserve the numbering of the migrations after it.
+    "",
+    // Backups redact passwords, so restored users could sign in with the redaction marker itself.
+    "UPDATE users SET password = '!' WHERE password = '********';",
+    // A job id inserted again, e.g. by a backup restore, is no longer deleted.
+    "CREATE TRIGGER jobs_untombstone AFTER INSERT ON jobs
+     BEGIN
+         DELETE FROM job_tombstones WHERE job_id = new.id;
+     END;
+     DELETE FROM job_tombstones WHERE job_id IN (SELECT id FROM jobs);",
+];
+
+/// Schema version of a fully migrated database.
+pub const SCHEMA_VERSION: usize = MIGRATIONS.len();
+
+/// Schema version of the database, i.e. the number of migrations applied to it.
+pub fn schema_version(conn: &Connection) -> Result<usize> {
+    conn.query_row("PRAGMA user_version", [], |row| row.get(0))
+}
+
 pub fn initialize_database() -> Result<()> {
     dotenv().ok();
 
     let database_url = env::var("DATABASE_URL")
         .expect("DATABASE_URL must be set");
 
-    let conn = Connection::open(database_url)?;
+    let mut conn = db::open(database_url)?;
+    initialize(&mut conn)
+}
+
+/// Create the base tables of a new database and migrate it to the current schema version.
+pub fn initialize(conn: &mut Connection) -> Result<()> {
+    rename_application_table(conn)?;
+    create_base_tables(conn)?;
+    run_migrations(conn)?;
+    hash_plaintext_passwords(conn)
+}
+
+fn create_base_tables(conn: &Connection) -> Result<()> {
 
     conn.execute_batch(
         "
@@ -32,10 +162,10 @@ pub fn initialize_database() -> Result<()> {
             employment_type TEXT CHECK(employment_type IN ('full_time', 'part_time', 'contract')),
             posted_at TEXT NOT NULL,
             updated_at TEXT NOT NULL,
-            FOREIGN KEY (employer_id) REFERENCES User(id)
+            FOREIGN KEY (employer_id) REFERENCES users(id)
         );
 
-        CREATE TABLE IF NOT EXISTS application (
+        CREATE TABLE IF NOT EXISTS applications (
             id INTEGER PRIMARY KEY,
             job_seeker_id INTEGER NOT NULL,
             job_id INTEGER NOT NULL,
@@ -43,11 +173,120 @@ pub fn initialize_database() -> Result<()> {
             resume TEXT,
             status TEXT CHECK(status IN ('pending', 'reviewed', 'accepted', 'rejected')) NOT NULL,
             applied_at TEXT NOT NULL,
-            FOREIGN KEY (job_seeker_id) REFERENCES User(id),
-            FOREIGN KEY (job_id) REFERENCES Job(id)
+            FOREIGN KEY (job_seeker_id) REFERENCES users(id),
+            FOREIGN KEY (job_id) REFERENCES jobs(id)
         );
         "
+    )
+}
+
+/// Rename the `application` table of databases created before it was named `applications`, like every query names it.
+///
+/// Renaming also updates the foreign keys and triggers referring to the table.
+fn rename_application_table(conn: &Connection) -> Result<()> {
+    let exists: bool = conn.query_row(
+        "SELECT EXISTS (SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'application')",
+        [],
+        |row| row.get(0),
     )?;
+    if exists {
+        conn.execute_batch("ALTER TABLE application RENAME TO applications;")?;
+        info!("Renamed table application to applications");
+    }
+    Ok(())
+}
+
+/// Apply every migration newer than the database's current schema version.
+fn run_migrations(conn: &mut Connection) -> Result<()> {
+    let version = schema_version(conn)?;
+
+    for (index, migration) in MIGRATIONS.iter().enumerate().skip(version) {
+        let tx = conn.transaction()?;
+        tx.execute_batch(migration)?;
+        tx.pragma_update(None, "user_version", index + 1)?;
+        tx.commit()?;
+        info!("Applied database migration {}", index + 1);
+    }
 
     Ok(())
-}
\ No newline at end of file
+}
+/// Hash passwords stored as they were sent, before password hashing was introduced.
+///
+/// Only rows that hold neither a password hash nor the unusable marker are touched, so this does nothing once every
+/// legacy password has been hashed.
+fn hash_plaintext_passwords(conn: &mut Connection) -> Result<()> {
+    let legacy = {
+        let mut stmt = conn.prepare("SELECT id, password FROM users WHERE password <> ?1")?;
+        let rows = stmt.query_map([password::UNUSABLE], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?)))?;
+        rows.filter(|row| !matches!(row, Ok((_, stored)) if password::is_hash(stored)))
+            .collect::<Result<Vec<_>>>()?
+    };
+    if legacy.is_empty() {
+        return Ok(());
+    }
+
+    let tx = conn.transaction()?;
+    for (id, plaintext) in &legacy {
+        let hash = password::hash(plaintext).map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?;
+        tx.execute("UPDATE users SET password = ?1 WHERE id = ?2", (hash, id))?;
+    }
+    tx.commit()?;
+    info!("Hashed {} plaintext passwords", legacy.len());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::auth::password;
+    use crate::utils::testing::TestDb;
+    use rusqlite::Connection;
+    use super::{initialize, SCHEMA_VERSION};
+
+    #[test]
+    fn plaintext_passwords_are_hashed_once() {
+        let db = TestDb::new();
+        let plaintext = db.user("jane@example.com", "employer", "hunter2");
+        let unusable = db.user("john@example.com", "employer", password::UNUSABLE);
+
+        initialize(&mut db.conn()).unwrap();
+        let stored = |id: i64| -> String {
+            db.conn().query_row("SELECT password FROM users WHERE id = ?1", [id], |row| row.get(0)).unwrap()
+        };
+        let hashed = stored(plaintext);
+        assert!(password::verify("hunter2", &hashed));
+        assert_eq!(stored(unusable), password::UNUSABLE);
+
+        initialize(&mut db.conn()).unwrap();
+        assert_eq!(stored(plaintext), hashed);
+    }
+
+    #[test]
+    fn databases_with_the_old_application_table_are_renamed_before_migrating() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT NOT NULL, email TEXT NOT NULL UNIQUE,
+                 password TEXT NOT NULL, role TEXT NOT NULL, created_at TEXT NOT NULL, updated_at TEXT NOT NULL);
+             CREATE TABLE jobs (id INTEGER PRIMARY KEY, employer_id INTEGER NOT NULL, title TEXT NOT NULL,
+                 description TEXT NOT NULL, location TEXT NOT NULL, salary TEXT, employment_type TEXT,
+                 posted_at TEXT NOT NULL, updated_at TEXT NOT NULL);
+             CREATE TABLE application (id INTEGER PRIMARY KEY, job_seeker_id INTEGER NOT NULL, job_id INTEGER NOT NULL,
+                 cover_letter TEXT, resume TEXT, status TEXT NOT NULL, applied_at TEXT NOT NULL);
+             INSERT INTO application (job_seeker_id, job_id, resume, status, applied_at)
+                 VALUES (1, 1, 'https://example.com/cv.pdf', 'pending', '2024-09-16T12:00:00+00:00');",
+        )
+        .unwrap();
+
+        initialize(&mut conn).unwrap();
+        let tables: Vec<String> = conn
+            .prepare("SELECT name FROM sqlite_master WHERE type = 'table' AND name LIKE 'application%' ORDER BY name")
+            .unwrap()
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(tables, ["application_attachments", "application_notes", "application_status_changes", "applications"]);
+        let attachments: i64 = conn.query_row("SELECT COUNT(*) FROM application_attachments", [], |row| row.get(0)).unwrap();
+        assert_eq!(attachments, 1);
+        assert_eq!(super::schema_version(&conn).unwrap(), SCHEMA_VERSION);
+    }
+}
@@ -0,0 +1,54 @@
+use actix_web::body::{EitherBody, MessageBody};
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::middleware::Next;
+use actix_web::{Error, HttpResponse};
+use log::warn;
+use crate::utils::ErrorResponse;
+
+/// Reject requests whose query string is longer than `max_length` bytes with `414 URI Too Long`.
+///
+/// Wrap it with `actix_web::middleware::from_fn`, capturing the limit from `Settings`.
+pub async fn limit_query_length<B: MessageBody>(
+    max_length: usize,
+    req: ServiceRequest,
+    next: Next<B>,
+) -> Result<ServiceResponse<EitherBody<B>>, Error> {
+    let length = req.query_string().len();
+    if length > max_length {
+        warn!("Rejected request to {} with a {} byte query string", req.path(), length);
+        let response = HttpResponse::UriTooLong().json(ErrorResponse::UriTooLong(format!(
+            "query string is {} bytes, the maximum is {}",
+            length, max_length
+        )));
+        return Ok(req.into_response(response).map_into_right_body());
+    }
+
+    next.call(req).await.map(ServiceResponse::map_into_left_body)
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{test, web, App, HttpResponse};
+    use actix_web::http::StatusCode;
+    use actix_web::middleware::from_fn;
+    use serde_json::{json, Value};
+    use super::limit_query_length;
+
+    #[actix_web::test]
+    async fn rejects_long_query_strings_with_414() {
+        let app = test::init_service(
+            App::new()
+                .wrap(from_fn(|req, next| limit_query_length(8, req, next)))
+                .route("/jobs", web::get().to(HttpResponse::Ok)),
+        )
+        .await;
+
+        let short = test::TestRequest::get().uri("/jobs?page=1").to_request();
+        assert_eq!(test::call_service(&app, short).await.status(), StatusCode::OK);
+        let long = test::TestRequest::get().uri("/jobs?page=1&limit=10").to_request();
+        let response = test::call_service(&app, long).await;
+        assert_eq!(response.status(), StatusCode::URI_TOO_LONG);
+        let body: Value = test::read_body_json(response).await;
+        assert_eq!(body, json!({"UriTooLong": "query string is 15 bytes, the maximum is 8"}));
+    }
+}
@@ -0,0 +1,146 @@
+use std::fmt::Display;
+use std::str::FromStr;
+use serde::{Deserialize, Deserializer};
+
+/// Number of items returned when `limit` is not given.
+pub const DEFAULT_LIMIT: i64 = 10;
+
+/// Largest `limit` a list endpoint honours. Larger values are lowered to it.
+pub const MAX_LIMIT: i64 = 100;
+
+/// Query parameters shared by list endpoints, embedded in each endpoint's query with `#[serde(flatten)]`.
+#[derive(Deserialize)]
+pub struct ListParams {
+    #[serde(default, deserialize_with = "from_str")]
+    pub limit: Option<i64>,
+    #[serde(default, deserialize_with = "from_str")]
+    pub offset: Option<i64>,
+    pub sort: Option<String>,
+    pub order: Option<String>,
+}
+
+/// Validated `limit`, `offset` and sort order of a list request.
+#[derive(Clone, Debug)]
+pub struct Page {
+    pub limit: i64,
+    pub offset: i64,
+    pub sort: Option<Sort>,
+}
+
+/// Field a list is sorted by, taken from the endpoint's sortable fields.
+#[derive(Clone, Debug)]
+pub struct Sort {
+    pub field: String,
+    pub descending: bool,
+}
+
+impl ListParams {
+    /// Validate the parameters against the fields the endpoint can be sorted by.
+    ///
+    /// `order` defaults to `asc`. `limit` defaults to [`DEFAULT_LIMIT`] and is lowered to [`MAX_LIMIT`]; a `limit` below 1, a negative `offset`,
+    /// a `sort` field outside `sortable`, an `order` other than `asc` or `desc`, or an `order` without `sort` is an
+    /// error.
+    pub fn page(&self, sortable: &[&str]) -> Result<Page, String> {
+        let limit = self.limit.unwrap_or(DEFAULT_LIMIT);
+        if limit < 1 {
+            return Err("limit must be at least 1".to_string());
+        }
+        let offset = self.offset.unwrap_or(0);
+        if offset < 0 {
+            return Err("offset must not be negative".to_string());
+        }
+        if let Some(sort) = self.sort.as_deref() {
+            if !sortable.contains(&sort) {
+                return Err(format!("cannot sort by: {}", sort));
+            }
+        }
+        match (self.order.as_deref(), &self.sort) {
+            (None, _) | (Some("asc" | "desc"), Some(_)) => {}
+            (Some("asc" | "desc"), None) => return Err("order requires sort".to_string()),
+            (Some(order), _) => return Err(format!("order must be asc or desc, got: {}", order)),
+        }
+        Ok(Page {
+            limit: limit.min(MAX_LIMIT),
+            offset,
+            sort: self.sort.clone().map(|field| Sort {
+                field,
+                descending: self.order.as_deref() == Some("desc"),
+            }),
+        })
+    }
+}
+
+impl Page {
+    /// Current page number, starting at 1.
+    pub fn number(&self) -> i64 {
+        self.offset / self.limit + 1
+    }
+}
+
+/// Deserialize an optional number from its string form.
+///
+/// Flattened fields reach the deserializer as strings, so the query string's numbers are parsed here.
+fn from_str<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: FromStr,
+    T::Err: Display,
+{
+    match Option::<String>::deserialize(deserializer)? {
+        Some(value) => value.parse().map(Some).map_err(serde::de::Error::custom),
+        None => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::web::Query;
+    use serde::Deserialize;
+    use super::{ListParams, DEFAULT_LIMIT, MAX_LIMIT};
+
+    #[derive(Deserialize)]
+    struct EndpointQuery {
+        #[serde(flatten)]
+        list: ListParams,
+    }
+
+    fn params(query: &str) -> ListParams {
+        Query::<EndpointQuery>::from_query(query).unwrap().into_inner().list
+    }
+
+    #[test]
+    fn limit_and_offset_are_validated_and_clamped() {
+        let page = params("").page(&[]).unwrap();
+        assert_eq!((page.limit, page.offset), (DEFAULT_LIMIT, 0));
+
+        assert_eq!(params("limit=0").page(&[]).unwrap_err(), "limit must be at least 1");
+        assert_eq!(params("offset=-1").page(&[]).unwrap_err(), "offset must not be negative");
+
+        let page = params("limit=500&offset=20").page(&[]).unwrap();
+        assert_eq!((page.limit, page.offset), (MAX_LIMIT, 20));
+    }
+
+    #[test]
+    fn sort_and_order_are_checked_against_the_sortable_fields() {
+        let page = params("sort=title&order=desc").page(&["title"]).unwrap();
+        let sort = page.sort.unwrap();
+        assert_eq!((sort.field.as_str(), sort.descending), ("title", true));
+
+        assert_eq!(params("sort=salary").page(&["title"]).unwrap_err(), "cannot sort by: salary");
+        assert_eq!(params("order=asc").page(&["title"]).unwrap_err(), "order requires sort");
+        assert_eq!(params("sort=title&order=up").page(&["title"]).unwrap_err(), "order must be asc or desc, got: up");
+    }
+
+    #[test]
+    fn page_numbers_use_the_validated_limit() {
+        for limit in ["0", "-3"] {
+            let query = format!("limit={}", limit);
+            assert_eq!(params(&query).page(&[]).unwrap_err(), "limit must be at least 1", "{}", query);
+        }
+
+        let page = params("limit=1000&offset=250").page(&[]).unwrap();
+        assert_eq!(page.limit, MAX_LIMIT);
+        assert_eq!(page.number(), 3);
+        assert_eq!(params("limit=1&offset=0").page(&[]).unwrap().number(), 1);
+    }
+}
@@ -0,0 +1,82 @@
+use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use actix_web::body::{EitherBody, MessageBody};
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::http::Method;
+use actix_web::middleware::Next;
+use actix_web::{Error, HttpResponse};
+use crate::utils::ErrorResponse;
+
+/// Whether the API is in maintenance mode and rejects writes. Cloning shares the flag, so it can be toggled at runtime.
+#[derive(Clone, Default)]
+pub struct MaintenanceMode(Arc<AtomicBool>);
+
+impl MaintenanceMode {
+    pub fn new(enabled: bool) -> Self {
+        MaintenanceMode(Arc::new(AtomicBool::new(enabled)))
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.0.load(Ordering::Acquire)
+    }
+
+    pub fn set(&self, enabled: bool) {
+        self.0.store(enabled, Ordering::Release);
+    }
+}
+
+/// Reject every request but `GET`, `HEAD` and `OPTIONS` with `503 Service Unavailable` while in maintenance mode.
+///
+/// Requests to exactly `toggle_path`, which toggles maintenance mode, are always allowed, so it can be turned off
+/// again. Wrap it with `actix_web::middleware::from_fn`, capturing a clone of the shared `MaintenanceMode` and the full
+/// path of the toggle endpoint.
+pub async fn block_writes<B: MessageBody>(
+    mode: MaintenanceMode,
+    toggle_path: Rc<str>,
+    req: ServiceRequest,
+    next: Next<B>,
+) -> Result<ServiceResponse<EitherBody<B>>, Error> {
+    let is_read = matches!(*req.method(), Method::GET | Method::HEAD | Method::OPTIONS);
+    if mode.is_enabled() && !is_read && req.path() != &*toggle_path {
+        let response = HttpResponse::ServiceUnavailable().json(ErrorResponse::ServiceUnavailable(
+            "The API is in maintenance mode, changes are temporarily disabled".to_string(),
+        ));
+        return Ok(req.into_response(response).map_into_right_body());
+    }
+
+    next.call(req).await.map(ServiceResponse::map_into_left_body)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+    use actix_web::{test, web, App, HttpResponse};
+    use actix_web::http::StatusCode;
+    use actix_web::middleware::from_fn;
+    use super::{block_writes, MaintenanceMode};
+
+    #[actix_web::test]
+    async fn only_the_toggle_is_writable_in_maintenance_mode() {
+        let mode = MaintenanceMode::new(true);
+        let toggle_path: Rc<str> = Rc::from("/v1/admin/maintenance");
+        let app = test::init_service(
+            App::new()
+                .wrap(from_fn(move |req, next| block_writes(mode.clone(), toggle_path.clone(), req, next)))
+                .route("/v1/admin/maintenance", web::post().to(HttpResponse::Ok))
+                .route("/v1/jobs/admin/maintenance", web::post().to(HttpResponse::Ok))
+                .route("/v1/jobs", web::get().to(HttpResponse::Ok)),
+        )
+        .await;
+
+        for (request, status) in [
+            (test::TestRequest::post().uri("/v1/admin/maintenance"), StatusCode::OK),
+            (test::TestRequest::post().uri("/v1/jobs/admin/maintenance"), StatusCode::SERVICE_UNAVAILABLE),
+            (test::TestRequest::get().uri("/v1/jobs"), StatusCode::OK),
+        ] {
+            let request = request.to_request();
+            let path = request.path().to_string();
+            assert_eq!(test::call_service(&app, request).await.status(), status, "{}", path);
+        }
+    }
+}
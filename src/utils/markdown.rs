@@ -0,0 +1,16 @@
+use pulldown_cmark::{html, Event, Options, Parser};
+
+/// Render Markdown to HTML.
+///
+/// Raw HTML in the input is escaped rather than passed through, so user-provided text cannot inject markup.
+pub fn render(markdown: &str) -> String {
+    let parser = Parser::new_ext(markdown, Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH)
+        .map(|event| match event {
+            Event::Html(raw) | Event::InlineHtml(raw) => Event::Text(raw),
+            event => event,
+        });
+
+    let mut output = String::with_capacity(markdown.len() * 3 / 2);
+    html::push_html(&mut output, parser);
+    output
+}
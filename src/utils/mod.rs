@@ -1,33 +1,159 @@
+use actix_web::error::{InternalError, JsonPayloadError, PathError};
+use actix_web::{HttpRequest, HttpResponse};
 use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
 use crate::models::{User, Job, Application};
+use crate::models::application::Applicant;
+use crate::models::user::{ActivityEvent, Employer};
+use crate::models::job::JobChange;
 
 pub mod init_db;
+pub mod fields;
+pub mod background;
+pub mod envelope;
+pub mod compress;
+pub mod content_type;
+pub mod https;
+pub mod limits;
+pub mod list;
+pub mod maintenance;
+pub mod markdown;
+pub mod pdf;
+pub mod queue;
+pub mod readiness;
+pub mod digest;
+pub mod auto_reject;
+pub mod seed;
+pub mod single_flight;
+pub mod timezone;
+#[cfg(test)]
+pub mod testing;
 
 /// Pagination User
 #[derive(Serialize, Deserialize, Clone, ToSchema)]
 pub struct PaginationUser {
+    /// Current page number, starting at 1.
+    #[schema(example = 1)]
     pub page: i64,
+    /// Total number of items across all pages.
+    #[schema(example = 25)]
     pub count: i64,
+    /// Total the client is paging against. Echoes the `snapshot_count` query parameter, or `count` when it is
+    /// absent, so clients can send it back on subsequent pages.
+    #[schema(example = 25)]
+    pub snapshot_count: i64,
+    /// Items on the current page.
     pub items: Vec<User>,
 }
 
+/// Cursor pagination ActivityEvent
+#[derive(Serialize, Deserialize, Clone, ToSchema)]
+pub struct PaginationActivity {
+    /// Maximum number of events on a page.
+    #[schema(example = 10)]
+    pub limit: i64,
+    /// Cursor of the next page, absent on the last page.
+    #[schema(example = "status_changed:7")]
+    pub next_cursor: Option<String>,
+    /// Events on the current page, newest first.
+    pub items: Vec<ActivityEvent>,
+}
+
 /// Pagination Job
 #[derive(Serialize, Deserialize, Clone, ToSchema)]
 pub struct PaginationJob {
+    /// Current page number, starting at 1.
+    #[schema(example = 1)]
     pub page: i64,
+    /// Total number of items across all pages.
+    #[schema(example = 25)]
     pub count: i64,
+    /// Total the client is paging against. Echoes the `snapshot_count` query parameter, or `count` when it is
+    /// absent, so clients can send it back on subsequent pages.
+    #[schema(example = 25)]
+    pub snapshot_count: i64,
+    /// Items on the current page.
     pub items: Vec<Job>,
 }
 
+/// Pagination JobChange
+#[derive(Serialize, Deserialize, Clone, ToSchema)]
+pub struct PaginationJobChange {
+    /// Current page number, starting at 1.
+    #[schema(example = 1)]
+    pub page: i64,
+    /// Total number of changes across all pages.
+    #[schema(example = 25)]
+    pub count: i64,
+    /// Total the client is paging against. Echoes the `snapshot_count` query parameter, or `count` when it is
+    /// absent, so clients can send it back on subsequent pages.
+    #[schema(example = 25)]
+    pub snapshot_count: i64,
+    /// Changes on the current page, oldest first.
+    pub items: Vec<JobChange>,
+}
+
 /// Pagination Application
 #[derive(Serialize, Deserialize, Clone, ToSchema)]
 pub struct PaginationApplication {
+    /// Current page number, starting at 1.
+    #[schema(example = 1)]
     pub page: i64,
+    /// Total number of items across all pages.
+    #[schema(example = 25)]
     pub count: i64,
+    /// Total the client is paging against. Echoes the `snapshot_count` query parameter, or `count` when it is
+    /// absent, so clients can send it back on subsequent pages.
+    #[schema(example = 25)]
+    pub snapshot_count: i64,
+    /// Items on the current page.
     pub items: Vec<Application>,
 }
 
+/// Pagination Applicant
+#[derive(Serialize, Deserialize, Clone, ToSchema)]
+pub struct PaginationApplicant {
+    /// Current page number, starting at 1.
+    #[schema(example = 1)]
+    pub page: i64,
+    /// Total number of distinct applicants across all pages.
+    #[schema(example = 25)]
+    pub count: i64,
+    /// Total the client is paging against. Echoes the `snapshot_count` query parameter, or `count` when it is
+    /// absent, so clients can send it back on subsequent pages.
+    #[schema(example = 25)]
+    pub snapshot_count: i64,
+    /// Applicants on the current page.
+    pub items: Vec<Applicant>,
+}
+
+/// Pagination Employer
+#[derive(Serialize, Deserialize, Clone, ToSchema)]
+pub struct PaginationEmployer {
+    /// Current page number, starting at 1.
+    #[schema(example = 1)]
+    pub page: i64,
+    /// Total number of matching employers across all pages.
+    #[schema(example = 25)]
+    pub count: i64,
+    /// Total the client is paging against. Echoes the `snapshot_count` query parameter, or `count` when it is
+    /// absent, so clients can send it back on subsequent pages.
+    #[schema(example = 25)]
+    pub snapshot_count: i64,
+    /// Employers on the current page.
+    pub items: Vec<Employer>,
+}
+
+/// Response header set on list responses when the total changed since the client's `snapshot_count`.
+///
+/// Its value is the signed difference between the current total and the snapshot.
+pub const PAGINATION_DRIFT_HEADER: &str = "X-Pagination-Drift";
+
+/// Response header set on list responses when malformed rows were left out of the page.
+///
+/// Rows are only skipped when `STRICT_ROW_DECODING` is turned off; its value is the number of rows skipped.
+pub const SKIPPED_ROWS_HEADER: &str = "X-Skipped-Rows";
+
 /// API endpoint error responses
 #[derive(Serialize, Deserialize, Clone, ToSchema)]
 pub enum ErrorResponse {
@@ -45,4 +171,58 @@ pub enum ErrorResponse {
     Forbidden(String),
     /// When a requested resource already exists.
     AlreadyExists(String),
+    /// When a well-formed request is semantically invalid (e.g., failed field validation, illegal status transition).
+    UnprocessableEntity(String),
+    /// When the service cannot handle the request yet (e.g., still running database migrations at startup).
+    ServiceUnavailable(String),
+    /// When the request body is in a format the endpoint does not accept (e.g., a JSON endpoint sent `text/plain`).
+    UnsupportedMediaType(String),
+    /// When the request URI is longer than the server accepts (e.g., a query string over `MAX_QUERY_LENGTH`).
+    UriTooLong(String),
+}
+
+
+/// A single validation failure for a request field
+#[derive(Serialize, Deserialize, Clone, Debug, ToSchema)]
+pub struct FieldError {
+    /// Name of the offending field.
+    #[schema(example = "salary")]
+    pub field: String,
+    /// Human readable description of the problem.
+    #[schema(example = "salary must be an amount or a range like \"$120,000 - $150,000\"")]
+    pub message: String,
+}
+
+/// Result of a dry-run validation
+#[derive(Serialize, Deserialize, Clone, ToSchema)]
+pub struct ValidationResponse {
+    /// Whether the payload passed validation.
+    #[schema(example = true)]
+    pub valid: bool,
+    /// Field errors, present only when the payload is invalid.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<FieldError>,
+}
+
+
+/// Map path extraction failures (e.g. `/v1/jobs/abc`) to a `400 ErrorResponse::BadRequest`.
+pub fn path_error_handler(err: PathError, req: &HttpRequest) -> actix_web::Error {
+    let segments: Vec<(&str, &str)> = req.match_info().iter().collect();
+    let message = match segments.as_slice() {
+        [(name, value)] => format!("invalid {}: {}", name, value),
+        _ => format!("invalid path: {}", err),
+    };
+    let response = HttpResponse::BadRequest().json(ErrorResponse::BadRequest(message));
+    InternalError::from_response(err, response).into()
+}
+
+/// Map JSON body extraction failures (e.g. malformed JSON or a field of the wrong type) to a `400
+/// ErrorResponse::BadRequest`, keeping 415 for a wrong `Content-Type`.
+pub fn json_error_handler(err: JsonPayloadError, _req: &HttpRequest) -> actix_web::Error {
+    let response = match &err {
+        JsonPayloadError::ContentType => HttpResponse::UnsupportedMediaType()
+            .json(ErrorResponse::UnsupportedMediaType("Content-Type must be application/json".to_string())),
+        _ => HttpResponse::BadRequest().json(ErrorResponse::BadRequest(format!("invalid JSON body: {}", err))),
+    };
+    InternalError::from_response(err, response).into()
 }
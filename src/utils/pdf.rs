@@ -0,0 +1,121 @@
+use lopdf::content::{Content, Operation};
+use lopdf::{dictionary, Document, Object, Stream};
+
+/// A4 page size in points.
+const PAGE_WIDTH: i64 = 595;
+const PAGE_HEIGHT: i64 = 842;
+const MARGIN: i64 = 50;
+const FONT_SIZE: i64 = 11;
+const TITLE_SIZE: i64 = 16;
+const LEADING: i64 = 15;
+/// Characters per line that fit between the margins in Helvetica at `FONT_SIZE`.
+const LINE_WIDTH: usize = 90;
+
+/// Render a plain text document with a title, labelled fields and a free-form body, paginated over A4 pages.
+///
+/// The document uses the standard Helvetica font, so characters outside Latin-1 are replaced with `?`.
+pub fn render(title: &str, fields: &[(&str, String)], body: &str) -> lopdf::Result<Vec<u8>> {
+    let mut lines = Vec::new();
+    for (label, value) in fields {
+        lines.extend(wrap(&format!("{}: {}", label, value)));
+    }
+    lines.push(String::new());
+    for paragraph in body.lines() {
+        lines.extend(wrap(paragraph));
+    }
+
+    let mut doc = Document::with_version("1.5");
+    let pages_id = doc.new_object_id();
+    let font_id = doc.add_object(dictionary! {
+        "Type" => "Font",
+        "Subtype" => "Type1",
+        "BaseFont" => "Helvetica",
+        "Encoding" => "WinAnsiEncoding",
+    });
+    let resources_id = doc.add_object(dictionary! {
+        "Font" => dictionary! { "F1" => font_id },
+    });
+
+    let lines_per_page = ((PAGE_HEIGHT - 2 * MARGIN) / LEADING) as usize;
+    let first_page_lines = lines_per_page - 2;
+    let mut pages = vec![lines.iter().take(first_page_lines).collect::<Vec<_>>()];
+    pages.extend(lines[first_page_lines.min(lines.len())..].chunks(lines_per_page).map(|chunk| chunk.iter().collect()));
+
+    let mut kids = Vec::new();
+    for (number, page_lines) in pages.iter().enumerate() {
+        let mut operations = vec![
+            Operation::new("BT", vec![]),
+            Operation::new("TL", vec![LEADING.into()]),
+            Operation::new("Td", vec![MARGIN.into(), (PAGE_HEIGHT - MARGIN).into()]),
+        ];
+        if number == 0 {
+            operations.push(Operation::new("Tf", vec!["F1".into(), TITLE_SIZE.into()]));
+            operations.push(Operation::new("Tj", vec![Object::string_literal(latin1(title))]));
+            operations.push(Operation::new("T*", vec![]));
+            operations.push(Operation::new("T*", vec![]));
+        }
+        operations.push(Operation::new("Tf", vec!["F1".into(), FONT_SIZE.into()]));
+        for line in page_lines {
+            operations.push(Operation::new("Tj", vec![Object::string_literal(latin1(line))]));
+            operations.push(Operation::new("T*", vec![]));
+        }
+        operations.push(Operation::new("ET", vec![]));
+
+        let content = Content { operations };
+        let content_id = doc.add_object(Stream::new(dictionary! {}, content.encode()?));
+        kids.push(doc.add_object(dictionary! {
+            "Type" => "Page",
+            "Parent" => pages_id,
+            "Contents" => content_id,
+        }));
+    }
+
+    let count = kids.len() as i64;
+    doc.objects.insert(pages_id, Object::Dictionary(dictionary! {
+        "Type" => "Pages",
+        "Kids" => kids.into_iter().map(Object::Reference).collect::<Vec<_>>(),
+        "Count" => count,
+        "Resources" => resources_id,
+        "MediaBox" => vec![0.into(), 0.into(), PAGE_WIDTH.into(), PAGE_HEIGHT.into()],
+    }));
+    let catalog_id = doc.add_object(dictionary! {
+        "Type" => "Catalog",
+        "Pages" => pages_id,
+    });
+    doc.trailer.set("Root", catalog_id);
+
+    let mut bytes = Vec::new();
+    doc.save_to(&mut bytes)?;
+    Ok(bytes)
+}
+
+/// Split `text` into lines of at most `LINE_WIDTH` characters, breaking at spaces where possible.
+fn wrap(text: &str) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut line = String::new();
+    for word in text.split_whitespace() {
+        let mut word = word;
+        while word.chars().count() > LINE_WIDTH {
+            if !line.is_empty() {
+                lines.push(std::mem::take(&mut line));
+            }
+            let split = word.char_indices().nth(LINE_WIDTH).map_or(word.len(), |(index, _)| index);
+            lines.push(word[..split].to_string());
+            word = &word[split..];
+        }
+        if !line.is_empty() && line.chars().count() + 1 + word.chars().count() > LINE_WIDTH {
+            lines.push(std::mem::take(&mut line));
+        }
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        line.push_str(word);
+    }
+    lines.push(line);
+    lines
+}
+
+/// Encode `text` as Latin-1, which matches WinAnsiEncoding for printable characters, replacing anything else.
+fn latin1(text: &str) -> Vec<u8> {
+    text.chars().map(|c| u8::try_from(u32::from(c)).unwrap_or(b'?')).collect()
+}
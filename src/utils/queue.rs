@@ -0,0 +1,75 @@
+use std::future::Future;
+use log::{debug, info, warn};
+use tokio::sync::mpsc::{self, error::TrySendError, Receiver, Sender};
+
+/// A side effect deferred out of the request path and performed by the queue worker.
+#[derive(Clone, Debug)]
+pub enum SideEffect {
+    /// Send an email. There is no mail transport configured yet, so it is written to the log.
+    Email { to: String, subject: String, body: String },
+}
+
+enum Message {
+    Perform(SideEffect),
+    Close,
+}
+
+/// Handle for enqueueing side effects onto the bounded in-process work queue. Cloning shares the queue.
+#[derive(Clone)]
+pub struct WorkQueue {
+    sender: Sender<Message>,
+    block_when_full: bool,
+}
+
+/// Create a work queue holding at most `capacity` pending side effects, along with its worker.
+///
+/// The worker performs side effects one at a time in the order they were enqueued. It finishes once the queue is
+/// [closed](WorkQueue::close) and every side effect enqueued before that has been performed, so it can be drained
+/// on shutdown.
+pub fn start(capacity: usize, block_when_full: bool) -> (WorkQueue, impl Future<Output = ()>) {
+    let (sender, receiver) = mpsc::channel(capacity.max(1));
+    (WorkQueue { sender, block_when_full }, work(receiver))
+}
+
+impl WorkQueue {
+    /// Add `effect` to the queue.
+    ///
+    /// When the queue is full, wait for room if `block_when_full` is set and drop `effect` with a warning otherwise.
+    pub async fn enqueue(&self, effect: SideEffect) {
+        if self.block_when_full {
+            if self.sender.send(Message::Perform(effect)).await.is_err() {
+                warn!("Work queue is closed, dropping side effect");
+            }
+            return;
+        }
+
+        match self.sender.try_send(Message::Perform(effect)) {
+            Ok(()) => {}
+            Err(TrySendError::Full(Message::Perform(effect))) => {
+                warn!("Work queue is full, dropping {:?}", effect);
+            }
+            Err(_) => warn!("Work queue is closed, dropping side effect"),
+        }
+    }
+
+    /// Stop accepting side effects once those already enqueued have been performed.
+    pub async fn close(&self) {
+        let _ = self.sender.send(Message::Close).await;
+    }
+}
+
+async fn work(mut receiver: Receiver<Message>) {
+    while let Some(message) = receiver.recv().await {
+        match message {
+            Message::Perform(effect) => perform(effect),
+            Message::Close => receiver.close(),
+        }
+    }
+    debug!("Work queue drained");
+}
+
+fn perform(effect: SideEffect) {
+    match effect {
+        SideEffect::Email { to, subject, body } => info!("Email to {}: {}\n{}", to, subject, body),
+    }
+}
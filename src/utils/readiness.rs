@@ -0,0 +1,79 @@
+use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use actix_web::body::{EitherBody, MessageBody};
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::http::header::RETRY_AFTER;
+use actix_web::middleware::Next;
+use actix_web::{Error, HttpResponse};
+use crate::utils::ErrorResponse;
+
+/// Seconds clients are told to wait before retrying while the service is starting up.
+const RETRY_AFTER_SECS: u64 = 5;
+
+/// Whether startup work such as database migrations has completed. Cloning shares the flag.
+#[derive(Clone, Default)]
+pub struct Readiness(Arc<AtomicBool>);
+
+impl Readiness {
+    pub fn is_ready(&self) -> bool {
+        self.0.load(Ordering::Acquire)
+    }
+
+    pub fn mark_ready(&self) {
+        self.0.store(true, Ordering::Release);
+    }
+}
+
+/// Reject requests with `503 Service Unavailable` and `Retry-After` until `readiness` is marked ready.
+///
+/// Requests to exactly `health_path` are always served so orchestrators can watch startup progress. Wrap it with
+/// `actix_web::middleware::from_fn`, capturing a clone of the shared `Readiness` and the full path of the health check.
+pub async fn require_ready<B: MessageBody>(
+    readiness: Readiness,
+    health_path: Rc<str>,
+    req: ServiceRequest,
+    next: Next<B>,
+) -> Result<ServiceResponse<EitherBody<B>>, Error> {
+    if !readiness.is_ready() && req.path() != &*health_path {
+        let response = HttpResponse::ServiceUnavailable()
+            .insert_header((RETRY_AFTER, RETRY_AFTER_SECS.to_string()))
+            .json(ErrorResponse::ServiceUnavailable(
+                "The service is starting up, retry shortly".to_string(),
+            ));
+        return Ok(req.into_response(response).map_into_right_body());
+    }
+
+    next.call(req).await.map(ServiceResponse::map_into_left_body)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+    use actix_web::{test, web, App, HttpResponse};
+    use actix_web::http::StatusCode;
+    use actix_web::middleware::from_fn;
+    use super::{require_ready, Readiness};
+
+    #[actix_web::test]
+    async fn only_the_health_check_is_served_before_startup_completes() {
+        let readiness = Readiness::default();
+        let health_path: Rc<str> = Rc::from("/v1/health");
+        let ready = readiness.clone();
+        let app = test::init_service(
+            App::new()
+                .wrap(from_fn(move |req, next| require_ready(ready.clone(), health_path.clone(), req, next)))
+                .route("/v1/health", web::get().to(HttpResponse::Ok))
+                .route("/v1/jobs/health", web::get().to(HttpResponse::Ok)),
+        )
+        .await;
+
+        for (path, status) in [("/v1/health", StatusCode::OK), ("/v1/jobs/health", StatusCode::SERVICE_UNAVAILABLE)] {
+            let response = test::call_service(&app, test::TestRequest::get().uri(path).to_request()).await;
+            assert_eq!(response.status(), status, "{}", path);
+        }
+        readiness.mark_ready();
+        let response = test::call_service(&app, test::TestRequest::get().uri("/v1/jobs/health").to_request()).await;
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+}
@@ -0,0 +1,94 @@
+use argon2::password_hash::rand_core::{OsRng, RngCore};
+use chrono::Utc;
+use log::{info, warn};
+use rusqlite::{params, Result};
+use crate::auth::password;
+use crate::db;
+
+/// Email of the administrator account created when seeding.
+const ADMIN_EMAIL: &str = "admin@example.com";
+
+/// Seed an empty database with an administrator and a few sample records.
+///
+/// Nothing is inserted if any user exists. The administrator's password is generated and logged once, so it has to be
+/// noted down from the startup log. Returns whether the database was seeded.
+pub fn seed_if_empty(database_url: &str) -> Result<bool> {
+    // Hashing is slow, so it is done before the transaction to keep the database locked briefly.
+    let password = generate_password();
+    let admin_hash = hash_password(&password)?;
+    let seeker_hash = hash_password(&generate_password())?;
+
+    let mut conn = db::open(database_url)?;
+    let tx = conn.transaction()?;
+    let users: i64 = tx.query_row("SELECT COUNT(*) FROM users", [], |row| row.get(0))?;
+    if users > 0 {
+        info!("Database already has users, skipping seeding");
+        return Ok(false);
+    }
+
+    let now = Utc::now().to_rfc3339();
+    tx.execute(
+        "INSERT INTO users (name, email, password, role, created_at, updated_at) VALUES (?1, ?2, ?3, 'employer', ?4, ?4)",
+        params!["Admin", ADMIN_EMAIL, admin_hash, now],
+    )?;
+    let employer_id = tx.last_insert_rowid();
+    tx.execute(
+        "INSERT INTO users (name, email, password, role, created_at, updated_at, bio)
+         VALUES (?1, ?2, ?3, 'job_seeker', ?4, ?4, ?5)",
+        params!["Sample Seeker", "seeker@example.com", seeker_hash, now, "Backend developer looking for Rust roles."],
+    )?;
+    let mut insert_job = tx.prepare(
+        "INSERT INTO jobs (employer_id, title, description, location, salary, employment_type, posted_at, updated_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?7)",
+    )?;
+    insert_job.execute(params![
+        employer_id,
+        "Rust Backend Engineer",
+        "Build and operate **backend** services in Rust.",
+        "Remote",
+        "$120,000 - $150,000",
+        "full_time",
+        now,
+    ])?;
+    insert_job.execute(params![
+        employer_id,
+        "API Consultant",
+        "Help design a public REST API.",
+        "Berlin, Germany",
+        Option::<String>::None,
+        "contract",
+        now,
+    ])?;
+    drop(insert_job);
+    tx.commit()?;
+
+    warn!(
+        "Seeded the empty database. Administrator login: {} / {} (shown only once, change it after signing in)",
+        ADMIN_EMAIL, password
+    );
+    Ok(true)
+}
+
+fn hash_password(plain: &str) -> Result<String> {
+    password::hash(plain).map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))
+}
+
+/// A random 32 character hex password, drawn from the operating system's secure random number generator.
+fn generate_password() -> String {
+    let mut bytes = [0u8; 16];
+    OsRng.fill_bytes(&mut bytes);
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::generate_password;
+
+    #[test]
+    fn generated_passwords_are_random_hex() {
+        let password = generate_password();
+        assert_eq!(password.len(), 32);
+        assert!(password.chars().all(|c| c.is_ascii_hexdigit()));
+        assert_ne!(password, generate_password());
+    }
+}
@@ -0,0 +1,96 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::sync::Mutex;
+use futures::channel::oneshot;
+use futures::future::{FutureExt, Shared};
+
+/// Coalesces concurrent identical reads so they share a single call.
+///
+/// The first caller for a key runs the read; callers arriving for the same key while it is in flight wait for its
+/// result instead of running their own. Nothing is cached: once the read completes, the next caller runs it again.
+pub struct SingleFlight<K, V> {
+    in_flight: Mutex<HashMap<K, Shared<oneshot::Receiver<V>>>>,
+}
+
+impl<K, V> Default for SingleFlight<K, V> {
+    fn default() -> Self {
+        SingleFlight {
+            in_flight: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+impl<K: Eq + Hash + Clone, V: Clone> SingleFlight<K, V> {
+    /// Return the result of `read` for `key`, sharing the call with any concurrent caller for the same key.
+    pub async fn run<F: FnOnce() -> V>(&self, key: K, read: F) -> V {
+        let leader = {
+            let mut in_flight = self.in_flight.lock().unwrap();
+            match in_flight.get(&key) {
+                Some(waiting) => Err(waiting.clone()),
+                None => {
+                    let (tx, rx) = oneshot::channel();
+                    in_flight.insert(key.clone(), rx.shared());
+                    Ok(tx)
+                }
+            }
+        };
+        let tx = match leader {
+            Ok(tx) => tx,
+            // The sender is only dropped without a value if the leading read panicked; read ourselves then.
+            Err(waiting) => return waiting.await.unwrap_or_else(|_| read()),
+        };
+
+        let guard = InFlight {
+            in_flight: &self.in_flight,
+            key,
+        };
+        let value = read();
+        drop(guard);
+        let _ = tx.send(value.clone());
+        value
+    }
+}
+
+/// Removes a key from the in-flight map when its read finishes, including when the read panics.
+struct InFlight<'a, K: Eq + Hash, V> {
+    in_flight: &'a Mutex<HashMap<K, Shared<oneshot::Receiver<V>>>>,
+    key: K,
+}
+
+impl<K: Eq + Hash, V> Drop for InFlight<'_, K, V> {
+    fn drop(&mut self) {
+        if let Ok(mut in_flight) = self.in_flight.lock() {
+            in_flight.remove(&self.key);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::thread;
+    use std::time::Duration;
+    use futures::executor::block_on;
+    use super::SingleFlight;
+
+    #[test]
+    fn concurrent_callers_share_one_read_and_its_error() {
+        let flight: SingleFlight<i64, Result<Option<i64>, String>> = SingleFlight::default();
+        let reads = AtomicUsize::new(0);
+        let read = || {
+            reads.fetch_add(1, Ordering::SeqCst);
+            thread::sleep(Duration::from_millis(300));
+            Err("database is locked".to_string())
+        };
+
+        let results = thread::scope(|scope| {
+            let leader = scope.spawn(|| block_on(flight.run(1, read)));
+            thread::sleep(Duration::from_millis(100));
+            let follower = scope.spawn(|| block_on(flight.run(1, read)));
+            [leader.join().unwrap(), follower.join().unwrap()]
+        });
+
+        assert_eq!(reads.load(Ordering::SeqCst), 1);
+        assert_eq!(results, [Err("database is locked".to_string()), Err("database is locked".to_string())]);
+    }
+}
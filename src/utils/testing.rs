@@ -0,0 +1,88 @@
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+use actix_web::web::Data;
+use rusqlite::params;
+use crate::db::{self, Pool, ReadPool};
+use crate::utils::init_db;
+
+static NEXT_DATABASE: AtomicUsize = AtomicUsize::new(0);
+
+/// Fully migrated database in a temporary file, removed again when dropped.
+pub struct TestDb {
+    path: PathBuf,
+    pool: Pool,
+}
+
+impl TestDb {
+    pub fn new() -> Self {
+        let path = std::env::temp_dir().join(format!(
+            "rust-backend-starter-test-{}-{}.db",
+            std::process::id(),
+            NEXT_DATABASE.fetch_add(1, Ordering::Relaxed)
+        ));
+        let mut conn = db::open(&path).expect("open test database");
+        init_db::initialize(&mut conn).expect("initialize test database");
+        let pool = db::pool(&path, 4, Duration::from_secs(5));
+        TestDb { path, pool }
+    }
+
+    pub fn pool(&self) -> Data<Pool> {
+        Data::new(self.pool.clone())
+    }
+
+    pub fn read_pool(&self) -> Data<ReadPool> {
+        Data::new(ReadPool::primary(self.pool.clone()))
+    }
+
+    pub fn conn(&self) -> r2d2::PooledConnection<r2d2_sqlite::SqliteConnectionManager> {
+        self.pool.get().expect("connect to test database")
+    }
+
+    /// Insert a user with the given role and stored password, returning its id.
+    pub fn user(&self, email: &str, role: &str, password: &str) -> i64 {
+        let conn = self.conn();
+        conn.execute(
+            "INSERT INTO users (name, email, password, role, created_at, updated_at)
+             VALUES ('Test User', ?1, ?2, ?3, '2024-09-16T12:00:00+00:00', '2024-09-16T12:00:00+00:00')",
+            params![email, password, role],
+        )
+        .expect("insert test user");
+        conn.last_insert_rowid()
+    }
+
+    /// Insert an open full-time job posted by `employer_id`, returning its id.
+    pub fn job(&self, employer_id: i64) -> i64 {
+        let conn = self.conn();
+        conn.execute(
+            "INSERT INTO jobs (employer_id, title, description, location, employment_type, posted_at, updated_at)
+             VALUES (?1, 'Software Engineer', 'Build things.', 'Stockholm', 'full_time',
+                     '2024-09-16T12:00:00+00:00', '2024-09-16T12:00:00+00:00')",
+            params![employer_id],
+        )
+        .expect("insert test job");
+        conn.last_insert_rowid()
+    }
+
+    /// Insert a pending application by `job_seeker_id` for `job_id`, returning its id.
+    pub fn application(&self, job_id: i64, job_seeker_id: i64) -> i64 {
+        let conn = self.conn();
+        conn.execute(
+            "INSERT INTO applications (job_seeker_id, job_id, status, applied_at, reference)
+             VALUES (?1, ?2, 'pending', '2024-09-16T12:00:00+00:00', 'APP-' || ?2 || '-' || ?1)",
+            params![job_seeker_id, job_id],
+        )
+        .expect("insert test application");
+        conn.last_insert_rowid()
+    }
+}
+
+impl Drop for TestDb {
+    fn drop(&mut self) {
+        for suffix in ["", "-wal", "-shm", "-journal"] {
+            let mut path = self.path.clone().into_os_string();
+            path.push(suffix);
+            let _ = std::fs::remove_file(path);
+        }
+    }
+}
@@ -0,0 +1,97 @@
+use actix_web::body::{self, BoxBody, MessageBody};
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::http::header::{HeaderName, HeaderValue, CONTENT_TYPE};
+use actix_web::middleware::Next;
+use actix_web::web::Query;
+use actix_web::Error;
+use chrono::DateTime;
+use chrono_tz::Tz;
+use log::debug;
+use serde::Deserialize;
+use serde_json::Value;
+
+/// Response header naming the zone timestamps were converted to, set whenever `tz` is given.
+pub const TIMEZONE_HEADER: &str = "X-Timezone";
+
+/// Fields holding Unix timestamps in response bodies.
+const TIMESTAMP_FIELDS: &[&str] = &[
+    "created_at",
+    "updated_at",
+    "applied_at",
+    "posted_at",
+    "deadline",
+    "closed_at",
+    "auto_reject_after",
+    "uploaded_at",
+    "oldest_pending_at",
+    "changed_at",
+];
+
+#[derive(Deserialize)]
+struct TimezoneQuery {
+    tz: Option<String>,
+}
+
+/// Convert timestamps in successful JSON responses to RFC 3339 strings in an IANA zone when called with `?tz=`.
+///
+/// Timestamps are Unix timestamps, i.e. UTC, by default. With `tz`, every timestamp field anywhere in the body is
+/// rendered with the zone's offset, e.g. `2024-09-16T11:30:00-04:00` for `tz=America/New_York`. An unknown zone falls
+/// back to UTC. The zone used is echoed in the `X-Timezone` header. When `enabled` is false, `tz` is ignored.
+pub async fn convert_timestamps(
+    enabled: bool,
+    req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<BoxBody>, Error> {
+    let requested = Query::<TimezoneQuery>::from_query(req.query_string())
+        .ok()
+        .and_then(|query| query.tz.clone())
+        .filter(|_| enabled);
+
+    let res = next.call(req).await?.map_into_boxed_body();
+    let is_json = res
+        .headers()
+        .get(CONTENT_TYPE)
+        .is_some_and(|content_type| content_type.as_bytes().starts_with(b"application/json"));
+    let requested = match requested {
+        Some(requested) if res.status().is_success() && is_json => requested,
+        _ => return Ok(res),
+    };
+    let tz = requested.parse::<Tz>().unwrap_or_else(|_| {
+        debug!("Unknown time zone {:?}, falling back to UTC", requested);
+        Tz::UTC
+    });
+
+    let (req, res) = res.into_parts();
+    let (mut res, body) = res.into_parts();
+    let bytes = body::to_bytes(body).await.map_err(Error::from)?;
+    let converted = match serde_json::from_slice::<Value>(&bytes) {
+        Ok(mut value) => {
+            convert(&mut value, tz);
+            serde_json::to_vec(&value)?
+        }
+        Err(_) => bytes.to_vec(),
+    };
+
+    res.headers_mut().insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+    if let (Ok(name), Ok(zone)) = (HeaderName::from_bytes(TIMEZONE_HEADER.as_bytes()), HeaderValue::from_str(tz.name())) {
+        res.headers_mut().insert(name, zone);
+    }
+    Ok(ServiceResponse::new(req, res.set_body(BoxBody::new(converted))))
+}
+
+/// Replace every Unix timestamp in a timestamp field of `value` with an RFC 3339 string in `tz`.
+fn convert(value: &mut Value, tz: Tz) {
+    match value {
+        Value::Object(object) => {
+            for (key, field) in object.iter_mut() {
+                let timestamp = field.as_i64().filter(|_| TIMESTAMP_FIELDS.contains(&key.as_str()));
+                match timestamp.and_then(|timestamp| DateTime::from_timestamp(timestamp, 0)) {
+                    Some(at) => *field = Value::String(at.with_timezone(&tz).to_rfc3339()),
+                    None => convert(field, tz),
+                }
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(|item| convert(item, tz)),
+        _ => {}
+    }
+}